@@ -0,0 +1,14 @@
+-- tables created before the migrations are tracked are kept as they are
+CREATE TABLE IF NOT EXISTS t_post
+(
+    id           BIGINT       NOT NULL PRIMARY KEY,
+    post_id      BIGINT       NOT NULL,
+    title        VARCHAR(255) NOT NULL,
+    metadata     VARCHAR(255) NOT NULL,
+    version      VARCHAR(256) NOT NULL,
+    prev_version VARCHAR(256) NOT NULL,
+    create_time  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    head         BOOLEAN      NOT NULL DEFAULT FALSE,
+    INDEX idx_post_id_head (post_id, head)
+);
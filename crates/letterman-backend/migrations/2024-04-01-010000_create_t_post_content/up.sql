@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS t_post_content
+(
+    id           BIGINT       NOT NULL PRIMARY KEY,
+    post_id      BIGINT       NOT NULL,
+    version      VARCHAR(256) NOT NULL,
+    content      TEXT         NOT NULL,
+    prev_version VARCHAR(256) NOT NULL,
+    create_time  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time  TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    head         BOOLEAN      NOT NULL DEFAULT FALSE,
+    INDEX idx_post_id_head (post_id, head)
+);
@@ -0,0 +1 @@
+DROP TABLE t_github_post_record;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS t_github_post_record
+(
+    id          INT          NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    post_id     BIGINT       NOT NULL,
+    version     INT          NOT NULL,
+    path        VARCHAR(255) NOT NULL,
+    sha         VARCHAR(255) NOT NULL,
+    repository  VARCHAR(255) NOT NULL,
+    url         VARCHAR(255) NOT NULL,
+    create_time TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    INDEX idx_post_id (post_id)
+);
@@ -0,0 +1,16 @@
+CREATE TABLE t_sync_job
+(
+    id            BIGINT       NOT NULL PRIMARY KEY,
+    post_id       BIGINT       NOT NULL,
+    kind          VARCHAR(32)  NOT NULL,
+    request       TEXT         NOT NULL,
+    status        VARCHAR(32)  NOT NULL,
+    attempts      INT          NOT NULL DEFAULT 0,
+    max_attempts  INT          NOT NULL,
+    next_run_time TIMESTAMP    NOT NULL,
+    last_error    TEXT         NULL,
+    create_time   TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time   TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    INDEX idx_status_next_run_time (status, next_run_time),
+    INDEX idx_post_id (post_id)
+);
@@ -0,0 +1,14 @@
+CREATE TABLE t_post_schedule
+(
+    id          BIGINT       NOT NULL PRIMARY KEY,
+    post_id     BIGINT       NOT NULL,
+    version     VARCHAR(256) NOT NULL,
+    targets     TEXT         NOT NULL,
+    publish_at  TIMESTAMP    NOT NULL,
+    status      VARCHAR(32)  NOT NULL,
+    result      TEXT         NULL,
+    create_time TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    INDEX idx_status_publish_at (status, publish_at),
+    INDEX idx_post_id (post_id)
+);
@@ -0,0 +1,13 @@
+CREATE TABLE t_post_divergence
+(
+    id            BIGINT       NOT NULL PRIMARY KEY,
+    post_id       BIGINT       NOT NULL,
+    platform      VARCHAR(32)  NOT NULL,
+    repository    VARCHAR(255) NOT NULL,
+    path          VARCHAR(255) NOT NULL,
+    local_version VARCHAR(256) NOT NULL,
+    remote_commit VARCHAR(64)  NOT NULL,
+    create_time   TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time   TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    INDEX idx_post_id (post_id)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE t_credential
+(
+    id          BIGINT       NOT NULL PRIMARY KEY,
+    name        VARCHAR(128) NOT NULL,
+    owner       VARCHAR(128) NOT NULL,
+    platform    VARCHAR(32)  NOT NULL,
+    secret      TEXT         NOT NULL,
+    create_time TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time TIMESTAMP    NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    UNIQUE INDEX uk_owner_name (owner, name)
+);
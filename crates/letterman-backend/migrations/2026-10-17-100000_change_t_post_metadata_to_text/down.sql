@@ -0,0 +1,2 @@
+-- fails if any metadata is longer than 255 characters
+ALTER TABLE t_post MODIFY COLUMN metadata VARCHAR(255) NOT NULL;
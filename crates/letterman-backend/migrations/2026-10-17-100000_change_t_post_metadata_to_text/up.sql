@@ -0,0 +1,2 @@
+-- the frontmatter with tags, categories and a summary doesn't fit in 255 characters
+ALTER TABLE t_post MODIFY COLUMN metadata TEXT NOT NULL;
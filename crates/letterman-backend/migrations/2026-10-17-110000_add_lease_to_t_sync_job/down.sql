@@ -0,0 +1 @@
+ALTER TABLE t_sync_job DROP COLUMN lease_expire_time;
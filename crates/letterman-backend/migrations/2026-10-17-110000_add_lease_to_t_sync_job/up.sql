@@ -0,0 +1,3 @@
+-- a running job is owned by its worker until the lease expires, the worker renews it
+-- while the job runs. NULL for the jobs which aren't running.
+ALTER TABLE t_sync_job ADD COLUMN lease_expire_time TIMESTAMP NULL;
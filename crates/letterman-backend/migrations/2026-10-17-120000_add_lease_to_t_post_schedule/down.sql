@@ -0,0 +1 @@
+ALTER TABLE t_post_schedule DROP COLUMN lease_expire_time;
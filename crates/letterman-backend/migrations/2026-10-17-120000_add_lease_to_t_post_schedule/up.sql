@@ -0,0 +1,3 @@
+-- a running schedule is owned by its scheduler until the lease expires, the scheduler renews it
+-- while the schedule runs. NULL for the schedules which aren't running.
+ALTER TABLE t_post_schedule ADD COLUMN lease_expire_time TIMESTAMP NULL;
@@ -1,4 +1,5 @@
 pub mod logger;
+pub mod migrations;
 pub mod operations;
 pub mod routes;
 pub mod schema;
@@ -19,10 +20,21 @@ use mongodb::options::ClientOptions;
 use r2d2::Pool;
 use routes::{
     common::ping,
+    credentials::{
+        create_credential, delete_credential, get_credential, get_credential_list,
+        update_credential,
+    },
+    jobs::{get_job, get_job_list},
     posts::{
-        create, delete_post, force_pull, force_push, get_latest_sync_records, get_list, get_post,
-        get_post_history, get_sync_records, revert_post, synchronize, update,
+        bulk_synchronize, create, delete_post, force_pull, force_push, get_latest_sync_records,
+        get_divergences, get_list, get_post, get_post_history, get_sync_records, move_post,
+        publish, refresh_pull_request, revert_post, synchronize, unpublish, update,
+    },
+    render::{get_highlight_css, render_markdown},
+    schedules::{
+        create_schedule, delete_schedule, get_schedule, get_schedule_list, update_schedule,
     },
+    webhooks::github_webhook,
 };
 
 extern crate snowflake;
@@ -60,9 +72,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv::dotenv().ok();
     init_logger();
     let pool = database_pool()?;
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return migrations::command(&pool, args.get(1).map(String::as_str))
+            .map_err(|e| e as Box<dyn std::error::Error>);
+    }
+    if migrations::auto_migrate_enabled() {
+        migrations::run_on_startup(&pool).map_err(|e| e as Box<dyn std::error::Error>)?;
+    }
     let mongodb_databse = mongodb_database().await?;
     let host = env::var("HOST").unwrap_or("127.0.0.1".to_string());
     let port = env::var("PORT").map_or(8080_u16, |v| v.parse::<u16>().unwrap());
+    let workers = env::var("SYNC_WORKERS").map_or(2_usize, |v| v.parse::<usize>().unwrap());
+    operations::jobs::worker::start(workers, pool.clone(), mongodb_databse.clone()).await?;
+    operations::schedules::scheduler::start(pool.clone(), mongodb_databse.clone()).await?;
 
     HttpServer::new(move || {
         let state = State {
@@ -94,17 +117,64 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .route(put().to(update)),
                     )
                     .service(resource("sync/revert").route(put().to(revert_post)))
+                    .service(resource("sync/bulk").route(put().to(bulk_synchronize)))
                     .service(
                         scope("sync/{post_id}")
                             .service(resource("synchronize").route(put().to(synchronize)))
                             .service(resource("push").route(put().to(force_push)))
                             .service(resource("pull").route(put().to(force_pull)))
+                            .service(resource("publish").route(put().to(publish)))
+                            .service(resource("unpublish").route(put().to(unpublish)))
+                            .service(resource("move").route(put().to(move_post)))
+                            .service(resource("pull-request").route(put().to(refresh_pull_request)))
                             .service(resource("records").route(get().to(get_sync_records)))
+                            .service(resource("divergences").route(get().to(get_divergences)))
                             .service(
                                 resource("records/latest").route(get().to(get_latest_sync_records)),
                             ),
                     ),
             )
+            .service(
+                scope("/api/schedule")
+                    .service(resource("/list").route(get().to(get_schedule_list)))
+                    .service(
+                        resource("/{id}")
+                            .route(get().to(get_schedule))
+                            .route(delete().to(delete_schedule)),
+                    )
+                    .service(
+                        resource("")
+                            .route(post().to(create_schedule))
+                            .route(put().to(update_schedule)),
+                    ),
+            )
+            .service(
+                scope("/api/credential")
+                    .service(resource("/list").route(get().to(get_credential_list)))
+                    .service(
+                        resource("/{id}")
+                            .route(get().to(get_credential))
+                            .route(delete().to(delete_credential)),
+                    )
+                    .service(
+                        resource("")
+                            .route(post().to(create_credential))
+                            .route(put().to(update_credential)),
+                    ),
+            )
+            .service(
+                scope("/api/job")
+                    .service(resource("/list").route(get().to(get_job_list)))
+                    .service(resource("/{id}").route(get().to(get_job))),
+            )
+            .service(
+                scope("/api/render")
+                    .service(resource("").route(post().to(render_markdown)))
+                    .service(resource("/highlight.css").route(get().to(get_highlight_css))),
+            )
+            .service(
+                scope("/api/webhook").service(resource("/github").route(post().to(github_webhook))),
+            )
             .service(ping)
     })
     .bind((host, port))?
@@ -0,0 +1,123 @@
+//! migrations of `migrations` directory embedded in the binary, they're applied on startup
+//! if `AUTO_MIGRATE` env is `true`, or by `letterman-backend migrate`.
+
+use std::error::Error;
+
+use diesel::{
+    migration::{MigrationSource, MigrationVersion},
+    mysql::Mysql,
+    r2d2::ConnectionManager,
+    MysqlConnection,
+};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use log::info;
+use r2d2::Pool;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+const USAGE: &str = "usage: letterman-backend migrate <list|apply|revert>
+    list    list the migrations and whether they are applied
+    apply   apply the pending migrations
+    revert  revert the last applied migration";
+
+pub type MigrationError = Box<dyn Error + Send + Sync>;
+
+/// a migration and whether it's applied
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub name: String,
+    pub applied: bool,
+}
+
+pub fn auto_migrate_enabled() -> bool {
+    std::env::var("AUTO_MIGRATE").is_ok_and(|v| v == "true")
+}
+
+/// apply the pending migrations, return their versions
+pub fn apply(conn: &mut MysqlConnection) -> Result<Vec<String>, MigrationError> {
+    let applied = conn.run_pending_migrations(MIGRATIONS)?;
+    Ok(applied.iter().map(MigrationVersion::to_string).collect())
+}
+
+/// revert the last applied migration, `None` if no migration is applied
+pub fn revert(conn: &mut MysqlConnection) -> Result<Option<String>, MigrationError> {
+    if conn.applied_migrations()?.is_empty() {
+        return Ok(None);
+    }
+    let reverted = conn.revert_last_migration(MIGRATIONS)?;
+    Ok(Some(reverted.to_string()))
+}
+
+/// the embedded migrations in order
+pub fn list(conn: &mut MysqlConnection) -> Result<Vec<MigrationStatus>, MigrationError> {
+    let applied = conn.applied_migrations()?;
+    let migrations = MigrationSource::<Mysql>::migrations(&MIGRATIONS)?;
+    Ok(migrations
+        .iter()
+        .map(|migration| MigrationStatus {
+            name: migration.name().to_string(),
+            applied: applied.contains(&migration.name().version()),
+        })
+        .collect())
+}
+
+/// run `letterman-backend migrate <command>`
+pub fn command(
+    pool: &Pool<ConnectionManager<MysqlConnection>>,
+    command: Option<&str>,
+) -> Result<(), MigrationError> {
+    let mut conn = pool.get()?;
+    match command {
+        Some("list") => {
+            for migration in list(&mut conn)? {
+                let mark = if migration.applied { "x" } else { " " };
+                println!("[{}] {}", mark, migration.name);
+            }
+        }
+        Some("apply") => {
+            let applied = apply(&mut conn)?;
+            if applied.is_empty() {
+                println!("No pending migrations");
+            }
+            for version in applied {
+                println!("Applied {}", version);
+            }
+        }
+        Some("revert") => match revert(&mut conn)? {
+            Some(version) => println!("Reverted {}", version),
+            None => println!("No applied migrations"),
+        },
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+/// apply the pending migrations on startup
+pub fn run_on_startup(
+    pool: &Pool<ConnectionManager<MysqlConnection>>,
+) -> Result<(), MigrationError> {
+    let mut conn = pool.get()?;
+    for version in apply(&mut conn)? {
+        info!("applied migration {}", version);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod migrations_test {
+    use super::*;
+
+    #[test]
+    fn embedded_test() {
+        let migrations = MigrationSource::<Mysql>::migrations(&MIGRATIONS).unwrap();
+        let dirs = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations"))
+            .unwrap()
+            .count();
+        assert_eq!(migrations.len(), dirs);
+        let names: Vec<String> = migrations.iter().map(|m| m.name().to_string()).collect();
+        assert_eq!(names[0], "2024-04-01-000000_create_t_post");
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+}
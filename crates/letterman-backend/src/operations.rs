@@ -1,5 +1,12 @@
 pub mod constants;
+pub mod credentials;
+pub mod forge_record;
 pub mod github_record;
+pub mod jobs;
+pub mod local_git_record;
 pub mod pagination;
 pub mod posts;
 pub mod remote;
+pub mod render;
+pub mod schedules;
+pub mod webhooks;
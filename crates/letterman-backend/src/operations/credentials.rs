@@ -0,0 +1,135 @@
+use diesel::{Connection, ExpressionMethods, MysqlConnection, QueryDsl, RunQueryDsl};
+
+use crate::{
+    schema::t_credential,
+    traits::DbAction,
+    types::{
+        credentials::{
+            encrypt_token, CreateCredentialError, CreateCredentialReq, Credential,
+            CredentialPageReq, CredentialVO, DeleteCredentialError, InsertableCredential,
+            QueryCredentialError, UpdateCredentialError, UpdateCredentialReq,
+        },
+        Page, Platform,
+    },
+    utils,
+};
+
+use super::pagination::Paginate;
+
+pub struct CredentialCreator(pub CreateCredentialReq);
+
+impl DbAction for CredentialCreator {
+    type Item = Credential;
+
+    type Error = CreateCredentialError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let credential = InsertableCredential::new(self.0)?;
+        let id = credential.id;
+        diesel::insert_into(t_credential::table)
+            .values(credential)
+            .execute(conn)?;
+        Ok(t_credential::table.find(id).first(conn)?)
+    }
+}
+
+pub struct CredentialQueryer(pub i64);
+
+impl DbAction for CredentialQueryer {
+    type Item = Credential;
+
+    type Error = QueryCredentialError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        Ok(t_credential::table.find(self.0).first(conn)?)
+    }
+}
+
+/// decrypt the token of the credential, it must be a credential of the platform
+pub struct CredentialTokenQueryer(pub i64, pub Platform);
+
+impl DbAction for CredentialTokenQueryer {
+    type Item = String;
+
+    type Error = QueryCredentialError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let credential: Credential = t_credential::table.find(self.0).first(conn)?;
+        if credential.platform != self.1.to_string() {
+            return Err(QueryCredentialError::PlatformMismatch(self.1));
+        }
+        Ok(credential.token()?)
+    }
+}
+
+pub struct CredentialPageQueryer(pub CredentialPageReq);
+
+impl DbAction for CredentialPageQueryer {
+    type Item = Page<CredentialVO>;
+
+    type Error = QueryCredentialError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let mut query = t_credential::table.into_boxed();
+        if let Some(owner) = self.0.owner {
+            query = query.filter(t_credential::owner.eq(owner));
+        }
+        if let Some(platform) = self.0.platform {
+            query = query.filter(t_credential::platform.eq(platform.to_string()));
+        }
+        let (credentials, total) = query
+            .order_by(t_credential::create_time.desc())
+            .paginate(self.0.page)
+            .page_size(self.0.page_size)
+            .load_and_count_pages::<Credential>(conn)?;
+        let credentials = credentials.into_iter().map(CredentialVO::from).collect();
+        Ok(Page::new(total, self.0.page, credentials, self.0.page_size))
+    }
+}
+
+/// rename the credential or replace its token
+pub struct CredentialUpdater(pub UpdateCredentialReq);
+
+impl DbAction for CredentialUpdater {
+    type Item = Credential;
+
+    type Error = UpdateCredentialError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let req = self.0;
+        conn.transaction(|conn| {
+            let credential: Credential =
+                t_credential::table.find(req.id).for_update().first(conn)?;
+            let secret = match req.token {
+                Some(token) => encrypt_token(&token)?,
+                None => credential.secret,
+            };
+            diesel::update(t_credential::table.find(req.id))
+                .set((
+                    t_credential::name.eq(req.name.unwrap_or(credential.name)),
+                    t_credential::owner.eq(req.owner.unwrap_or(credential.owner)),
+                    t_credential::secret.eq(secret),
+                    t_credential::update_time.eq(utils::time_utils::now()),
+                ))
+                .execute(conn)?;
+            Ok(t_credential::table.find(req.id).first(conn)?)
+        })
+    }
+}
+
+/// the targets which still reference the credential fail to synchronize
+pub struct CredentialDeleter(pub i64);
+
+impl DbAction for CredentialDeleter {
+    type Item = ();
+
+    type Error = DeleteCredentialError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let deleted = diesel::delete(t_credential::table.find(self.0)).execute(conn)?;
+        if deleted == 0 {
+            return Err(DeleteCredentialError::NotFound);
+        }
+        Ok(())
+    }
+}
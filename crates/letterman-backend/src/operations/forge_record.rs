@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use mongodb::{bson::doc, Cursor};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    traits::{DocumentConvert, MongoAction},
+    types::{
+        forge_record::{
+            CreateForgeRecordError, ForgeRecord, GiteaRecord, GitlabRecord, InsertableForgeRecord,
+            QueryForgeRecordError,
+        },
+        Platform,
+    },
+    utils,
+};
+
+use super::constants;
+
+pub struct GiteaRecordQueryerByPostId(pub i64);
+
+#[async_trait]
+impl MongoAction for GiteaRecordQueryerByPostId {
+    type Item = Vec<GiteaRecord>;
+
+    type Error = QueryForgeRecordError;
+
+    async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
+        query_records(db, self.0, Platform::Gitea).await
+    }
+}
+
+pub struct GitlabRecordQueryerByPostId(pub i64);
+
+#[async_trait]
+impl MongoAction for GitlabRecordQueryerByPostId {
+    type Item = Vec<GitlabRecord>;
+
+    type Error = QueryForgeRecordError;
+
+    async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
+        query_records(db, self.0, Platform::Gitlab).await
+    }
+}
+
+/// query records of the platform, the latest record comes first
+async fn query_records<T>(
+    db: mongodb::Database,
+    post_id: i64,
+    platform: Platform,
+) -> Result<Vec<T>, QueryForgeRecordError>
+where
+    T: ForgeRecord + DeserializeOwned + serde::Serialize + std::fmt::Debug + Send + Sync,
+{
+    let filter = doc! {"post_id": post_id, "platform": platform};
+    let cursor: Cursor<T> = db
+        .collection(constants::SYNC_RECORDS_COLLECTION)
+        .find(filter, None)
+        .await?;
+    let mut data = utils::mongo_utils::to_vec(cursor).await;
+    data.sort_by(|a, b| b.create_time().cmp(a.create_time()));
+    Ok(data)
+}
+
+pub struct ForgeRecordCreator(pub InsertableForgeRecord);
+
+#[async_trait]
+impl MongoAction for ForgeRecordCreator {
+    type Item = ();
+
+    type Error = CreateForgeRecordError;
+
+    async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
+        let doc = self.0.to_doc();
+        db.collection(constants::SYNC_RECORDS_COLLECTION)
+            .insert_one(doc, None)
+            .await?;
+        Ok(())
+    }
+}
+
+impl From<mongodb::error::Error> for QueryForgeRecordError {
+    fn from(value: mongodb::error::Error) -> Self {
+        QueryForgeRecordError::Database(value)
+    }
+}
+
+impl From<mongodb::error::Error> for CreateForgeRecordError {
+    fn from(value: mongodb::error::Error) -> Self {
+        CreateForgeRecordError::Database(value)
+    }
+}
@@ -1,9 +1,19 @@
 use async_trait::async_trait;
-use mongodb::{bson::doc, Cursor};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, DateTime},
+    options::FindOneOptions,
+    Cursor,
+};
 
 use crate::{
     traits::{DocumentConvert, MongoAction},
-    types::github_record::{GithubRecord, InsertableGithubRecord, QueryGithubRecordError},
+    types::{
+        github_record::{
+            GithubRecord, InsertableGithubRecord, QueryGithubRecordError, UpdateGithubRecordError,
+        },
+        pull_requests::PullRequest,
+        Platform,
+    },
     utils,
 };
 
@@ -18,7 +28,7 @@ impl MongoAction for GithubRecordQueryerByPostId {
     type Error = QueryGithubRecordError;
 
     async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
-        let filter = doc! {"post_id": self.0};
+        let filter = doc! {"post_id": self.0, "platform": Platform::Github};
         let cursor: Cursor<GithubRecord> = db
             .collection(constants::SYNC_RECORDS_COLLECTION)
             .find(filter, None)
@@ -29,6 +39,27 @@ impl MongoAction for GithubRecordQueryerByPostId {
     }
 }
 
+/// the latest record of the file in the repository
+pub struct GithubRecordQueryerByPath(pub String, pub String);
+
+#[async_trait]
+impl MongoAction for GithubRecordQueryerByPath {
+    type Item = Option<GithubRecord>;
+
+    type Error = QueryGithubRecordError;
+
+    async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
+        let filter = doc! {"platform": Platform::Github, "repository": self.0, "path": self.1};
+        let options = FindOneOptions::builder()
+            .sort(doc! {"create_time": -1})
+            .build();
+        Ok(db
+            .collection(constants::SYNC_RECORDS_COLLECTION)
+            .find_one(filter, options)
+            .await?)
+    }
+}
+
 pub struct GithubRecordCreator(pub InsertableGithubRecord);
 
 #[async_trait]
@@ -46,6 +77,31 @@ impl MongoAction for GithubRecordCreator {
     }
 }
 
+/// store the current state of the pull request in the record
+pub struct GithubRecordPullRequestUpdater(pub String, pub PullRequest);
+
+#[async_trait]
+impl MongoAction for GithubRecordPullRequestUpdater {
+    type Item = ();
+
+    type Error = UpdateGithubRecordError;
+
+    async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
+        let id = ObjectId::parse_str(&self.0).map_err(|_| UpdateGithubRecordError::InvalidId)?;
+        let pull_request = mongodb::bson::to_bson(&self.1).unwrap_or(Bson::Null);
+        let now = DateTime::from_millis(utils::time_utils::now().and_utc().timestamp_millis());
+        db.collection::<GithubRecord>(constants::SYNC_RECORDS_COLLECTION)
+            .update_one(
+                doc! {"_id": id},
+                doc! {"$set": {"pull_request": pull_request, "update_time": now}},
+                None,
+            )
+            .await
+            .map_err(UpdateGithubRecordError::Database)?;
+        Ok(())
+    }
+}
+
 impl From<mongodb::error::Error> for CreateGithubRecordError {
     fn from(item: mongodb::error::Error) -> Self {
         CreateGithubRecordError::Database(item)
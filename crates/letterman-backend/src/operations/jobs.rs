@@ -0,0 +1,274 @@
+use chrono::{Duration, NaiveDateTime};
+use diesel::{
+    BoolExpressionMethods, Connection, ExpressionMethods, MysqlConnection, QueryDsl, RunQueryDsl,
+};
+
+use crate::{
+    schema::t_sync_job,
+    traits::DbAction,
+    types::{
+        jobs::{
+            CreateJobError, InsertableSyncJob, JobPageReq, JobStatus, QueryJobError, SyncJob,
+            SyncJobVO, UpdateJobError,
+        },
+        Page,
+    },
+    utils,
+};
+
+use super::pagination::Paginate;
+
+pub mod worker;
+
+/// delay of the first retry, it's doubled on every attempt
+const RETRY_BASE_DELAY_SECONDS: i64 = 10;
+const RETRY_MAX_DELAY_SECONDS: i64 = 60 * 60;
+
+/// a running job is owned by its worker until the lease expires,
+/// the worker renews it while the job runs
+pub const JOB_LEASE_SECONDS: i64 = 60;
+
+/// when the lease of a job claimed or renewed now expires
+pub fn lease_expire_time() -> NaiveDateTime {
+    utils::time_utils::now() + Duration::seconds(JOB_LEASE_SECONDS)
+}
+
+/// delay before retrying a job which has been executed `attempts` times
+pub fn backoff(attempts: i32) -> Duration {
+    let exp = attempts.clamp(1, 16) as u32 - 1;
+    let seconds = RETRY_BASE_DELAY_SECONDS.saturating_mul(2_i64.pow(exp));
+    Duration::seconds(seconds.min(RETRY_MAX_DELAY_SECONDS))
+}
+
+pub struct JobCreator(pub InsertableSyncJob);
+
+impl DbAction for JobCreator {
+    type Item = i64;
+
+    type Error = CreateJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let id = self.0.id;
+        diesel::insert_into(t_sync_job::table)
+            .values(self.0)
+            .execute(conn)?;
+        Ok(id)
+    }
+}
+
+pub struct JobQueryer(pub i64);
+
+impl DbAction for JobQueryer {
+    type Item = SyncJob;
+
+    type Error = QueryJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        Ok(t_sync_job::table.find(self.0).first(conn)?)
+    }
+}
+
+pub struct JobPageQueryer(pub JobPageReq);
+
+impl DbAction for JobPageQueryer {
+    type Item = Page<SyncJobVO>;
+
+    type Error = QueryJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let mut query = t_sync_job::table.into_boxed();
+        if let Some(post_id) = self.0.post_id {
+            query = query.filter(t_sync_job::post_id.eq(post_id));
+        }
+        if let Some(status) = self.0.status {
+            query = query.filter(t_sync_job::status.eq(status.to_string()));
+        }
+        let (jobs, total) = query
+            .order_by(t_sync_job::id.desc())
+            .paginate(self.0.page)
+            .page_size(self.0.page_size)
+            .load_and_count_pages::<SyncJob>(conn)?;
+        let jobs = jobs.into_iter().map(SyncJobVO::from).collect();
+        Ok(Page::new(total, self.0.page, jobs, self.0.page_size))
+    }
+}
+
+/// take the earliest due job and mark it as running,
+/// the row is locked so that every job is claimed by only one worker.
+pub struct DueJobClaimer;
+
+impl DbAction for DueJobClaimer {
+    type Item = Option<SyncJob>;
+
+    type Error = UpdateJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let now = utils::time_utils::now();
+        conn.transaction(|conn| {
+            let job: Option<SyncJob> = t_sync_job::table
+                .filter(
+                    t_sync_job::status
+                        .eq(JobStatus::Pending.to_string())
+                        .and(t_sync_job::next_run_time.le(now)),
+                )
+                .order_by(t_sync_job::next_run_time.asc())
+                .for_update()
+                .skip_locked()
+                .first(conn)
+                .map(Some)
+                .or_else(|e| match e {
+                    diesel::result::Error::NotFound => Ok(None),
+                    e => Err(e),
+                })?;
+            let job = match job {
+                Some(job) => job,
+                None => return Ok(None),
+            };
+            diesel::update(t_sync_job::table.find(job.id))
+                .set((
+                    t_sync_job::status.eq(JobStatus::Running.to_string()),
+                    t_sync_job::attempts.eq(job.attempts + 1),
+                    t_sync_job::lease_expire_time.eq(Some(lease_expire_time())),
+                    t_sync_job::update_time.eq(now),
+                ))
+                .execute(conn)?;
+            Ok(Some(SyncJob {
+                status: JobStatus::Running.to_string(),
+                attempts: job.attempts + 1,
+                update_time: now,
+                lease_expire_time: Some(lease_expire_time()),
+                ..job
+            }))
+        })
+    }
+}
+
+/// mark the job as succeeded
+pub struct JobCompleter(pub i64);
+
+impl DbAction for JobCompleter {
+    type Item = ();
+
+    type Error = UpdateJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::update(t_sync_job::table.find(self.0))
+            .set((
+                t_sync_job::status.eq(JobStatus::Succeeded.to_string()),
+                t_sync_job::last_error.eq(None::<String>),
+                t_sync_job::lease_expire_time.eq(None::<NaiveDateTime>),
+                t_sync_job::update_time.eq(utils::time_utils::now()),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// mark the job as failed with the error
+pub struct JobFailer(pub i64, pub String);
+
+impl DbAction for JobFailer {
+    type Item = ();
+
+    type Error = UpdateJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::update(t_sync_job::table.find(self.0))
+            .set((
+                t_sync_job::status.eq(JobStatus::Failed.to_string()),
+                t_sync_job::last_error.eq(Some(self.1)),
+                t_sync_job::lease_expire_time.eq(None::<NaiveDateTime>),
+                t_sync_job::update_time.eq(utils::time_utils::now()),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// queue the job again, it will be run after the time
+pub struct JobRetryScheduler(pub i64, pub NaiveDateTime, pub String);
+
+impl DbAction for JobRetryScheduler {
+    type Item = ();
+
+    type Error = UpdateJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::update(t_sync_job::table.find(self.0))
+            .set((
+                t_sync_job::status.eq(JobStatus::Pending.to_string()),
+                t_sync_job::next_run_time.eq(self.1),
+                t_sync_job::last_error.eq(Some(self.2)),
+                t_sync_job::lease_expire_time.eq(None::<NaiveDateTime>),
+                t_sync_job::update_time.eq(utils::time_utils::now()),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// extend the lease of the running job, so it isn't queued again by the other workers
+pub struct JobLeaseRenewer(pub i64);
+
+impl DbAction for JobLeaseRenewer {
+    type Item = ();
+
+    type Error = UpdateJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::update(
+            t_sync_job::table.filter(
+                t_sync_job::id
+                    .eq(self.0)
+                    .and(t_sync_job::status.eq(JobStatus::Running.to_string())),
+            ),
+        )
+        .set(t_sync_job::lease_expire_time.eq(Some(lease_expire_time())))
+        .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// queue the running jobs whose lease is expired again, their workers are gone,
+/// e.g. the server is restarted. The jobs run by the live workers are left alone.
+pub struct RunningJobResetter;
+
+impl DbAction for RunningJobResetter {
+    type Item = usize;
+
+    type Error = UpdateJobError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let now = utils::time_utils::now();
+        Ok(diesel::update(
+            t_sync_job::table.filter(
+                t_sync_job::status.eq(JobStatus::Running.to_string()).and(
+                    // the jobs claimed before the leases are recorded have no lease
+                    t_sync_job::lease_expire_time
+                        .is_null()
+                        .or(t_sync_job::lease_expire_time.lt(now)),
+                ),
+            ),
+        )
+        .set((
+            t_sync_job::status.eq(JobStatus::Pending.to_string()),
+            t_sync_job::lease_expire_time.eq(None::<NaiveDateTime>),
+            t_sync_job::update_time.eq(now),
+        ))
+        .execute(conn)?)
+    }
+}
+
+#[cfg(test)]
+mod jobs_test {
+    use super::*;
+
+    #[test]
+    fn backoff_test() {
+        assert_eq!(backoff(1), Duration::seconds(10));
+        assert_eq!(backoff(2), Duration::seconds(20));
+        assert_eq!(backoff(4), Duration::seconds(80));
+        assert_eq!(backoff(20), Duration::seconds(3600));
+        assert_eq!(backoff(0), Duration::seconds(10));
+    }
+}
@@ -0,0 +1,129 @@
+//! workers which execute the queued sync jobs in background.
+
+use std::time::Duration;
+
+use diesel::{r2d2::ConnectionManager, MysqlConnection};
+use log::{error, info};
+use r2d2::Pool;
+
+use crate::{
+    operations::remote::{
+        self, factory::SyncerFactory, forge::JOB_REQUEST_TIMEOUT, types::SyncError,
+    },
+    traits::{DbAction, DbActionError},
+    types::jobs::{JobKind, SyncJob, UpdateJobError},
+    utils,
+};
+
+use super::{
+    backoff, DueJobClaimer, JobCompleter, JobFailer, JobLeaseRenewer, JobRetryScheduler,
+    RunningJobResetter, JOB_LEASE_SECONDS,
+};
+
+/// how long an idle worker waits before looking for due jobs again
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// the lease of a running job is renewed several times before it expires
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(JOB_LEASE_SECONDS as u64 / 3);
+
+/// spawn the workers on the current system.
+/// jobs left running by the dead workers are queued again before that, and periodically.
+pub async fn start(
+    workers: usize,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<(), DbActionError<UpdateJobError>> {
+    reset_expired(pool.clone()).await?;
+    for _ in 0..workers {
+        actix_rt::spawn(work(pool.clone(), mongo_db.clone()));
+    }
+    actix_rt::spawn(async move {
+        loop {
+            actix_rt::time::sleep(Duration::from_secs(JOB_LEASE_SECONDS as u64)).await;
+            if let Err(e) = reset_expired(pool.clone()).await {
+                error!("failed to queue interrupted sync jobs: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn reset_expired(
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+) -> Result<(), DbActionError<UpdateJobError>> {
+    let reset = RunningJobResetter.execute(pool).await?;
+    if reset > 0 {
+        info!("{} interrupted sync jobs are queued again", reset);
+    }
+    Ok(())
+}
+
+/// renew the lease of the job until it's aborted
+async fn heartbeat(id: i64, pool: Pool<ConnectionManager<MysqlConnection>>) {
+    loop {
+        actix_rt::time::sleep(HEARTBEAT_INTERVAL).await;
+        if let Err(e) = JobLeaseRenewer(id).execute(pool.clone()).await {
+            error!("failed to renew the lease of sync job {}: {}", id, e);
+        }
+    }
+}
+
+async fn work(pool: Pool<ConnectionManager<MysqlConnection>>, mongo_db: mongodb::Database) {
+    loop {
+        match DueJobClaimer.execute(pool.clone()).await {
+            Ok(Some(job)) => run(job, pool.clone(), mongo_db.clone()).await,
+            Ok(None) => actix_rt::time::sleep(POLL_INTERVAL).await,
+            Err(e) => {
+                error!("failed to claim sync job: {}", e);
+                actix_rt::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// execute the job and record the result, transient errors are retried with backoff
+async fn run(
+    job: SyncJob,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) {
+    let heartbeat = actix_rt::spawn(heartbeat(job.id, pool.clone()));
+    let result = execute(&job, pool.clone(), mongo_db).await;
+    heartbeat.abort();
+    let updated = match result {
+        Ok(()) => JobCompleter(job.id).execute(pool).await,
+        Err(e) if e.is_retryable() && job.attempts < job.max_attempts => {
+            let next_run_time = utils::time_utils::now() + backoff(job.attempts);
+            JobRetryScheduler(job.id, next_run_time, e.to_string())
+                .execute(pool)
+                .await
+        }
+        Err(e) => JobFailer(job.id, e.to_string()).execute(pool).await,
+    };
+    if let Err(e) = updated {
+        error!("failed to update sync job {}: {}", job.id, e);
+    }
+}
+
+async fn execute(
+    job: &SyncJob,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<(), SyncError> {
+    let kind = job
+        .kind()
+        .ok_or_else(|| SyncError::Other(format!("unknown job kind: {}", job.kind)))?;
+    let req = job
+        .request()
+        .map_err(|e| SyncError::Other(format!("invalid sync request: {}", e)))?;
+    let syncer = SyncerFactory::create_with_timeout(req, pool.clone(), JOB_REQUEST_TIMEOUT).await?;
+    match kind {
+        JobKind::Synchronize => remote::synchronize(syncer, job.post_id, pool, mongo_db)
+            .await
+            .map(|_| ()),
+        JobKind::Push => remote::force_push(syncer, job.post_id, pool, mongo_db).await,
+        JobKind::Pull => remote::force_pull(syncer, job.post_id, pool, mongo_db).await,
+        JobKind::Unpublish => remote::unpublish(syncer, job.post_id, pool, mongo_db).await,
+        JobKind::Move => remote::move_post(syncer, job.post_id, pool, mongo_db).await,
+    }
+}
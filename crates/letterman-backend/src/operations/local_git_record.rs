@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use mongodb::{bson::doc, Cursor};
+
+use crate::{
+    traits::{DocumentConvert, MongoAction},
+    types::{
+        local_git_record::{
+            CreateLocalGitRecordError, InsertableLocalGitRecord, LocalGitRecord,
+            QueryLocalGitRecordError,
+        },
+        Platform,
+    },
+    utils,
+};
+
+use super::constants;
+
+pub struct LocalGitRecordQueryerByPostId(pub i64);
+
+#[async_trait]
+impl MongoAction for LocalGitRecordQueryerByPostId {
+    type Item = Vec<LocalGitRecord>;
+
+    type Error = QueryLocalGitRecordError;
+
+    async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
+        let filter = doc! {"post_id": self.0, "platform": Platform::LocalGit};
+        let cursor: Cursor<LocalGitRecord> = db
+            .collection(constants::SYNC_RECORDS_COLLECTION)
+            .find(filter, None)
+            .await?;
+        let mut data = utils::mongo_utils::to_vec(cursor).await;
+        data.sort_by(|a, b| b.create_time().cmp(a.create_time()));
+        Ok(data)
+    }
+}
+
+pub struct LocalGitRecordCreator(pub InsertableLocalGitRecord);
+
+#[async_trait]
+impl MongoAction for LocalGitRecordCreator {
+    type Item = ();
+
+    type Error = CreateLocalGitRecordError;
+
+    async fn mongo_action(self, db: mongodb::Database) -> Result<Self::Item, Self::Error> {
+        let doc = self.0.to_doc();
+        db.collection(constants::SYNC_RECORDS_COLLECTION)
+            .insert_one(doc, None)
+            .await?;
+        Ok(())
+    }
+}
+
+impl From<mongodb::error::Error> for QueryLocalGitRecordError {
+    fn from(value: mongodb::error::Error) -> Self {
+        QueryLocalGitRecordError::Database(value)
+    }
+}
+
+impl From<mongodb::error::Error> for CreateLocalGitRecordError {
+    fn from(value: mongodb::error::Error) -> Self {
+        CreateLocalGitRecordError::Database(value)
+    }
+}
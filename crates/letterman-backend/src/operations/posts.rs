@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use chrono::NaiveDateTime;
 use diesel::{
     BoolExpressionMethods, Connection, ExpressionMethods, MysqlConnection, QueryDsl, RunQueryDsl,
 };
@@ -313,6 +314,27 @@ impl DbAction for LatestPostQueryerByPostId {
     }
 }
 
+/// head versions of all posts, optionally created in [since, until)
+pub struct HeadPostQueryer(pub Option<NaiveDateTime>, pub Option<NaiveDateTime>);
+
+impl DbAction for HeadPostQueryer {
+    type Item = Vec<BasePost>;
+
+    type Error = QueryPostError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        use schema::t_post::dsl::*;
+        let mut query = t_post.filter(head.eq(true)).into_boxed();
+        if let Some(since) = self.0 {
+            query = query.filter(create_time.ge(since));
+        }
+        if let Some(until) = self.1 {
+            query = query.filter(create_time.lt(until));
+        }
+        Ok(query.order_by(id.desc()).load(conn)?)
+    }
+}
+
 pub struct LatestPostQueryerByPostIds(pub Vec<i64>);
 
 impl DbAction for LatestPostQueryerByPostIds {
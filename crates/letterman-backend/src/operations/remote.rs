@@ -2,24 +2,54 @@ use async_trait::async_trait;
 use diesel::{r2d2::ConnectionManager, MysqlConnection};
 use r2d2::Pool;
 
+use futures::{future::join_all, stream, StreamExt};
+
 use crate::{
     traits::{DbAction, MongoAction},
-    types::posts::Post,
+    types::{
+        posts::{BulkSyncReq, Post, SyncReq, DEFAULT_BULK_SYNC_CONCURRENCY},
+        pull_requests::PullRequest,
+        transforms::TransformRule,
+    },
+    utils,
 };
 
-use self::types::SyncError;
+use self::{
+    factory::SyncerFactory,
+    merge::Merged,
+    transform::Pipeline,
+    types::{
+        BulkSyncReport, BulkSyncResult, ConflictReport, MergeConflict, SyncError, SyncPlan,
+        SyncPreview, SyncStatus, SyncTarget, TargetResult,
+    },
+};
 
-use super::{
-    github_record::GithubRecordQueryerByPostId,
-    posts::{LatestPostQueryerByPostId, PostDirectCreator},
+use super::posts::{
+    HeadPostQueryer, LatestPostQueryerByPostId, PostDirectCreator, PostLatestSyncRecordQueryer,
+    PostQueryerByPostIdAndVersion,
 };
 
+mod assets;
+pub mod commit_message;
 pub mod factory;
+pub mod forge;
+pub mod frontmatter;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
+pub mod local_git;
+mod merge;
+#[cfg(test)]
+mod mock_github;
+mod transform;
 pub mod types;
 
 #[async_trait]
 pub trait SyncAction {
+    /// render the file of the post as it's published to outer platform,
+    /// the content is transformed by the rules of the target
+    fn render(&self, post: &Post) -> Result<String, SyncError>;
+
     /// push post to create a new article in outer platform
     async fn push_create(
         &mut self,
@@ -41,39 +71,329 @@ pub trait SyncAction {
         mongo_db: mongodb::Database,
     ) -> Result<Option<Post>, SyncError>;
 
-    /// check if the article is changed
-    /// return (is_latest, is_older_version, never_synced, local_is_older_version)
+    /// fetch latest article in outer platform without recording it
+    async fn fetch(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<Post>, SyncError>;
+
+    /// version of the post synchronized to outer platform most recently
+    async fn synced_version(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<String>, SyncError>;
+
+    /// where the post is published and the content published currently
+    async fn target(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<SyncTarget, SyncError>;
+
+    /// check if the post or the article is changed since the last synchronization,
+    /// by comparing the post version and the remote content hash with the latest record
     async fn check_changed(
         &mut self,
         post: &Post,
         mongo_db: mongodb::Database,
-    ) -> Result<(bool, bool, bool), SyncError>;
+    ) -> Result<SyncStatus, SyncError>;
+
+    /// publish the post at the path of the syncer and remove the article at the old path,
+    /// the article stays in the same repository
+    async fn push_move(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError>;
+
+    /// delete the article from outer platform and record a tombstone,
+    /// the post is treated as never synced to the platform after that
+    async fn unpublish(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError>;
 }
 
 /// synchronize post to the outer platform
 /// this function will just push post if there is any change in the article stored in the database.
-/// It will not pull article from outer platform although there may be some changes.
+/// It will not pull article from outer platform if only the article is changed.
 /// if you need to pull article from outer platform, use `pull` to force that.
+/// if both sides are changed, they are merged with the last synchronized version as ancestor.
 pub(crate) async fn synchronize(
+    syncer: Box<dyn SyncAction>,
+    post_id: i64,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<SyncPlan, SyncError> {
+    let post = LatestPostQueryerByPostId(post_id)
+        .execute(pool.clone())
+        .await?;
+    synchronize_post(syncer, post, pool, mongo_db).await
+}
+
+/// synchronize the given version of the post, which may not be the latest one
+pub(crate) async fn synchronize_post(
     mut syncer: Box<dyn SyncAction>,
+    post: Post,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<SyncPlan, SyncError> {
+    let status = syncer.check_changed(&post, mongo_db.clone()).await?;
+    let plan = synchronize_plan(status);
+    match plan {
+        SyncPlan::Create => syncer.push_create(&post, mongo_db.clone()).await?,
+        SyncPlan::Update => syncer.push_update(&post, mongo_db.clone()).await?,
+        SyncPlan::Merge => merge_remote(syncer, post, pool, mongo_db).await?,
+        SyncPlan::Skip | SyncPlan::Pull => {}
+    }
+    Ok(plan)
+}
+
+/// synchronize post to several platforms concurrently,
+/// a failed target doesn't stop the others.
+pub(crate) async fn publish(
+    targets: Vec<SyncReq>,
     post_id: i64,
     pool: Pool<ConnectionManager<MysqlConnection>>,
     mongo_db: mongodb::Database,
-) -> Result<(), SyncError> {
+) -> Result<Vec<TargetResult>, SyncError> {
     let post = LatestPostQueryerByPostId(post_id)
         .execute(pool.clone())
         .await?;
-    let (is_latest, is_older_version, never_synced) =
-        syncer.check_changed(&post, mongo_db.clone()).await?;
-    if never_synced {
-        syncer.push_create(&post, mongo_db.clone()).await
-    } else if is_latest {
-        Ok(())
-    } else if is_older_version {
-        syncer.push_update(&post, mongo_db.clone()).await
-    } else {
-        Err(SyncError::Ambiguous)
+    Ok(publish_post(targets, post, pool, mongo_db).await)
+}
+
+/// synchronize the post to every target concurrently
+pub(crate) async fn publish_post(
+    targets: Vec<SyncReq>,
+    post: Post,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Vec<TargetResult> {
+    let tasks = targets.into_iter().map(|target| {
+        let (post, pool, mongo_db) = (post.clone(), pool.clone(), mongo_db.clone());
+        async move {
+            let result = match SyncerFactory::create(target.clone(), pool.clone()).await {
+                Ok(syncer) => synchronize_post(syncer, post, pool, mongo_db).await,
+                Err(e) => Err(e),
+            };
+            TargetResult {
+                target,
+                outcome: result.into(),
+            }
+        }
+    });
+    join_all(tasks).await
+}
+
+/// synchronize every head post whose version differs from its latest sync record.
+/// only the records matching the platform and repository of the request are considered.
+pub(crate) async fn bulk_synchronize(
+    req: BulkSyncReq,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<BulkSyncReport, SyncError> {
+    let posts = HeadPostQueryer(req.since, req.until)
+        .execute(pool.clone())
+        .await?;
+    let mut targets = vec![];
+    for post in posts {
+        let records = PostLatestSyncRecordQueryer(post.post_id)
+            .execute(mongo_db.clone())
+            .await?;
+        for record in records {
+            if req
+                .platform
+                .as_ref()
+                .is_some_and(|p| *p != record.platform())
+                || req
+                    .repository
+                    .as_deref()
+                    .is_some_and(|r| r != record.repository())
+                || record.version() == post.version
+                || record.deleted()
+            {
+                continue;
+            }
+            // the target fails if it can't be rebuilt, instead of syncing with the defaults
+            let target = SyncReq::from_record(&record).map_err(|e| (SyncReq::location(&record), e));
+            targets.push((post.post_id, target));
+        }
+    }
+
+    let concurrency = req.concurrency.unwrap_or(DEFAULT_BULK_SYNC_CONCURRENCY);
+    let results = stream::iter(targets)
+        .map(|(post_id, target)| {
+            let (pool, mongo_db) = (pool.clone(), mongo_db.clone());
+            async move {
+                let (target, result) = match target {
+                    Ok(target) => {
+                        let result = match SyncerFactory::create(target.clone(), pool.clone()).await
+                        {
+                            Ok(syncer) => synchronize(syncer, post_id, pool, mongo_db).await,
+                            Err(e) => Err(e),
+                        };
+                        (target, result)
+                    }
+                    Err((target, e)) => (target, Err(e)),
+                };
+                BulkSyncResult {
+                    post_id,
+                    result: TargetResult {
+                        target,
+                        outcome: result.into(),
+                    },
+                }
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    Ok(BulkSyncReport::new(results))
+}
+
+/// show what `synchronize` will do without writing anything
+pub(crate) async fn preview_synchronize(
+    mut syncer: Box<dyn SyncAction>,
+    post_id: i64,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<SyncPreview, SyncError> {
+    let post = LatestPostQueryerByPostId(post_id)
+        .execute(pool.clone())
+        .await?;
+    let status = syncer.check_changed(&post, mongo_db.clone()).await?;
+    preview(
+        syncer,
+        post,
+        status,
+        synchronize_plan(status),
+        pool,
+        mongo_db,
+    )
+    .await
+}
+
+fn synchronize_plan(status: SyncStatus) -> SyncPlan {
+    match status {
+        SyncStatus::NeverSynced => SyncPlan::Create,
+        SyncStatus::UpToDate | SyncStatus::RemoteAhead => SyncPlan::Skip,
+        SyncStatus::LocalAhead => SyncPlan::Update,
+        SyncStatus::Diverged => SyncPlan::Merge,
+    }
+}
+
+/// three-way merge local post and remote article, then store and push the merged version.
+/// conflicts are reported instead of being resolved.
+async fn merge_remote(
+    mut syncer: Box<dyn SyncAction>,
+    post: Post,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<(), SyncError> {
+    let plan = plan_merge(syncer.as_mut(), &post, pool.clone(), mongo_db.clone()).await?;
+    let merged = plan.merged.map_err(|conflicts| {
+        SyncError::Conflict(ConflictReport {
+            post_id: post.post_id(),
+            base_version: plan.base_version.clone(),
+            local_version: post.version().to_string(),
+            remote_version: plan.remote.version().to_string(),
+            conflicts,
+        })
+    })?;
+
+    if merged.is_same(&post) {
+        if merged.is_same(&plan.remote) {
+            return Ok(());
+        }
+        // only local is changed
+        return syncer.push_update(&post, mongo_db.clone()).await;
+    }
+    if merged.is_same(&plan.remote) {
+        // only remote is changed, record the remote article as the new version
+        if let Some(remote) = syncer.pull(&post, mongo_db.clone()).await? {
+            PostDirectCreator(remote).execute(pool.clone()).await?;
+        }
+        return Ok(());
+    }
+    let merged = merged_post(&post, merged);
+    syncer.push_update(&merged, mongo_db.clone()).await?;
+    PostDirectCreator(merged).execute(pool.clone()).await?;
+    Ok(())
+}
+
+struct MergePlan {
+    base_version: String,
+    remote: Post,
+    merged: Result<Merged, Vec<MergeConflict>>,
+}
+
+/// fetch the ancestor and the remote article, then merge them with the post
+async fn plan_merge(
+    syncer: &mut dyn SyncAction,
+    post: &Post,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<MergePlan, SyncError> {
+    let base_version = match syncer.synced_version(post, mongo_db.clone()).await? {
+        Some(version) => version,
+        None => return Err(SyncError::Ambiguous),
+    };
+    let base = match PostQueryerByPostIdAndVersion(post.post_id(), base_version.clone())
+        .execute(pool.clone())
+        .await
+    {
+        Ok(base) => base,
+        // the ancestor is never stored locally, e.g. it's deleted
+        Err(_) => return Err(SyncError::Ambiguous),
+    };
+    let remote = match syncer.fetch(post, mongo_db.clone()).await? {
+        Some(remote) => remote,
+        None => return Err(SyncError::NotFound),
+    };
+    let merged = merge::merge(&base, post, &remote);
+    Ok(MergePlan {
+        base_version,
+        remote,
+        merged,
+    })
+}
+
+/// the merged version, whose previous version is the post
+fn merged_post(post: &Post, merged: Merged) -> Post {
+    let version = utils::sha_utils::sha_post2(&merged.title, &merged.metadata, &merged.content);
+    Post::new(
+        utils::snowflake::next_id(),
+        post.post_id(),
+        merged.title,
+        merged.metadata,
+        merged.content,
+        version,
+        post.version().to_string(),
+        utils::time_utils::now(),
+        utils::time_utils::now(),
+    )
+}
+
+/// import the remote changes reported by the platform, e.g. by a webhook.
+/// The post is not changed if it has unsynchronized changes too, which is told by the status.
+pub(crate) async fn import_remote_changes(
+    mut syncer: Box<dyn SyncAction>,
+    post: &Post,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<SyncStatus, SyncError> {
+    let status = syncer.check_changed(post, mongo_db.clone()).await?;
+    if status == SyncStatus::RemoteAhead {
+        let remote_post = syncer.pull(post, mongo_db).await?.ok_or(SyncError::Other(
+            "failed to pull article from remote".to_string(),
+        ))?;
+        PostDirectCreator(remote_post).execute(pool).await?;
     }
+    Ok(status)
 }
 
 /// pull article from outer platform as the latest version
@@ -97,6 +417,80 @@ pub(crate) async fn force_pull(
     }
 }
 
+/// move the article to the path of the syncer, the latest version is published there.
+/// The remote changes must be synchronized before that, otherwise they are lost.
+pub(crate) async fn move_post(
+    mut syncer: Box<dyn SyncAction>,
+    post_id: i64,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<(), SyncError> {
+    let post = LatestPostQueryerByPostId(post_id)
+        .execute(pool.clone())
+        .await?;
+    let status = syncer.check_changed(&post, mongo_db.clone()).await?;
+    check_movable(status)?;
+    syncer.push_move(&post, mongo_db).await
+}
+
+fn check_movable(status: SyncStatus) -> Result<(), SyncError> {
+    match status {
+        SyncStatus::UpToDate | SyncStatus::LocalAhead => Ok(()),
+        SyncStatus::NeverSynced => Err(SyncError::UserError(
+            "the post is not published".to_string(),
+        )),
+        SyncStatus::RemoteAhead | SyncStatus::Diverged => Err(SyncError::UserError(
+            "the article is changed remotely, synchronize it before moving".to_string(),
+        )),
+    }
+}
+
+/// take the article down from outer platform
+pub(crate) async fn unpublish(
+    mut syncer: Box<dyn SyncAction>,
+    post_id: i64,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<(), SyncError> {
+    let post = LatestPostQueryerByPostId(post_id)
+        .execute(pool.clone())
+        .await?;
+    syncer.unpublish(&post, mongo_db).await
+}
+
+/// refresh the state of the pull request which publishes the post
+pub(crate) async fn refresh_pull_request(
+    req: SyncReq,
+    post_id: i64,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<PullRequest, SyncError> {
+    let token = SyncerFactory::token(&req, pool).await?;
+    match req {
+        SyncReq::Github(req) => {
+            let auth = SyncerFactory::github_auth(&req, token)?;
+            github::refresh_pull_request(req.api_url(), auth, post_id, mongo_db).await
+        }
+        _ => Err(SyncError::UserError(
+            "pull requests are only supported on github".to_string(),
+        )),
+    }
+}
+
+/// show what `force_pull` will do without writing anything
+pub(crate) async fn preview_force_pull(
+    mut syncer: Box<dyn SyncAction>,
+    post_id: i64,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<SyncPreview, SyncError> {
+    let post = LatestPostQueryerByPostId(post_id)
+        .execute(pool.clone())
+        .await?;
+    let status = syncer.check_changed(&post, mongo_db.clone()).await?;
+    preview(syncer, post, status, SyncPlan::Pull, pool, mongo_db).await
+}
+
 /// push local newest article to outer platform
 pub(crate) async fn force_push(
     mut syncer: Box<dyn SyncAction>,
@@ -107,12 +501,203 @@ pub(crate) async fn force_push(
     let post = LatestPostQueryerByPostId(post_id)
         .execute(pool.clone())
         .await?;
-    let records = GithubRecordQueryerByPostId(post_id)
-        .execute(mongo_db.clone())
+    let status = syncer.check_changed(&post, mongo_db.clone()).await?;
+    match force_push_plan(status) {
+        SyncPlan::Create => syncer.push_create(&post, mongo_db.clone()).await,
+        _ => syncer.push_update(&post, mongo_db.clone()).await,
+    }
+}
+
+/// show what `force_push` will do without writing anything
+pub(crate) async fn preview_force_push(
+    mut syncer: Box<dyn SyncAction>,
+    post_id: i64,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<SyncPreview, SyncError> {
+    let post = LatestPostQueryerByPostId(post_id)
+        .execute(pool.clone())
         .await?;
-    if records.is_empty() {
-        syncer.push_create(&post, mongo_db.clone()).await
-    } else {
-        syncer.push_update(&post, mongo_db.clone()).await
+    let status = syncer.check_changed(&post, mongo_db.clone()).await?;
+    preview(
+        syncer,
+        post,
+        status,
+        force_push_plan(status),
+        pool,
+        mongo_db,
+    )
+    .await
+}
+
+fn force_push_plan(status: SyncStatus) -> SyncPlan {
+    match status {
+        SyncStatus::NeverSynced => SyncPlan::Create,
+        _ => SyncPlan::Update,
+    }
+}
+
+/// the post as it's pushed to a target with the transforms
+pub(crate) fn transform_post(post: &Post, rules: &[TransformRule]) -> Result<Post, SyncError> {
+    Pipeline::new(rules)?.push(post)
+}
+
+/// the content as it's pushed to a target with the transforms
+pub(crate) fn transform_content(
+    content: String,
+    rules: &[TransformRule],
+) -> Result<String, SyncError> {
+    Pipeline::new(rules)?.push_content(content)
+}
+
+/// render the file to be written by the plan and diff it with the content to be overwritten
+async fn preview(
+    mut syncer: Box<dyn SyncAction>,
+    post: Post,
+    status: SyncStatus,
+    plan: SyncPlan,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<SyncPreview, SyncError> {
+    let target = syncer.target(&post, mongo_db.clone()).await?;
+    let mut conflicts = vec![];
+    let (old, new) = match plan {
+        SyncPlan::Skip => (target.content.clone(), target.content.clone()),
+        SyncPlan::Create | SyncPlan::Update => {
+            (target.content.clone(), Some(syncer.render(&post)?))
+        }
+        SyncPlan::Merge => {
+            match plan_merge(syncer.as_mut(), &post, pool, mongo_db)
+                .await?
+                .merged
+            {
+                Ok(merged) => (
+                    target.content.clone(),
+                    Some(syncer.render(&merged_post(&post, merged))?),
+                ),
+                Err(merge_conflicts) => {
+                    conflicts = merge_conflicts;
+                    (target.content.clone(), None)
+                }
+            }
+        }
+        SyncPlan::Pull => {
+            if target.content.is_none() {
+                return Err(SyncError::Other(
+                    "failed to pull article from remote".to_string(),
+                ));
+            }
+            (Some(syncer.render(&post)?), target.content.clone())
+        }
+    };
+    let diff = match &new {
+        Some(new) => unified_diff(
+            target.path.as_deref().unwrap_or_default(),
+            old.as_deref().unwrap_or_default(),
+            new,
+        ),
+        None => String::new(),
+    };
+    Ok(SyncPreview {
+        plan,
+        status,
+        repository: target.repository,
+        path: target.path,
+        content: new,
+        diff,
+        conflicts,
+    })
+}
+
+/// unified diff between two versions of the file, it's empty if nothing is changed
+fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    diffy::DiffOptions::new()
+        .set_original_filename(format!("a/{}", path))
+        .set_modified_filename(format!("b/{}", path))
+        .create_patch(old, new)
+        .to_string()
+}
+
+#[cfg(test)]
+mod remote_test {
+    use super::{types::TargetOutcome, *};
+
+    #[test]
+    fn plan_test() {
+        assert_eq!(synchronize_plan(SyncStatus::NeverSynced), SyncPlan::Create);
+        assert_eq!(synchronize_plan(SyncStatus::RemoteAhead), SyncPlan::Skip);
+        assert_eq!(synchronize_plan(SyncStatus::LocalAhead), SyncPlan::Update);
+        assert_eq!(synchronize_plan(SyncStatus::Diverged), SyncPlan::Merge);
+        assert_eq!(force_push_plan(SyncStatus::Diverged), SyncPlan::Update);
+    }
+
+    #[test]
+    fn check_movable_test() {
+        assert!(check_movable(SyncStatus::UpToDate).is_ok());
+        assert!(check_movable(SyncStatus::LocalAhead).is_ok());
+        assert!(check_movable(SyncStatus::NeverSynced).is_err());
+        assert!(check_movable(SyncStatus::RemoteAhead).is_err());
+        assert!(check_movable(SyncStatus::Diverged).is_err());
+    }
+
+    #[test]
+    fn target_outcome_test() {
+        let outcome: TargetOutcome = Ok(SyncPlan::Skip).into();
+        assert!(matches!(outcome, TargetOutcome::Skipped));
+        let outcome: TargetOutcome = Ok(SyncPlan::Update).into();
+        assert!(matches!(
+            outcome,
+            TargetOutcome::Synced {
+                plan: SyncPlan::Update
+            }
+        ));
+        let outcome: TargetOutcome = Err(SyncError::NetworkError("timeout".to_string())).into();
+        assert!(matches!(
+            outcome,
+            TargetOutcome::Failed {
+                retryable: true,
+                ..
+            }
+        ));
+        let outcome: TargetOutcome = Err(SyncError::UserError("no path".to_string())).into();
+        assert!(matches!(
+            outcome,
+            TargetOutcome::Failed {
+                retryable: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn bulk_sync_report_test() {
+        let result = |outcome: TargetOutcome| BulkSyncResult {
+            post_id: 1,
+            result: TargetResult {
+                target: SyncReq::LocalGit(Default::default()),
+                outcome,
+            },
+        };
+        let report = BulkSyncReport::new(vec![
+            result(Ok(SyncPlan::Update).into()),
+            result(Ok(SyncPlan::Skip).into()),
+            result(Err(SyncError::RemoteServer).into()),
+            result(Ok(SyncPlan::Create).into()),
+        ]);
+        assert_eq!(report.total, 4);
+        assert_eq!(report.synced, 2);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.failed, 1);
+    }
+
+    #[test]
+    fn unified_diff_test() {
+        assert_eq!(unified_diff("a.md", "same\n", "same\n"), "");
+        let diff = unified_diff("a.md", "hello\nworld\n", "hello\nrust\n");
+        assert!(diff.starts_with("--- a/a.md\n+++ b/a.md\n"));
+        assert!(diff.contains("-world\n+rust\n"));
     }
 }
@@ -0,0 +1,171 @@
+//! images referenced by an article, which are published together with it.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path},
+};
+
+use markdown::{mdast::Node, Constructs};
+use percent_encoding::percent_decode_str;
+
+use super::types::SyncError;
+
+/// a file published next to the article
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Asset {
+    /// path relative to the repository root
+    pub(super) path: String,
+    pub(super) content: Vec<u8>,
+}
+
+/// read the local images referenced by the article.
+/// The assets directory mirrors the repository, an image linked by the article is read
+/// from the same path under it as where it's published.
+pub(super) fn collect(
+    article_path: &str,
+    content: &str,
+    assets_dir: Option<&Path>,
+) -> Result<Vec<Asset>, SyncError> {
+    let mut assets = vec![];
+    for link in image_links(content)? {
+        let path = resolve(article_path, &link)?;
+        if assets.iter().any(|asset: &Asset| asset.path == path) {
+            continue;
+        }
+        let assets_dir = assets_dir.ok_or(SyncError::UserError(
+            "Please set LETTERMAN_ASSETS_DIR env if you want to publish images".to_string(),
+        ))?;
+        let content = fs::read(assets_dir.join(&path))
+            .map_err(|_| SyncError::UserError(format!("image {} is not found", path)))?;
+        assets.push(Asset { path, content });
+    }
+    Ok(assets)
+}
+
+/// destinations of the images which are stored locally, in order of appearance
+fn image_links(content: &str) -> Result<Vec<String>, markdown::message::Message> {
+    let constructs = Constructs {
+        frontmatter: true,
+        ..Constructs::default()
+    };
+    let ast = markdown::to_mdast(
+        content,
+        &markdown::ParseOptions {
+            constructs,
+            ..markdown::ParseOptions::default()
+        },
+    )?;
+    let mut definitions = HashMap::new();
+    let mut links = vec![];
+    visit(&ast, &mut definitions, &mut links);
+    Ok(links
+        .into_iter()
+        .filter_map(|link| match link {
+            ImageLink::Url(url) => Some(url),
+            ImageLink::Reference(id) => definitions.get(&id).cloned(),
+        })
+        .filter(|url| is_local(url))
+        .collect())
+}
+
+enum ImageLink {
+    Url(String),
+    /// identifier of the definition, e.g. `![logo][id]`
+    Reference(String),
+}
+
+fn visit(node: &Node, definitions: &mut HashMap<String, String>, links: &mut Vec<ImageLink>) {
+    match node {
+        Node::Image(image) => links.push(ImageLink::Url(image.url.clone())),
+        Node::ImageReference(image) => links.push(ImageLink::Reference(image.identifier.clone())),
+        Node::Definition(definition) => {
+            definitions.insert(definition.identifier.clone(), definition.url.clone());
+        }
+        _ => {}
+    }
+    if let Some(children) = node.children() {
+        for child in children {
+            visit(child, definitions, links);
+        }
+    }
+}
+
+/// relative links without a scheme, e.g. `images/a.png` rather than `https://...` or `/a.png`
+fn is_local(url: &str) -> bool {
+    let scheme = url
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.contains('/'));
+    !url.is_empty() && !scheme && !url.starts_with('/') && !url.starts_with('#')
+}
+
+/// path of the image in the repository, relative to the directory of the article
+fn resolve(article_path: &str, link: &str) -> Result<String, SyncError> {
+    let link = link.split(['?', '#']).next().unwrap_or(link);
+    let link = percent_decode_str(link).decode_utf8_lossy();
+    let dir = Path::new(article_path).parent().unwrap_or(Path::new(""));
+    let mut segments: Vec<String> = vec![];
+    for component in dir.join(link.as_ref()).components() {
+        match component {
+            Component::Normal(segment) => segments.push(segment.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            Component::ParentDir if segments.pop().is_some() => {}
+            _ => {
+                return Err(SyncError::UserError(format!(
+                    "image {} is outside the repository",
+                    link
+                )))
+            }
+        }
+    }
+    Ok(segments.join("/"))
+}
+
+#[cfg(test)]
+mod assets_test {
+    use super::*;
+
+    #[test]
+    fn image_links_test() {
+        let content = "---\ntitle: a\n---\n![a](images/a.png) ![b](https://example.com/b.png)\n\
+            ![c][logo] ![d](/d.png) ![a again](images/a.png)\n\n[logo]: ../logo.png \"logo\"\n";
+        assert_eq!(
+            image_links(content).unwrap(),
+            vec!["images/a.png", "../logo.png", "images/a.png"]
+        );
+    }
+
+    #[test]
+    fn resolve_test() {
+        let article = "source/_posts/hello.md";
+        assert_eq!(
+            resolve(article, "images/a%20b.png?raw=true").unwrap(),
+            "source/_posts/images/a b.png"
+        );
+        assert_eq!(resolve(article, "./../../logo.png").unwrap(), "logo.png");
+        assert_eq!(resolve("hello.md", "a.png").unwrap(), "a.png");
+        assert!(resolve(article, "../../../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn collect_test() {
+        let dir = std::env::temp_dir().join(format!(
+            "letterman-assets-{}",
+            crate::utils::snowflake::next_id()
+        ));
+        fs::create_dir_all(dir.join("posts/images")).unwrap();
+        fs::write(dir.join("posts/images/a.png"), b"png").unwrap();
+        let content = "![a](images/a.png)\n![a](./images/a.png)";
+        assert_eq!(
+            collect("posts/hello.md", content, Some(&dir)).unwrap(),
+            vec![Asset {
+                path: "posts/images/a.png".to_string(),
+                content: b"png".to_vec(),
+            }]
+        );
+        assert!(collect("posts/hello.md", content, None).is_err());
+        assert!(collect("hello.md", content, Some(&dir)).is_err());
+        assert!(collect("hello.md", "no images", None).unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+//! messages of the commits which write articles to repositories.
+
+use std::fmt::Display;
+
+use diffy::Line;
+
+use crate::types::posts::Post;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Create,
+    Update,
+    Move,
+    Delete,
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            ChangeKind::Create => "create",
+            ChangeKind::Update => "update",
+            ChangeKind::Move => "move",
+            ChangeKind::Delete => "delete",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+/// a change of the article, which is committed to the repository
+#[derive(Debug, Clone)]
+pub struct Change<'a> {
+    pub kind: ChangeKind,
+    pub path: &'a str,
+    /// path of the article before it's moved
+    pub previous_path: Option<&'a str>,
+    pub title: &'a str,
+    pub version: &'a str,
+    /// lines added to the article
+    pub additions: usize,
+    /// lines removed from the article
+    pub deletions: usize,
+}
+
+impl<'a> Change<'a> {
+    pub fn new(kind: ChangeKind, path: &'a str, post: &'a Post) -> Change<'a> {
+        Change {
+            kind,
+            path,
+            previous_path: None,
+            title: post.title(),
+            version: post.version(),
+            additions: 0,
+            deletions: 0,
+        }
+    }
+
+    pub fn moved_from(self, previous_path: &'a str) -> Change<'a> {
+        Change {
+            previous_path: Some(previous_path),
+            ..self
+        }
+    }
+
+    /// count the lines changed from the old content of the article
+    pub fn with_stats(self, old: &str, new: &str) -> Change<'a> {
+        let patch = diffy::create_patch(old, new);
+        let (mut additions, mut deletions) = (0, 0);
+        for line in patch.hunks().iter().flat_map(|hunk| hunk.lines()) {
+            match line {
+                Line::Insert(_) => additions += 1,
+                Line::Delete(_) => deletions += 1,
+                Line::Context(_) => {}
+            }
+        }
+        Change {
+            additions,
+            deletions,
+            ..self
+        }
+    }
+
+    /// e.g. `update source/_posts/hello.md`
+    pub fn default_message(&self) -> String {
+        match (self.kind, self.previous_path) {
+            (ChangeKind::Move, Some(previous_path)) => {
+                format!("move {} to {}", previous_path, self.path)
+            }
+            (kind, _) => format!("{} {}", kind, self.path),
+        }
+    }
+
+    /// fill the placeholders of the template: `{action}`, `{path}`, `{previous_path}`,
+    /// `{title}`, `{version}` (the first 8 characters), `{additions}` and `{deletions}`.
+    /// Unknown placeholders are kept as they are.
+    pub fn render(&self, template: &str) -> String {
+        let mut message = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            message.push_str(&rest[..start]);
+            let placeholder = &rest[start..];
+            let end = match placeholder.find('}') {
+                Some(end) => end,
+                None => {
+                    rest = placeholder;
+                    break;
+                }
+            };
+            // a lone `{`, e.g. `{a {path}`
+            if placeholder[1..end].contains('{') {
+                message.push('{');
+                rest = &placeholder[1..];
+                continue;
+            }
+            match self.placeholder(&placeholder[1..end]) {
+                Some(value) => message.push_str(&value),
+                None => message.push_str(&placeholder[..=end]),
+            }
+            rest = &placeholder[end + 1..];
+        }
+        message.push_str(rest);
+        message
+    }
+
+    fn placeholder(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "action" => self.kind.to_string(),
+            "path" => self.path.to_string(),
+            "previous_path" => self.previous_path.unwrap_or(self.path).to_string(),
+            "title" => self.title.to_string(),
+            "version" => self.version.chars().take(8).collect(),
+            "additions" => self.additions.to_string(),
+            "deletions" => self.deletions.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod commit_message_test {
+    use super::*;
+
+    fn post() -> Post {
+        Post::new(
+            1,
+            1,
+            "Hello {path}".to_string(),
+            Default::default(),
+            String::new(),
+            "0a1b2c3d4e5f".to_string(),
+            String::new(),
+            Default::default(),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn render_test() {
+        let post = post();
+        let change = Change::new(ChangeKind::Update, "posts/hello.md", &post)
+            .with_stats("a\nb\nc\n", "a\nd\nc\ne\n");
+        assert_eq!((change.additions, change.deletions), (2, 1));
+        assert_eq!(change.default_message(), "update posts/hello.md");
+        assert_eq!(
+            change.render("{action} {title} ({version}, +{additions} -{deletions}) {unknown} {"),
+            "update Hello {path} (0a1b2c3d, +2 -1) {unknown} {"
+        );
+        assert_eq!(change.render("{a {path}"), "{a posts/hello.md");
+
+        let change = Change::new(ChangeKind::Move, "posts/b.md", &post).moved_from("posts/a.md");
+        assert_eq!(change.default_message(), "move posts/a.md to posts/b.md");
+        assert_eq!(
+            change.render("{previous_path} -> {path}"),
+            "posts/a.md -> posts/b.md"
+        );
+    }
+}
@@ -1,14 +1,158 @@
-use crate::types::posts::SyncReq;
+use std::time::Duration;
 
-use super::{github::GithubSyncer, types::SyncError, SyncAction};
+use diesel::{r2d2::ConnectionManager, MysqlConnection};
+use r2d2::Pool;
+
+use crate::{
+    operations::credentials::CredentialTokenQueryer,
+    traits::DbAction,
+    types::posts::{GithubSyncReq, SyncReq},
+};
+
+use super::{
+    forge::{ForgeSyncError, REQUEST_TIMEOUT},
+    gitea::GiteaSyncer,
+    github::{
+        auth::{GithubApp, GithubAuth},
+        GithubSyncer,
+    },
+    gitlab::GitlabSyncer,
+    local_git::LocalGitSyncer,
+    transform::Pipeline,
+    types::SyncError,
+    SyncAction,
+};
 
 #[derive(Debug, Default)]
 pub struct SyncerFactory;
 
 impl SyncerFactory {
-    pub fn create(req: SyncReq) -> Result<Box<dyn SyncAction>, SyncError> {
+    pub async fn create(
+        req: SyncReq,
+        pool: Pool<ConnectionManager<MysqlConnection>>,
+    ) -> Result<Box<dyn SyncAction>, SyncError> {
+        SyncerFactory::create_with_timeout(req, pool, REQUEST_TIMEOUT).await
+    }
+
+    /// the syncer whose requests to the forge time out after `timeout`
+    pub async fn create_with_timeout(
+        req: SyncReq,
+        pool: Pool<ConnectionManager<MysqlConnection>>,
+        timeout: Duration,
+    ) -> Result<Box<dyn SyncAction>, SyncError> {
+        let token = SyncerFactory::token(&req, pool).await?;
+        let pipeline = Pipeline::new(req.transforms())?;
+        let target = req.clone();
         match req {
-            SyncReq::Github(req) => Ok(Box::new(GithubSyncer::new(req.path(), req.repository())?)),
+            SyncReq::Github(req) => Ok(Box::new(
+                GithubSyncer::new(
+                    req.path(),
+                    req.repository(),
+                    req.api_url(),
+                    req.mode(),
+                    req.pull_request(),
+                    req.commit_options(),
+                    SyncerFactory::github_auth(&req, token)?,
+                )?
+                .with_pipeline(pipeline)
+                .with_target(target)
+                .with_timeout(timeout),
+            )),
+            SyncReq::Gitea(req) => Ok(Box::new(
+                GiteaSyncer::new(req.path(), req.repository(), token)?
+                    .with_pipeline(pipeline)
+                    .with_target(target)
+                    .with_timeout(timeout),
+            )),
+            SyncReq::Gitlab(req) => Ok(Box::new(
+                GitlabSyncer::new(req.path(), req.repository(), req.branch(), token)?
+                    .with_pipeline(pipeline)
+                    .with_target(target)
+                    .with_timeout(timeout),
+            )),
+            SyncReq::LocalGit(req) => Ok(Box::new(
+                LocalGitSyncer::new(req.path(), req.repository())?
+                    .with_pipeline(pipeline)
+                    .with_target(target),
+            )),
+        }
+    }
+
+    /// the checks of `create` which don't reach the forge,
+    /// so the requests run in background are rejected before they're queued
+    pub async fn validate(
+        req: &SyncReq,
+        pool: Pool<ConnectionManager<MysqlConnection>>,
+    ) -> Result<(), SyncError> {
+        SyncerFactory::token(req, pool).await?;
+        Pipeline::new(req.transforms())?;
+        if let SyncReq::LocalGit(req) = req {
+            LocalGitSyncer::new(req.path(), req.repository())?;
+        }
+        Ok(())
+    }
+
+    /// decrypt the token of the credential referenced by the request,
+    /// `None` if the token of the env is used
+    pub async fn token(
+        req: &SyncReq,
+        pool: Pool<ConnectionManager<MysqlConnection>>,
+    ) -> Result<Option<String>, SyncError> {
+        match req.credential_id() {
+            Some(id) => Ok(Some(
+                CredentialTokenQueryer(id, req.platform())
+                    .execute(pool)
+                    .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// the token of the credential, the installation token of the github app,
+    /// or `GITHUB_TOKEN` env in order
+    pub fn github_auth(
+        req: &GithubSyncReq,
+        token: Option<String>,
+    ) -> Result<GithubAuth, ForgeSyncError> {
+        if token.is_some() {
+            return Ok(GithubAuth::Token(token));
+        }
+        match GithubApp::from_env(req.installation_id())? {
+            Some(app) => Ok(GithubAuth::App(app)),
+            None => Ok(GithubAuth::Token(None)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod factory_test {
+    use super::*;
+
+    /// no connection is opened, the requests without credentials don't query the database
+    fn unconnected_pool() -> Pool<ConnectionManager<MysqlConnection>> {
+        Pool::builder()
+            .min_idle(Some(0))
+            .build_unchecked(ConnectionManager::new("mysql://127.0.0.1:1/letterman"))
+    }
+
+    #[actix_web::test]
+    async fn validate_test() {
+        let req = |json| serde_json::from_value::<SyncReq>(json).unwrap();
+        let valid = req(serde_json::json!({"platform": "LocalGit", "path": "posts/hello.md"}));
+        assert!(SyncerFactory::validate(&valid, unconnected_pool())
+            .await
+            .is_ok());
+
+        let escaped = req(serde_json::json!({"platform": "LocalGit", "path": "../hello.md"}));
+        let transforms = req(serde_json::json!({
+            "platform": "Github",
+            "transforms": [{"kind": "wikiLink", "prefix": ""}],
+        }));
+        for invalid in [escaped, transforms] {
+            let error = SyncerFactory::validate(&invalid, unconnected_pool())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, SyncError::UserError(_)));
         }
     }
 }
@@ -0,0 +1,692 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::error;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    traits::MongoActionError,
+    types::{
+        forge_record::{CreateForgeRecordError, ForgeRecord, QueryForgeRecordError},
+        posts::{Post, SyncReq},
+        pull_requests::PullRequest,
+    },
+    utils,
+};
+
+use super::{
+    commit_message::{Change, ChangeKind},
+    frontmatter::{extract, package},
+    transform::Pipeline,
+    types::{Context, SyncError, SyncStatus, SyncTarget},
+    SyncAction,
+};
+
+static FORGE_SYNC_RECORDS_KEY: &str = "records";
+static FORGE_SYNC_FILE_KEY: &str = "file";
+
+/// timeout of the requests to the forges while the user waits for the response
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// timeout of the requests of the background jobs, nobody waits for them,
+/// so the large files and the slow servers are given more time
+pub const JOB_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// a file stored in the repository of a git forge
+#[derive(Debug, Clone)]
+pub struct ForgeFile {
+    pub path: String,
+    /// sha of the file blob
+    pub sha: String,
+    /// url to view the file in browser
+    pub url: String,
+    /// decoded content of the file, it's empty if the file is returned by a write api.
+    pub content: String,
+    /// sha of the commit which writes the file, if it's returned by a write api
+    pub commit: Option<String>,
+    /// pull request which publishes the file, if it's not committed to the default branch
+    pub pull_request: Option<PullRequest>,
+}
+
+/// endpoints and payload shapes of a git forge "repository contents" api.
+/// `ForgeSyncer` drives the create/update/pull/check flow on top of it.
+#[async_trait]
+pub trait ForgeApi: Send + Sync {
+    type Record: ForgeRecord + Clone + Send + Sync + 'static;
+
+    /// create a new file in the repository
+    async fn create_file(
+        &self,
+        repo: &str,
+        path: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError>;
+
+    /// overwrite the file whose current blob is `sha`
+    async fn update_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError>;
+
+    /// delete the file whose current blob is `sha`
+    async fn delete_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+    ) -> Result<(), SyncError>;
+
+    /// timeout of the following requests, `REQUEST_TIMEOUT` by default
+    fn set_timeout(&mut self, timeout: Duration);
+
+    /// message of the commit which writes the change
+    fn commit_message(&self, change: &Change) -> String {
+        change.default_message()
+    }
+
+    /// called with the sync records of the post before its file is read or written,
+    /// the latest record comes first
+    async fn prepare(&mut self, _post: &Post, _records: &[Self::Record]) -> Result<(), SyncError> {
+        Ok(())
+    }
+
+    /// fetch the file and decode its content
+    async fn get_file(&self, repo: &str, path: &str) -> Result<ForgeFile, SyncError>;
+
+    /// query sync records of the platform, the latest record comes first
+    async fn query_records(
+        &self,
+        post_id: i64,
+        mongo_db: mongodb::Database,
+    ) -> Result<Vec<Self::Record>, SyncError>;
+
+    /// store a sync record of the platform, `previous_path` is given if the file is moved,
+    /// `target` is the request which synchronizes it
+    async fn create_record(
+        &self,
+        post: &Post,
+        repo: &str,
+        file: &ForgeFile,
+        previous_path: Option<&str>,
+        target: Option<&SyncReq>,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError>;
+
+    /// store a tombstone of the record after the file is deleted
+    async fn create_tombstone(
+        &self,
+        record: &Self::Record,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError>;
+}
+
+/// syncer for the platforms which store articles as files in a git repository
+pub struct ForgeSyncer<A: ForgeApi> {
+    /// path is required for the first time sync
+    path: Option<String>,
+    repository: Option<String>,
+    api: A,
+    pipeline: Pipeline,
+    /// the request which creates the syncer, it's stored with the records
+    target: Option<SyncReq>,
+    ctx: Context,
+}
+
+#[async_trait]
+impl<A: ForgeApi + 'static> SyncAction for ForgeSyncer<A> {
+    fn render(&self, post: &Post) -> Result<String, SyncError> {
+        Ok(package(&self.pipeline.push(post)?)?)
+    }
+
+    async fn push_create(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        // an unpublished article is published to the place of its tombstone by default
+        self.get_sync_records(post, mongo_db.clone()).await?;
+        if self.path.is_none() || self.repository.is_none() {
+            return Err(ForgeSyncError::UserError(
+                "path and repository is required for the first time sync".to_string(),
+            )
+            .into());
+        }
+        let repo = self.repository.clone().unwrap();
+        let path = self.path.clone().unwrap();
+        let content = self.render(post)?;
+        let change = Change::new(ChangeKind::Create, &path, post).with_stats("", &content);
+        let file = self
+            .api
+            .create_file(&repo, &path, &self.api.commit_message(&change), &content)
+            .await?;
+        self.api
+            .create_record(
+                post,
+                &repo,
+                &file,
+                None,
+                self.target.as_ref(),
+                mongo_db.clone(),
+            )
+            .await
+    }
+
+    async fn push_update(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => return Err(SyncError::NotFound),
+        };
+        // the remote file may be fetched and changed since the last sync
+        let sha = match self.ctx.get::<ForgeFile>(FORGE_SYNC_FILE_KEY) {
+            Some(file) => file.sha,
+            None => record.sha().to_string(),
+        };
+        let content = self.render(post)?;
+        let previous = self.remote_content(post, mongo_db.clone()).await?;
+        let change =
+            Change::new(ChangeKind::Update, record.path(), post).with_stats(&previous, &content);
+        let file = self
+            .api
+            .update_file(
+                record.repository(),
+                record.path(),
+                &sha,
+                &self.api.commit_message(&change),
+                &content,
+            )
+            .await?;
+        self.api
+            .create_record(
+                post,
+                record.repository(),
+                &file,
+                None,
+                self.target.as_ref(),
+                mongo_db.clone(),
+            )
+            .await
+    }
+
+    async fn pull(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<Post>, SyncError> {
+        let remote = match self.fetch(post, mongo_db.clone()).await? {
+            Some(remote) => remote,
+            None => return Ok(None),
+        };
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        let repo = records.unwrap().first().unwrap().repository().to_string();
+        let file = self.get_remote_file(post, mongo_db.clone()).await?.unwrap();
+        self.api
+            .create_record(
+                &remote,
+                &repo,
+                &file,
+                None,
+                self.target.as_ref(),
+                mongo_db.clone(),
+            )
+            .await?;
+
+        Ok(Some(remote))
+    }
+
+    async fn fetch(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<Post>, SyncError> {
+        let file = self.get_remote_file(post, mongo_db.clone()).await?;
+        if file.is_none() {
+            return Ok(None);
+        }
+        let file = file.unwrap();
+        let res = extract(&file.content)?;
+        let content = self.pipeline.pull(res.content)?;
+        let title = if let Some(title) = res.title {
+            title
+        } else {
+            post.title().to_string()
+        };
+        let version = utils::sha_utils::sha_post2(&title, &res.metadata, &content);
+        Ok(Some(Post::new(
+            utils::snowflake::next_id(),
+            post.post_id(),
+            title,
+            res.metadata,
+            content,
+            version,
+            post.version().to_string(),
+            utils::time_utils::now(),
+            utils::time_utils::now(),
+        )))
+    }
+
+    async fn synced_version(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<String>, SyncError> {
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        Ok(records.map(|records| records.first().unwrap().version().to_string()))
+    }
+
+    async fn target(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<SyncTarget, SyncError> {
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => {
+                return Ok(SyncTarget {
+                    repository: self.repository.clone(),
+                    path: self.path.clone(),
+                    content: None,
+                })
+            }
+        };
+        let file = self.get_remote_file(post, mongo_db.clone()).await?;
+        Ok(SyncTarget {
+            repository: Some(record.repository().to_string()),
+            path: Some(record.path().to_string()),
+            content: file.map(|file| file.content),
+        })
+    }
+
+    async fn check_changed(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<SyncStatus, SyncError> {
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => return Ok(SyncStatus::NeverSynced),
+        };
+        // the file deleted outside letterman is created again
+        let file = match self.get_remote_file(post, mongo_db.clone()).await? {
+            Some(file) => file,
+            None => return Ok(SyncStatus::NeverSynced),
+        };
+        Ok(SyncStatus::new(
+            post.version() != record.version(),
+            file.sha != record.sha(),
+        ))
+    }
+
+    async fn push_move(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => {
+                return Err(
+                    ForgeSyncError::UserError("the post is not published".to_string()).into(),
+                )
+            }
+        };
+        let path = match &self.path {
+            Some(path) if path != record.path() => path.clone(),
+            _ => {
+                return Err(ForgeSyncError::UserError(
+                    "a new path is required to move the article".to_string(),
+                )
+                .into())
+            }
+        };
+        let repo = record.repository();
+        let sha = match self.ctx.get::<ForgeFile>(FORGE_SYNC_FILE_KEY) {
+            Some(file) => file.sha,
+            None => record.sha().to_string(),
+        };
+        let content = self.render(post)?;
+        let previous = self.remote_content(post, mongo_db.clone()).await?;
+        let change = Change::new(ChangeKind::Move, &path, post)
+            .moved_from(record.path())
+            .with_stats(&previous, &content);
+        let message = self.api.commit_message(&change);
+        let file = self
+            .api
+            .create_file(repo, &path, &message, &content)
+            .await?;
+        // keep the article at the old path only, if the old file can't be removed
+        if let Err(e) = self
+            .api
+            .delete_file(repo, record.path(), &sha, &message)
+            .await
+        {
+            if let Err(e) = self.api.delete_file(repo, &path, &file.sha, &message).await {
+                error!(
+                    "failed to remove {} of {} after moving failed: {}",
+                    path, repo, e
+                );
+            }
+            return Err(e);
+        }
+        self.api
+            .create_record(
+                post,
+                repo,
+                &file,
+                Some(record.path()),
+                self.target.as_ref(),
+                mongo_db,
+            )
+            .await?;
+        self.ctx = Context::new();
+        Ok(())
+    }
+
+    async fn unpublish(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => {
+                return Err(
+                    ForgeSyncError::UserError("the post is not published".to_string()).into(),
+                )
+            }
+        };
+        let previous = self.remote_content(post, mongo_db.clone()).await?;
+        let change = Change::new(ChangeKind::Delete, record.path(), post).with_stats(&previous, "");
+        // the remote file changed since the last sync is not deleted, because the sha doesn't match
+        self.api
+            .delete_file(
+                record.repository(),
+                record.path(),
+                record.sha(),
+                &self.api.commit_message(&change),
+            )
+            .await?;
+        self.api.create_tombstone(&record, mongo_db).await?;
+        self.ctx = Context::new();
+        Ok(())
+    }
+}
+
+impl<A: ForgeApi> ForgeSyncer<A> {
+    pub fn with_api(path: Option<String>, repository: Option<String>, api: A) -> ForgeSyncer<A> {
+        ForgeSyncer {
+            path,
+            repository,
+            api,
+            pipeline: Pipeline::default(),
+            target: None,
+            ctx: Context::new(),
+        }
+    }
+
+    pub(super) fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub(super) fn with_target(mut self, target: SyncReq) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub(super) fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.api.set_timeout(timeout);
+        self
+    }
+
+    async fn get_sync_records(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<Vec<A::Record>>, SyncError> {
+        let records: Option<Vec<A::Record>> = self.ctx.get(FORGE_SYNC_RECORDS_KEY);
+        if records.is_some() {
+            return Ok(records);
+        }
+        let records = self
+            .api
+            .query_records(post.post_id(), mongo_db.clone())
+            .await?;
+        self.api.prepare(post, &records).await?;
+        if records.is_empty() {
+            return Ok(None);
+        }
+        // the article is unpublished, it's published again to the same place by default
+        if let Some(tombstone) = records.first().filter(|record| record.deleted()) {
+            self.path.get_or_insert(tombstone.path().to_string());
+            self.repository
+                .get_or_insert(tombstone.repository().to_string());
+            return Ok(None);
+        }
+        self.ctx
+            .set(FORGE_SYNC_RECORDS_KEY.to_string(), records.clone());
+        Ok(Some(records))
+    }
+
+    /// content of the remote file, for the stats of the change
+    async fn remote_content(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<String, SyncError> {
+        let file = self.get_remote_file(post, mongo_db).await?;
+        Ok(file.map(|file| file.content).unwrap_or_default())
+    }
+
+    /// fetch the file described by the latest sync record, `None` if it's deleted remotely
+    async fn get_remote_file(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<ForgeFile>, SyncError> {
+        let file: Option<ForgeFile> = self.ctx.get(FORGE_SYNC_FILE_KEY);
+        if file.is_some() {
+            return Ok(file);
+        }
+        let records = self.get_sync_records(post, mongo_db.clone()).await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => return Ok(None),
+        };
+        let file = match self.api.get_file(record.repository(), record.path()).await {
+            Ok(file) => file,
+            // like a tombstone, the article is published to the same place again by default
+            Err(SyncError::RemoteNotFound(_)) => {
+                self.path.get_or_insert(record.path().to_string());
+                self.repository
+                    .get_or_insert(record.repository().to_string());
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        self.ctx.set(FORGE_SYNC_FILE_KEY.to_string(), file.clone());
+        Ok(Some(file))
+    }
+}
+
+/// return the response if it's successful, otherwise log the body and fail
+/// with the error of the status
+pub(super) async fn ensure_success(
+    resp: reqwest::Response,
+    action: &str,
+) -> Result<reqwest::Response, SyncError> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().await?;
+    error!("{} error: {}", action, body);
+    let message = match serde_json::from_str::<ErrorResp>(&body) {
+        Ok(resp) => format!("{}: {}", action, resp.message),
+        Err(_) => action.to_string(),
+    };
+    Err(match status {
+        StatusCode::NOT_FOUND => SyncError::RemoteNotFound(message),
+        StatusCode::CONFLICT => SyncError::RemoteConflict(message),
+        StatusCode::UNPROCESSABLE_ENTITY => SyncError::RemoteValidation(message),
+        StatusCode::TOO_MANY_REQUESTS => SyncError::RateLimited { reset_at: None },
+        _ => SyncError::RemoteServer,
+    })
+}
+
+/// error body of the forge apis, e.g. `{"message": "Not Found"}`
+#[derive(Debug, Deserialize)]
+struct ErrorResp {
+    message: String,
+}
+
+/// the token of the credential, or the token of the platform from env
+pub(super) fn token_or_env(
+    token: Option<String>,
+    key: &'static str,
+) -> Result<String, ForgeSyncError> {
+    match token {
+        Some(token) => Ok(token),
+        None => std::env::var(key).map_err(|_| ForgeSyncError::NoToken(key)),
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ForgeSyncError {
+    #[error("Network Error")]
+    NetworkError(String),
+    #[error("Unknown Error: {0}")]
+    Other(String),
+    #[error("User Error: {0}")]
+    UserError(String),
+    #[error("Please set {0} env if you want to synchronize to this platform")]
+    NoToken(&'static str),
+    #[error("Post not found")]
+    NotFound,
+}
+
+impl From<reqwest::Error> for ForgeSyncError {
+    fn from(item: reqwest::Error) -> Self {
+        ForgeSyncError::NetworkError(item.to_string())
+    }
+}
+
+impl From<ForgeSyncError> for SyncError {
+    fn from(val: ForgeSyncError) -> Self {
+        match val {
+            ForgeSyncError::NetworkError(e) => SyncError::NetworkError(e),
+            ForgeSyncError::Other(e) => SyncError::Other(e),
+            ForgeSyncError::UserError(e) => SyncError::UserError(e),
+            ForgeSyncError::NotFound => SyncError::NotFound,
+            ForgeSyncError::NoToken(_) => SyncError::UserError(val.to_string()),
+        }
+    }
+}
+
+impl From<MongoActionError<QueryForgeRecordError>> for SyncError {
+    fn from(value: MongoActionError<QueryForgeRecordError>) -> Self {
+        match value {
+            MongoActionError::Error(QueryForgeRecordError::Database(_)) => SyncError::Database,
+            MongoActionError::Pool(_) => SyncError::Database,
+        }
+    }
+}
+
+impl From<MongoActionError<CreateForgeRecordError>> for SyncError {
+    fn from(value: MongoActionError<CreateForgeRecordError>) -> Self {
+        match value {
+            MongoActionError::Error(CreateForgeRecordError::Database(_)) => SyncError::Database,
+            MongoActionError::Pool(_) => SyncError::Database,
+        }
+    }
+}
+
+#[cfg(test)]
+mod forge_test {
+    use mongodb::{bson::oid::ObjectId, options::ClientOptions, Client};
+
+    use super::*;
+    use crate::{
+        operations::remote::{
+            github::{auth::GithubAuth, GithubApi},
+            mock_github,
+        },
+        traits::DocumentConvert,
+        types::github_record::{GithubRecord, InsertableGithubRecord},
+    };
+
+    /// the records are read from the context, the database is never connected
+    async fn unused_mongo() -> mongodb::Database {
+        let options = ClientOptions::parse("mongodb://127.0.0.1:1").await.unwrap();
+        Client::with_options(options).unwrap().database("letterman")
+    }
+
+    #[actix_web::test]
+    async fn remote_deleted_test() {
+        let (base_url, mock) = mock_github::start().await;
+        let api =
+            GithubApi::with_base_url(&base_url, GithubAuth::Token(Some("test-token".to_string())))
+                .unwrap();
+        let repo = "letterman/test-repo";
+        let path = "posts/hello.md";
+        let file = api
+            .create_file(repo, path, "create", "hello")
+            .await
+            .unwrap();
+        let post = Post::new(
+            1,
+            1,
+            "Hello".to_string(),
+            Default::default(),
+            "world".to_string(),
+            "0a1b2c3d4e5f".to_string(),
+            String::new(),
+            Default::default(),
+            Default::default(),
+        );
+        let mut doc = InsertableGithubRecord::new(
+            post.post_id(),
+            post.version().to_string(),
+            path.to_string(),
+            file.sha.clone(),
+            repo.to_string(),
+            file.url.clone(),
+        )
+        .to_doc();
+        doc.insert("_id", ObjectId::new());
+        let record: GithubRecord = mongodb::bson::from_document(doc).unwrap();
+        // the article is deleted outside letterman
+        api.delete_file(repo, path, &file.sha, "delete")
+            .await
+            .unwrap();
+        assert!(mock.file(repo, path).is_none());
+
+        let mut syncer = ForgeSyncer::with_api(None, None, api);
+        syncer
+            .ctx
+            .set(FORGE_SYNC_RECORDS_KEY.to_string(), vec![record]);
+        let mongo_db = unused_mongo().await;
+        let status = syncer.check_changed(&post, mongo_db.clone()).await.unwrap();
+        assert_eq!(status, SyncStatus::NeverSynced);
+        // it's created again at the place of the last sync
+        let target = syncer.target(&post, mongo_db).await.unwrap();
+        assert_eq!(syncer.path.as_deref(), Some(path));
+        assert_eq!(syncer.repository.as_deref(), Some(repo));
+        assert!(target.content.is_none());
+    }
+}
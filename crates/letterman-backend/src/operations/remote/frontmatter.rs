@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use markdown::{mdast::Node, Constructs};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::types::posts::{Metadata, Post};
+
+use super::types::SyncError;
+
+#[derive(Debug)]
+pub(super) struct ExtractResult {
+    pub(super) title: Option<String>,
+    pub(super) content: String,
+    pub(super) metadata: Metadata,
+}
+
+#[derive(Debug, Error)]
+pub(super) enum FrontmatterError {
+    #[error("failed to parse markdown: {0}")]
+    Markdown(markdown::message::Message),
+    #[error("failed to parse frontmatter: {0}")]
+    Yaml(serde_yaml::Error),
+}
+
+/// package markdown content with metadata, the title comes first and the other keys
+/// are sorted, so the frontmatter of a post doesn't change between pushes
+pub(super) fn package(post: &Post) -> Result<String, serde_yaml::Error> {
+    let mut metadata = serde_yaml::Mapping::new();
+    metadata.insert("title".into(), post.title().into());
+    let mut keys: Vec<&String> = post
+        .metadata()
+        .keys()
+        .filter(|key| *key != "title")
+        .collect();
+    keys.sort();
+    for key in keys {
+        metadata.insert(
+            key.as_str().into(),
+            serde_yaml::to_value(&post.metadata()[key])?,
+        );
+    }
+    let frontmatter = serde_yaml::to_string(&metadata)?;
+    let content = format!("---\n{}\n---\n{}", frontmatter, post.content());
+    Ok(content)
+}
+
+/// extract metadata from content
+/// return (title, content, metadata)
+pub(super) fn extract(content: &str) -> Result<ExtractResult, FrontmatterError> {
+    let constructs = Constructs {
+        frontmatter: true,
+        ..Constructs::default()
+    };
+    let ast = markdown::to_mdast(
+        content,
+        &markdown::ParseOptions {
+            constructs,
+            ..markdown::ParseOptions::default()
+        },
+    )?;
+    let frontmatter = ast.children().and_then(|children| {
+        children.iter().find_map(|child| match child {
+            Node::Yaml(yaml) => Some(yaml),
+            _ => None,
+        })
+    });
+    let frontmatter = match frontmatter {
+        Some(frontmatter) => frontmatter,
+        None => {
+            return Ok(ExtractResult {
+                title: None,
+                content: content.to_string(),
+                metadata: HashMap::new(),
+            })
+        }
+    };
+
+    // the content starts after the line break of the closing fence
+    let content = match &frontmatter.position {
+        Some(position) => {
+            let content = &content[position.end.offset..];
+            content
+                .strip_prefix("\r\n")
+                .or_else(|| content.strip_prefix('\n'))
+                .unwrap_or(content)
+        }
+        None => content,
+    };
+    let mut metadata = extract_metadata(&frontmatter.value)?;
+    let title = match metadata.remove("title") {
+        Some(Value::String(title)) => Some(title),
+        Some(Value::Null) | None => None,
+        Some(title) => Some(title.to_string()),
+    };
+    Ok(ExtractResult {
+        title,
+        content: content.to_string(),
+        metadata,
+    })
+}
+
+/// parse the yaml of the frontmatter, it must be a map
+fn extract_metadata(yaml: &str) -> Result<Metadata, serde_yaml::Error> {
+    if yaml.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    serde_yaml::from_str(yaml)
+}
+
+impl From<markdown::message::Message> for FrontmatterError {
+    fn from(value: markdown::message::Message) -> Self {
+        FrontmatterError::Markdown(value)
+    }
+}
+
+impl From<serde_yaml::Error> for FrontmatterError {
+    fn from(value: serde_yaml::Error) -> Self {
+        FrontmatterError::Yaml(value)
+    }
+}
+
+impl From<FrontmatterError> for SyncError {
+    fn from(value: FrontmatterError) -> Self {
+        SyncError::Decode(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod frontmatter_test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn markdown_extract() {
+        let content = "---\na:b\n---\ncontent";
+        let constructs = Constructs {
+            frontmatter: true,
+            ..Constructs::default()
+        };
+        let ast = markdown::to_mdast(
+            content,
+            &markdown::ParseOptions {
+                constructs,
+                ..markdown::ParseOptions::default()
+            },
+        )
+        .unwrap();
+
+        println!("{:#?}", ast);
+    }
+
+    #[test]
+    fn serialize_metadata_test() {
+        let v = vec![("title", "11")];
+        let mut m = HashMap::new();
+        m.insert("title".to_string(), "111".to_string());
+        println!("{}", serde_yaml::to_string(&v).unwrap());
+        println!("{}", serde_yaml::to_string(&m).unwrap())
+    }
+
+    #[test]
+    fn extract_test() {
+        let content: &'static str = "---
+title: Remake | CS50 AI入门笔记
+top: false
+toc: true
+mathjax: true
+date: 2022-07-02 17:02:19
+password:
+summary:
+tags:
+    - 人工智能
+categories:
+    - Remake
+---
+
+# Introduction  to  AI
+";
+        let constructs = Constructs {
+            frontmatter: true,
+            ..Constructs::default()
+        };
+        let ast = markdown::to_mdast(
+            content,
+            &markdown::ParseOptions {
+                constructs,
+                ..markdown::ParseOptions::default()
+            },
+        )
+        .unwrap();
+        println!("{:#?}", ast);
+        let res = extract(content).unwrap();
+        assert_eq!(res.title.as_deref(), Some("Remake | CS50 AI入门笔记"));
+        assert_eq!(res.content, "\n# Introduction  to  AI\n");
+        assert_eq!(res.metadata["top"], Value::Bool(false));
+        assert_eq!(res.metadata["date"], "2022-07-02 17:02:19");
+        assert_eq!(res.metadata["password"], Value::Null);
+        assert_eq!(res.metadata["tags"], json!(["人工智能"]));
+        assert_eq!(res.metadata["categories"], json!(["Remake"]));
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let content = "---
+title: Hello
+date: 2022-07-02 17:02:19
+tags:
+  - rust
+  - hexo
+categories:
+  - [Notes, Rust]
+toc: true
+weight: 3
+ratio: 0.5
+summary:
+sticky: 'yes'
+cover:
+  image: /images/cover.png
+  alt: cover
+---
+# Hello
+
+---
+
+world
+";
+        let extracted = extract(content).unwrap();
+        assert_eq!(extracted.metadata["weight"], 3);
+        assert_eq!(extracted.metadata["ratio"], 0.5);
+        assert_eq!(extracted.metadata["sticky"], "yes");
+        assert_eq!(extracted.metadata["categories"], json!([["Notes", "Rust"]]));
+        assert_eq!(extracted.metadata["cover"]["alt"], "cover");
+        assert_eq!(extracted.content, "# Hello\n\n---\n\nworld\n");
+
+        let post = Post::new(
+            1,
+            1,
+            extracted.title.unwrap(),
+            extracted.metadata.clone(),
+            extracted.content.clone(),
+            "".to_string(),
+            "".to_string(),
+            Default::default(),
+            Default::default(),
+        );
+        let packaged = package(&post).unwrap();
+        assert!(packaged.starts_with("---\ntitle: Hello\ncategories:\n- - Notes\n"));
+        assert!(packaged.contains("\ndate: 2022-07-02 17:02:19\n"));
+        let repackaged = extract(&packaged).unwrap();
+        assert_eq!(repackaged.title.as_deref(), Some("Hello"));
+        assert_eq!(repackaged.metadata, extracted.metadata);
+        assert_eq!(repackaged.content, extracted.content);
+        assert_eq!(package(&post).unwrap(), packaged);
+    }
+
+    #[test]
+    fn extract_without_frontmatter_test() {
+        let content = "# Title\n\n---\n\ntext\n---\n";
+        let res = extract(content).unwrap();
+        assert!(res.title.is_none());
+        assert!(res.metadata.is_empty());
+        assert_eq!(res.content, content);
+
+        let res = extract("---\n---\ncontent").unwrap();
+        assert!(res.metadata.is_empty());
+        assert_eq!(res.content, "content");
+
+        assert!(matches!(
+            extract("---\n- a\n- b\n---\ncontent"),
+            Err(FrontmatterError::Yaml(_))
+        ));
+    }
+}
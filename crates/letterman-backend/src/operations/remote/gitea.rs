@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{self, HeaderValue};
+
+use crate::{
+    operations::forge_record::{ForgeRecordCreator, GiteaRecordQueryerByPostId},
+    traits::MongoAction,
+    types::{
+        forge_record::{GiteaRecord, InsertableForgeRecord},
+        github_record::{
+            CreateContentParam, DeleteContentParam, GithubArticleRecord, UpdateContentParam,
+            WriteContentResp,
+        },
+        posts::{Post, SyncReq},
+        Platform,
+    },
+};
+
+use super::{
+    forge::{
+        ensure_success, token_or_env, ForgeApi, ForgeFile, ForgeSyncError, ForgeSyncer,
+        REQUEST_TIMEOUT,
+    },
+    types::SyncError,
+};
+
+pub type GiteaSyncer = ForgeSyncer<GiteaApi>;
+
+impl GiteaSyncer {
+    pub fn new(
+        path: Option<String>,
+        repository: Option<String>,
+        token: Option<String>,
+    ) -> Result<GiteaSyncer, ForgeSyncError> {
+        Ok(ForgeSyncer::with_api(
+            path,
+            repository,
+            GiteaApi::new(token)?,
+        ))
+    }
+}
+
+/// gitea (and forgejo) repository contents api.
+/// it mirrors the github contents api, so the payloads of github are reused.
+pub struct GiteaApi {
+    base_url: String,
+    client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl GiteaApi {
+    /// the token is `GITEA_TOKEN` env if it's not given
+    pub fn new(token: Option<String>) -> Result<GiteaApi, ForgeSyncError> {
+        let base_url = std::env::var("GITEA_URL").map_err(|_| {
+            ForgeSyncError::UserError(
+                "Please set GITEA_URL env if you want to synchronize to gitea".to_string(),
+            )
+        })?;
+        let mut header_map = header::HeaderMap::new();
+        header_map.insert(
+            header::USER_AGENT,
+            header::HeaderValue::from_static("letterman"),
+        );
+        let token = token_or_env(token, "GITEA_TOKEN")?;
+        header_map.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("token {}", token))
+                .map_err(|_| ForgeSyncError::UserError("invalid gitea token".to_string()))?,
+        );
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .default_headers(header_map)
+            .build()?;
+        Ok(GiteaApi {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+            timeout: REQUEST_TIMEOUT,
+        })
+    }
+
+    fn content_url(&self, repo: &str, path: &str) -> String {
+        format!("{}/api/v1/repos/{repo}/contents/{path}", self.base_url)
+    }
+}
+
+#[async_trait]
+impl ForgeApi for GiteaApi {
+    type Record = GiteaRecord;
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    async fn create_file(
+        &self,
+        repo: &str,
+        path: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError> {
+        let param = CreateContentParam::new(message, content);
+        let resp = self
+            .client
+            .post(self.content_url(repo, path))
+            .json(&param)
+            .timeout(self.timeout)
+            .send()
+            .await?;
+        let resp = ensure_success(resp, "gitea push create").await?;
+        Ok(resp.json::<WriteContentResp>().await?.into())
+    }
+
+    async fn update_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError> {
+        let param = UpdateContentParam::new(message, content, sha);
+        let resp = self
+            .client
+            .put(self.content_url(repo, path))
+            .json(&param)
+            .timeout(self.timeout)
+            .send()
+            .await?;
+        let resp = ensure_success(resp, "gitea push update").await?;
+        Ok(resp.json::<WriteContentResp>().await?.into())
+    }
+
+    async fn delete_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+    ) -> Result<(), SyncError> {
+        let param = DeleteContentParam::new(message, sha);
+        let resp = self
+            .client
+            .delete(self.content_url(repo, path))
+            .json(&param)
+            .timeout(self.timeout)
+            .send()
+            .await?;
+        ensure_success(resp, "gitea delete article").await?;
+        Ok(())
+    }
+
+    async fn get_file(&self, repo: &str, path: &str) -> Result<ForgeFile, SyncError> {
+        let resp = self
+            .client
+            .get(self.content_url(repo, path))
+            .timeout(self.timeout)
+            .send()
+            .await?;
+        let resp = ensure_success(resp, "get gitea article").await?;
+        let content = resp.json::<GithubArticleRecord>().await?.decode_content()?;
+        Ok(content.into())
+    }
+
+    async fn query_records(
+        &self,
+        post_id: i64,
+        mongo_db: mongodb::Database,
+    ) -> Result<Vec<GiteaRecord>, SyncError> {
+        Ok(GiteaRecordQueryerByPostId(post_id)
+            .execute(mongo_db.clone())
+            .await?)
+    }
+
+    async fn create_record(
+        &self,
+        post: &Post,
+        repo: &str,
+        file: &ForgeFile,
+        previous_path: Option<&str>,
+        target: Option<&SyncReq>,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        ForgeRecordCreator(
+            InsertableForgeRecord::new(
+                Platform::Gitea,
+                post.post_id(),
+                post.version().to_string(),
+                file.path.clone(),
+                file.sha.clone(),
+                repo.to_string(),
+                file.url.clone(),
+            )
+            .moved_from(previous_path)
+            .for_target(target),
+        )
+        .execute(mongo_db.clone())
+        .await?;
+        Ok(())
+    }
+
+    async fn create_tombstone(
+        &self,
+        record: &GiteaRecord,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        ForgeRecordCreator(InsertableForgeRecord::tombstone(Platform::Gitea, record))
+            .execute(mongo_db.clone())
+            .await?;
+        Ok(())
+    }
+}
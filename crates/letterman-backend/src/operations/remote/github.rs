@@ -1,409 +1,406 @@
-use std::{collections::HashMap, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-
-use log::error;
-use markdown::{mdast::Node, Constructs};
 use reqwest::header::{self, HeaderValue};
 use thiserror::Error;
 
 use crate::{
-    operations::{
-        github_record::{GithubRecordCreator, GithubRecordQueryerByPostId},
-        posts::PostLatestSyncRecordQueryer,
+    operations::github_record::{
+        GithubRecordCreator, GithubRecordPullRequestUpdater, GithubRecordQueryerByPostId,
     },
     traits::{MongoAction, MongoActionError},
     types::{
+        commits::CommitOptions,
+        git_data::GithubWriteMode,
         github_record::{
-            CreateContentParam, GithubArticleRecord, GithubRecord, InsertableGithubRecord,
-            QueryGithubRecordError, UpdateContentParam, WriteContentResp,
+            CreateContentParam, DeleteContentParam, GithubArticleRecord, GithubRecord,
+            InsertableGithubRecord, QueryGithubRecordError, UpdateContentParam,
+            UpdateGithubRecordError, WriteContentResp,
         },
-        posts::{Post, QuerySyncRecordError, SyncRecord},
+        posts::{Post, QuerySyncRecordError, SyncReq},
+        pull_requests::PullRequest,
     },
-    utils::{self},
 };
 
+use self::{
+    auth::{GithubAuth, TokenProvider},
+    pull_request::ReviewBranch,
+    retry::RetryPolicy,
+};
 use super::{
-    types::{Context, SyncError},
-    SyncAction,
+    commit_message::Change,
+    forge::{ensure_success, ForgeApi, ForgeFile, ForgeSyncError, ForgeSyncer, REQUEST_TIMEOUT},
+    types::SyncError,
 };
 
-static GITHUB_SYNC_RECORDS_KEY: &str = "records";
-static GITHUB_SYNC_POST_KEY: &str = "post";
-pub struct GithubSyncer {
-    /// path is required for the first time sync
-    path: Option<String>,
-    repository: Option<String>,
-    client: reqwest::Client,
-    ctx: Context,
-}
+pub mod auth;
+mod git_data;
+mod pull_request;
+mod retry;
 
-#[async_trait]
-impl SyncAction for GithubSyncer {
-    async fn push_create(
-        &mut self,
-        post: &Post,
-        mongo_db: mongodb::Database,
-    ) -> Result<(), super::SyncError> {
-        if self.path.is_none() || self.repository.is_none() {
-            return Err(GithubSyncError::UserError(
-                "path and repository is required for the first time sync".to_string(),
-            )
-            .into());
-        }
-        let repo = self.repository.clone().unwrap();
-        let path = self.path.clone().unwrap();
-        let url = format!("https://api.github.com/repos/{repo}/contents/{path}");
-        let param = CreateContentParam::new(&format!("create {}", path), &package(post)?);
-        let resp = self.client.put(url).json(&param).send().await?;
-        if !resp.status().is_success() {
-            error!("push create error: {}", resp.text().await?);
-            return Err(SyncError::RemoteServer);
-        }
-        let resp = resp.json::<WriteContentResp>().await?;
-        GithubRecordCreator(InsertableGithubRecord::new(
-            post.post_id(),
-            post.version().to_string(),
-            resp.content.path,
-            resp.content.sha,
-            repo.clone(),
-            resp.content.html_url,
-        ))
-        .execute(mongo_db.clone())
-        .await?;
-        Ok(())
-    }
+static DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
 
-    async fn push_update(
-        &mut self,
-        post: &Post,
-        mongo_db: mongodb::Database,
-    ) -> Result<(), super::SyncError> {
-        let records = self
-            .get_github_sync_records(post.post_id(), mongo_db.clone())
-            .await?;
-        let record = records.unwrap().first().unwrap().clone();
-        let url = format!(
-            "https://api.github.com/repos/{repo}/contents/{path}",
-            repo = record.repository(),
-            path = record.path()
-        );
-        let req = UpdateContentParam::new(
-            &format!("update {}", record.path()),
-            &package(post)?,
-            record.sha(),
-        );
-        let resp = self.client.put(url).json(&req).send().await?;
-        if !resp.status().is_success() {
-            error!("push update error: {}", resp.text().await?);
-            return Err(SyncError::RemoteServer);
-        }
-        let resp: WriteContentResp = resp.json().await?;
-        GithubRecordCreator(InsertableGithubRecord::new(
-            post.post_id(),
-            post.version().to_string(),
-            resp.content.path,
-            resp.content.sha,
-            record.repository().to_string(),
-            resp.content.html_url,
-        ))
-        .execute(mongo_db.clone())
-        .await?;
-        Ok(())
-    }
+pub type GithubSyncer = ForgeSyncer<GithubApi>;
 
-    async fn pull(
-        &mut self,
-        post: &Post,
-        mongo_db: mongodb::Database,
-    ) -> Result<Option<Post>, super::SyncError> {
-        let content = self
-            .get_github_post(post.post_id(), mongo_db.clone())
-            .await?;
-        if content.is_none() {
-            return Ok(None);
-        }
-        let content = content.unwrap();
-        let res = extract(&content.content)?;
-        let title = if let Some(title) = res.title {
-            title
-        } else {
-            post.title().to_string()
-        };
-        let version = utils::sha_utils::sha_post2(&title, &res.metadata, &res.content);
-        let post = Post::new(
-            utils::snowflake::next_id(),
-            post.post_id(),
-            title,
-            res.metadata,
-            res.content,
-            version,
-            post.version().to_string(),
-            utils::time_utils::now(),
-            utils::time_utils::now(),
-        );
-        let records = PostLatestSyncRecordQueryer(post.post_id())
-            .execute(mongo_db.clone())
-            .await?;
-        let repo = records
-            .into_iter()
-            .filter(|record| matches!(record, SyncRecord::Github(_)))
-            .last()
-            .map(|r| match r {
-                SyncRecord::Github(r) => r.repository().to_string(),
-                _ => "".to_string(),
-            });
-        let repo = if let Some(repo) = repo {
-            repo.to_string()
-        } else if let Some(repo) = self.repository.clone() {
-            repo
-        } else {
-            return Err(SyncError::UserError("Not specify repository".to_string()));
-        };
-        GithubRecordCreator(InsertableGithubRecord::new(
-            post.post_id(),
-            post.version().to_string(),
-            content.path.clone(),
-            content.sha.clone(),
-            repo,
-            content.html_url.clone(),
+impl GithubSyncer {
+    pub fn new(
+        path: Option<String>,
+        repository: Option<String>,
+        api_url: Option<String>,
+        mode: GithubWriteMode,
+        pull_request: bool,
+        commit: CommitOptions,
+        auth: GithubAuth,
+    ) -> Result<GithubSyncer, ForgeSyncError> {
+        Ok(ForgeSyncer::with_api(
+            path,
+            repository,
+            GithubApi::new(api_url, auth)?
+                .with_mode(mode)
+                .with_pull_request(pull_request)
+                .with_commit_options(commit),
         ))
-        .execute(mongo_db.clone())
-        .await?;
-
-        Ok(Some(post))
     }
+}
 
-    async fn check_changed(
-        &mut self,
-        post: &Post,
-        mongo_db: mongodb::Database,
-    ) -> Result<(bool, bool, bool), super::SyncError> {
-        let records = self
-            .get_github_sync_records(post.post_id(), mongo_db.clone())
-            .await?;
-        if records.is_none() {
-            return Ok((false, false, true));
-        }
-        let records = records.unwrap();
-        let first = records.first().unwrap();
+/// github repository contents api
+pub struct GithubApi {
+    /// e.g. `https://api.github.com` or `https://{host}/api/v3` for github enterprise server
+    base_url: String,
+    client: reqwest::Client,
+    /// token of every request, see `send`
+    auth: Arc<dyn TokenProvider>,
+    mode: GithubWriteMode,
+    /// local images referenced by articles, see `LETTERMAN_ASSETS_DIR` env
+    assets_dir: Option<PathBuf>,
+    /// publish through pull requests rather than committing to the default branch
+    pull_request: bool,
+    /// branch of the post being synchronized, set by `prepare` in pull request mode
+    review: Option<ReviewBranch>,
+    commit: CommitOptions,
+    retry: RetryPolicy,
+    /// timeout of every request, see `send`
+    timeout: Duration,
+}
 
-        match post.create_time().cmp(first.create_time()) {
-            std::cmp::Ordering::Greater => Ok((false, true, false)),
-            std::cmp::Ordering::Equal => Ok((true, false, false)),
-            std::cmp::Ordering::Less => Ok((false, false, false)),
-        }
-    }
+/// `GITHUB_API_URL` env or `https://api.github.com`
+pub fn default_api_url() -> String {
+    std::env::var("GITHUB_API_URL").unwrap_or(DEFAULT_GITHUB_API_URL.to_string())
 }
 
-impl GithubSyncer {
-    pub fn new(
-        path: Option<String>,
-        repository: Option<String>,
-    ) -> Result<GithubSyncer, GithubSyncError> {
+impl GithubApi {
+    /// the api url is `GITHUB_API_URL` env or `https://api.github.com` by default.
+    /// A different url can be specified per request only if it's listed in
+    /// `GITHUB_ALLOWED_API_URLS` env (separated by comma), because the token is sent to it.
+    pub fn new(api_url: Option<String>, auth: GithubAuth) -> Result<GithubApi, ForgeSyncError> {
+        let default_url = default_api_url();
+        let base_url = match api_url {
+            Some(url) if !is_allowed_api_url(&url, &default_url) => {
+                return Err(ForgeSyncError::UserError(format!(
+                    "github api url {} is not allowed",
+                    url
+                )))
+            }
+            Some(url) => url,
+            None => default_url,
+        };
+        GithubApi::with_base_url(&base_url, auth)
+    }
+
+    pub fn with_base_url(base_url: &str, auth: GithubAuth) -> Result<GithubApi, ForgeSyncError> {
+        if reqwest::Url::parse(base_url).is_err() {
+            return Err(ForgeSyncError::UserError(format!(
+                "invalid github api url: {}",
+                base_url
+            )));
+        }
         let mut header_map = header::HeaderMap::new();
         header_map.insert(
             header::USER_AGENT,
             header::HeaderValue::from_static("letterman"),
         );
-        let token = std::env::var("GITHUB_TOKEN");
-        if token.is_err() {
-            return Err(GithubSyncError::NoToken);
-        }
-        let token = token.unwrap();
-        header_map.insert(
-            header::AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
-        );
         header_map.insert(
             header::ACCEPT,
             HeaderValue::from_static("application/vnd.github+json"),
         );
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(3))
+            .timeout(REQUEST_TIMEOUT)
             .default_headers(header_map)
             .build()?;
-        Ok(GithubSyncer {
-            path,
-            repository,
+        Ok(GithubApi {
+            base_url: base_url.trim_end_matches('/').to_string(),
             client,
-            ctx: Context::new(),
+            auth: auth.provider(base_url)?,
+            mode: GithubWriteMode::default(),
+            assets_dir: std::env::var("LETTERMAN_ASSETS_DIR")
+                .ok()
+                .map(PathBuf::from),
+            pull_request: false,
+            review: None,
+            commit: CommitOptions::from_env(),
+            retry: RetryPolicy::default(),
+            timeout: REQUEST_TIMEOUT,
         })
     }
 
-    async fn get_github_sync_records(
-        &mut self,
-        post_id: i64,
-        mongo_db: mongodb::Database,
-    ) -> Result<Option<Vec<GithubRecord>>, MongoActionError<QueryGithubRecordError>> {
-        // 定义一个局部作用域，以限制可变借用的范围
-        let records: Option<Vec<GithubRecord>> = {
-            // 仅当需要插入记录时，才进行可变借用
-            let records: Option<Vec<GithubRecord>> = self.ctx.get(GITHUB_SYNC_RECORDS_KEY);
-            records
-        };
-        {
-            if records.is_none() {
-                let new_records = GithubRecordQueryerByPostId(post_id)
-                    .execute(mongo_db.clone())
-                    .await?;
-                if !new_records.is_empty() {
-                    self.ctx
-                        .set(GITHUB_SYNC_RECORDS_KEY.to_string(), new_records);
-                }
-            }
-        }
-        let records: Option<Vec<GithubRecord>> = self.ctx.get(GITHUB_SYNC_RECORDS_KEY);
-        Ok(records)
+    pub fn with_mode(self, mode: GithubWriteMode) -> GithubApi {
+        GithubApi { mode, ..self }
     }
 
-    async fn get_github_post(
-        &mut self,
-        post_id: i64,
-        mongo_db: mongodb::Database,
-    ) -> Result<Option<GithubArticleRecord>, SyncError> {
-        {
-            let post: Option<&GithubArticleRecord> = self.ctx.get(GITHUB_SYNC_POST_KEY);
-            if let Some(post) = post {
-                return Ok(Some(post.clone()));
-            }
+    pub fn with_pull_request(self, pull_request: bool) -> GithubApi {
+        GithubApi {
+            pull_request,
+            ..self
         }
+    }
 
-        let records = self
-            .get_github_sync_records(post_id, mongo_db.clone())
-            .await?;
-        if records.is_none() {
-            return Ok(None);
-        }
-        let records = records.unwrap();
-        let record = records.first().unwrap();
-        let url = format!(
-            "https://api.github.com/repos/{repo}/contents/{path}",
-            repo = record.repository(),
-            path = record.path()
-        );
-        let resp = self.client.get(url).send().await?;
-        if !resp.status().is_success() {
-            error!("get github article error: {}", resp.text().await?);
-            return Err(SyncError::RemoteServer);
+    /// the options of the request, the global ones are used for the missing options
+    pub fn with_commit_options(self, commit: CommitOptions) -> GithubApi {
+        GithubApi {
+            commit: commit.or(self.commit),
+            ..self
         }
-        let content = resp.json::<GithubArticleRecord>().await?.decode_content()?;
-        {
-            self.ctx.set(GITHUB_SYNC_POST_KEY.to_string(), content);
+    }
+
+    /// attach the pull request of the branch, if the file isn't written to the default branch
+    async fn review(
+        &self,
+        repo: &str,
+        branch: Option<&str>,
+        mut file: ForgeFile,
+    ) -> Result<ForgeFile, SyncError> {
+        if let Some(branch) = branch {
+            file.pull_request = Some(self.open_pull_request(repo, branch).await?);
         }
+        Ok(file)
+    }
 
-        Ok(Some(
-            self.ctx
-                .get::<GithubArticleRecord>(GITHUB_SYNC_POST_KEY)
-                .unwrap()
-                .clone(),
-        ))
+    fn content_url(&self, repo: &str, path: &str) -> String {
+        format!("{}/repos/{repo}/contents/{path}", self.base_url)
     }
 }
 
-#[derive(Debug)]
-struct ExtractResult {
-    title: Option<String>,
-    content: String,
-    metadata: HashMap<String, String>,
+/// fetch the state of the latest pull request of the post, and store it in the record
+pub(crate) async fn refresh_pull_request(
+    api_url: Option<String>,
+    auth: GithubAuth,
+    post_id: i64,
+    mongo_db: mongodb::Database,
+) -> Result<PullRequest, SyncError> {
+    let api = GithubApi::new(api_url, auth)?;
+    let records = GithubRecordQueryerByPostId(post_id)
+        .execute(mongo_db.clone())
+        .await?;
+    let (record, pull_request) = records
+        .iter()
+        .find_map(|record| record.pull_request().map(|pr| (record, pr)))
+        .ok_or(SyncError::UserError(
+            "the post is not published through a pull request".to_string(),
+        ))?;
+    let pull_request = api
+        .get_pull_request(record.repository(), pull_request.number)
+        .await?;
+    GithubRecordPullRequestUpdater(record.id().to_string(), pull_request.clone())
+        .execute(mongo_db)
+        .await?;
+    Ok(pull_request)
+}
+
+fn is_allowed_api_url(url: &str, default_url: &str) -> bool {
+    let url = url.trim_end_matches('/');
+    url == default_url.trim_end_matches('/')
+        || std::env::var("GITHUB_ALLOWED_API_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .any(|allowed| allowed.trim().trim_end_matches('/') == url)
 }
 
-/// package markdown content with metadata
-fn package(post: &Post) -> Result<String, serde_yaml::Error> {
-    let mut metadata = HashMap::new();
+#[async_trait]
+impl ForgeApi for GithubApi {
+    type Record = GithubRecord;
 
-    metadata.insert("title".to_string(), post.title().to_string());
-    metadata.extend(post.metadata().clone());
-    let frontmatter = serde_yaml::to_string(&metadata)?;
-    let content = format!("---\n{}\n---\n{}", frontmatter, post.content());
-    Ok(content)
-}
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
 
-/// extract metadata from content
-/// return (title, content, metadata)
-fn extract(content: &str) -> Result<ExtractResult, markdown::message::Message> {
-    let constructs = Constructs {
-        frontmatter: true,
-        ..Constructs::default()
-    };
-    let ast = markdown::to_mdast(
-        content,
-        &markdown::ParseOptions {
-            constructs,
-            ..markdown::ParseOptions::default()
-        },
-    )?;
-    // TODO: 无法解析数组格式的YAML
-    let content = {
-        if let Some(idx) = content.find("---") {
-            if let Some(idx2) = content[idx + 3..].find("---") {
-                &content[idx + idx2 + 6..]
-            } else {
-                content
-            }
+    async fn create_file(
+        &self,
+        repo: &str,
+        path: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError> {
+        let branch = self.write_branch(repo).await?;
+        let file = if self.mode == GithubWriteMode::GitData {
+            self.commit_article(repo, branch.as_deref(), path, None, message, content)
+                .await?
         } else {
-            content
+            let param = CreateContentParam::new(message, content)
+                .on_branch(branch.as_deref())
+                .signed_by(&self.commit);
+            let resp = self
+                .send(self.client.put(self.content_url(repo, path)).json(&param))
+                .await?;
+            let resp = ensure_success(resp, "push create").await?;
+            resp.json::<WriteContentResp>().await?.into()
+        };
+        self.review(repo, branch.as_deref(), file).await
+    }
+
+    async fn update_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError> {
+        let branch = self.write_branch(repo).await?;
+        let file = if self.mode == GithubWriteMode::GitData {
+            self.commit_article(repo, branch.as_deref(), path, Some(sha), message, content)
+                .await?
+        } else {
+            let param = UpdateContentParam::new(message, content, sha)
+                .on_branch(branch.as_deref())
+                .signed_by(&self.commit);
+            let resp = self
+                .send(self.client.put(self.content_url(repo, path)).json(&param))
+                .await?;
+            let resp = ensure_success(resp, "push update").await?;
+            resp.json::<WriteContentResp>().await?.into()
+        };
+        self.review(repo, branch.as_deref(), file).await
+    }
+
+    async fn delete_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+    ) -> Result<(), SyncError> {
+        let branch = self.write_branch(repo).await?;
+        let param = DeleteContentParam::new(message, sha)
+            .on_branch(branch.as_deref())
+            .signed_by(&self.commit);
+        let resp = self
+            .send(
+                self.client
+                    .delete(self.content_url(repo, path))
+                    .json(&param),
+            )
+            .await?;
+        ensure_success(resp, "delete github article").await?;
+        if let Some(branch) = branch {
+            self.open_pull_request(repo, &branch).await?;
         }
-    };
+        Ok(())
+    }
 
-    if let Some(children) = ast.children() {
-        let mut metadata = HashMap::new();
-        for child in children {
-            let map = extract_metadata(child);
-            if let Some(map) = map {
-                metadata.extend(map);
-                break;
-            }
+    fn commit_message(&self, change: &Change) -> String {
+        match &self.commit.message {
+            Some(template) => change.render(template),
+            None => change.default_message(),
         }
-        let title = metadata.get("title").map(|t| t.to_string());
-        metadata.remove("title");
-        Ok(ExtractResult {
-            title,
-            content: content.to_string(),
-            metadata,
-        })
-    } else {
-        Ok(ExtractResult {
-            title: None,
-            content: content.to_string(),
-            metadata: HashMap::new(),
-        })
     }
-}
 
-fn extract_metadata(node: &Node) -> Option<HashMap<String, String>> {
-    if let Node::Yaml(markdown::mdast::Yaml { value, .. }) = node {
-        let mut metadata = HashMap::new();
-        let lines = value.split('\n');
-        for line in lines {
-            let mut split = line.splitn(2, ':'); // 使用 splitn 限制拆分次数为 2
-            if let (Some(key), Some(value)) = (split.next(), split.next()) {
-                metadata.insert(key.trim().to_string(), value.trim().to_string());
-            }
+    async fn prepare(&mut self, post: &Post, records: &[GithubRecord]) -> Result<(), SyncError> {
+        if self.pull_request {
+            self.prepare_review(post, records).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_file(&self, repo: &str, path: &str) -> Result<ForgeFile, SyncError> {
+        let mut req = self.client.get(self.content_url(repo, path));
+        if let Some(branch) = self.read_branch() {
+            req = req.query(&[("ref", branch)]);
         }
-        Some(metadata)
-    } else {
-        None
+        let resp = self.send(req).await?;
+        let resp = ensure_success(resp, "get github article").await?;
+        let mut article = resp.json::<GithubArticleRecord>().await?;
+        // the contents api leaves out the content of files larger than 1 MB
+        if article.encoding == "none" {
+            let blob = self.get_blob(repo, &article.sha).await?;
+            article.content = blob.content;
+            article.encoding = blob.encoding;
+        }
+        Ok(article.decode_content()?.into())
+    }
+
+    async fn query_records(
+        &self,
+        post_id: i64,
+        mongo_db: mongodb::Database,
+    ) -> Result<Vec<GithubRecord>, SyncError> {
+        Ok(GithubRecordQueryerByPostId(post_id)
+            .execute(mongo_db.clone())
+            .await?)
+    }
+
+    async fn create_record(
+        &self,
+        post: &Post,
+        repo: &str,
+        file: &ForgeFile,
+        previous_path: Option<&str>,
+        target: Option<&SyncReq>,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        GithubRecordCreator(
+            InsertableGithubRecord::new(
+                post.post_id(),
+                post.version().to_string(),
+                file.path.clone(),
+                file.sha.clone(),
+                repo.to_string(),
+                file.url.clone(),
+            )
+            .moved_from(previous_path)
+            .for_target(target)
+            .committed(file.commit.as_deref())
+            .with_pull_request(file.pull_request.clone()),
+        )
+        .execute(mongo_db.clone())
+        .await?;
+        Ok(())
+    }
+
+    async fn create_tombstone(
+        &self,
+        record: &GithubRecord,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        GithubRecordCreator(InsertableGithubRecord::tombstone(record))
+            .execute(mongo_db.clone())
+            .await?;
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone, Error)]
-pub enum GithubSyncError {
-    #[error("Network Error")]
-    NetworkError(String),
-    #[error("Unknown Error: {0}")]
-    Other(String),
-    #[error("User Error: {0}")]
-    UserError(String),
-    #[error("Please set GITHUB_TOKEN env if you want to use github synchronize")]
-    NoToken,
-    #[error("Post not found")]
-    NotFound,
+impl From<WriteContentResp> for ForgeFile {
+    fn from(item: WriteContentResp) -> Self {
+        ForgeFile {
+            path: item.content.path,
+            sha: item.content.sha,
+            url: item.content.html_url,
+            content: String::new(),
+            commit: item.commit.map(|commit| commit.sha),
+            pull_request: None,
+        }
+    }
 }
 
-impl From<reqwest::Error> for GithubSyncError {
-    fn from(item: reqwest::Error) -> Self {
-        GithubSyncError::NetworkError(item.to_string())
+impl From<GithubArticleRecord> for ForgeFile {
+    fn from(item: GithubArticleRecord) -> Self {
+        ForgeFile {
+            path: item.path,
+            sha: item.sha,
+            url: item.html_url,
+            content: item.content,
+            commit: None,
+            pull_request: None,
+        }
     }
 }
 
@@ -444,24 +441,24 @@ impl From<CreateGithubRecordError> for SyncError {
     }
 }
 
+impl From<MongoActionError<UpdateGithubRecordError>> for SyncError {
+    fn from(value: MongoActionError<UpdateGithubRecordError>) -> Self {
+        match value {
+            MongoActionError::Error(UpdateGithubRecordError::Database(_))
+            | MongoActionError::Pool(_) => SyncError::Database,
+            MongoActionError::Error(UpdateGithubRecordError::InvalidId) => {
+                SyncError::Other("invalid github record id".to_string())
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 pub enum CreateGithubRecordError {
     #[error("Database error")]
     Database(#[source] mongodb::error::Error),
 }
 
-impl From<GithubSyncError> for SyncError {
-    fn from(val: GithubSyncError) -> Self {
-        match val {
-            GithubSyncError::NetworkError(e) => SyncError::NetworkError(e),
-            GithubSyncError::Other(e) => SyncError::Other(e),
-            GithubSyncError::UserError(e) => SyncError::UserError(e),
-            GithubSyncError::NotFound => SyncError::NotFound,
-            GithubSyncError::NoToken => SyncError::UserError(val.to_string()),
-        }
-    }
-}
-
 impl From<markdown::message::Message> for SyncError {
     fn from(value: markdown::message::Message) -> Self {
         SyncError::Other(format!("failed to parse markdown: {}", value))
@@ -494,152 +491,519 @@ impl From<MongoActionError<QuerySyncRecordError>> for SyncError {
 
 #[cfg(test)]
 mod github_sync_test {
-    use std::env;
+    use base64::Engine;
 
     use crate::traits::DbAction;
+    use diesel::{r2d2::ConnectionManager, MysqlConnection};
+    use r2d2::Pool;
+
     use crate::{
         database_pool, mongodb_database,
-        operations::{posts::LatestPostQueryerByPostId, remote::synchronize},
-        types::github_record::GithubArticleRecord,
+        operations::{
+            posts::{LatestPostQueryerByPostId, PostDirectCreator},
+            remote::{mock_github, synchronize, types::Context, SyncAction},
+        },
+        types::github_record::{DecodeError, GithubArticleRecord},
     };
 
     use super::*;
+    use crate::{operations::remote::commit_message::ChangeKind, utils};
+
+    fn mock_api(base_url: &str) -> GithubApi {
+        GithubApi::with_base_url(base_url, GithubAuth::Token(Some("test-token".to_string())))
+            .unwrap()
+    }
+
+    /// a new post saved to the database, so the tests don't depend on the existing posts
+    async fn saved_post(
+        pool: Pool<ConnectionManager<MysqlConnection>>,
+    ) -> Result<Post, Box<dyn std::error::Error>> {
+        let post_id = utils::snowflake::next_id();
+        PostDirectCreator(Post::new(
+            0,
+            post_id,
+            "Hello".to_string(),
+            Default::default(),
+            "world".to_string(),
+            format!("{:x}", post_id),
+            String::new(),
+            utils::time_utils::now(),
+            utils::time_utils::now(),
+        ))
+        .execute(pool.clone())
+        .await?;
+        Ok(LatestPostQueryerByPostId(post_id).execute(pool).await?)
+    }
 
     #[actix_web::test]
+    #[ignore = "requires MySQL and MongoDB"]
     async fn github_syncer_test() -> Result<(), Box<dyn std::error::Error>> {
         dotenv::dotenv().ok();
         let pool = database_pool()?;
         let db = mongodb_database().await?;
-        let syncer = GithubSyncer::new(
-            Some("README.md".to_string()),
-            Some("ZephyrZenn/test-repo".to_string()),
-        )?;
-        synchronize(
-            Box::new(syncer),
-            7183026894152011778,
-            pool.clone(),
-            db.clone(),
-        )
-        .await?;
+        let (base_url, mock) = mock_github::start().await;
+        let post = saved_post(pool.clone()).await?;
+        let syncer = GithubSyncer::with_api(
+            Some("posts/hello.md".to_string()),
+            Some("letterman/test-repo".to_string()),
+            mock_api(&base_url),
+        );
+        synchronize(Box::new(syncer), post.post_id(), pool.clone(), db.clone()).await?;
+        let file = mock.file("letterman/test-repo", "posts/hello.md").unwrap();
+        assert!(file.content.contains("world"));
         Ok(())
     }
 
     #[actix_web::test]
     async fn get_content_test() {
-        dotenv::dotenv().ok();
-        let github_token = env::var("GITHUB_TOKEN").unwrap_or_default();
-        let client = reqwest::Client::new();
-        let url = "https://api.github.com/repos/ZephyrZenn/letterman/contents/README.md";
-        let resp = client
+        let (base_url, mock) = mock_github::start().await;
+        let api = mock_api(&base_url);
+        api.create_file("letterman/test-repo", "README.md", "create", "# README")
+            .await
+            .unwrap();
+        let url = format!("{base_url}/repos/letterman/test-repo/contents/README.md");
+        let resp = reqwest::Client::new()
             .get(url)
             .header("User-Agent", "letterman")
             .header("accept", "application/vnd.github+json")
-            .header("Authorization", github_token)
             .send()
             .await
             .unwrap();
-        // let content = resp.text().await.unwrap();
-        // println!("{:#?}", content);
         let content = resp.json::<GithubArticleRecord>().await.unwrap();
         let content = content.decode_content().unwrap();
-        println!("{:?}", content);
+        assert_eq!(content.content, "# README");
+        assert_eq!(
+            content.sha,
+            mock.file("letterman/test-repo", "README.md").unwrap().sha
+        );
     }
 
     #[actix_web::test]
-    async fn pull_test() -> Result<(), Box<dyn std::error::Error>> {
-        dotenv::dotenv().ok();
-        let pool = database_pool()?;
-        let db = mongodb_database().await?;
-        let mut syncer = GithubSyncer::new(None, None)?;
-        let post = LatestPostQueryerByPostId(7183657854551855106)
-            .execute(pool.clone())
-            .await?;
-        let remote_post = syncer.pull(&post, db.clone()).await?;
-        if let Some(remote_post) = remote_post {
-            assert!(!remote_post.metadata().is_empty())
-        }
-        Ok(())
+    async fn get_large_file_test() {
+        let (base_url, _mock) = mock_github::start().await;
+        let api = mock_api(&base_url);
+        let repo = "letterman/test-repo";
+        // the contents api leaves out the content of files larger than 1 MB
+        let content = "letterman\n".repeat(110 * 1024);
+        api.create_file(repo, "large.md", "create", &content)
+            .await
+            .unwrap();
+        let file = api.get_file(repo, "large.md").await.unwrap();
+        assert_eq!(file.content, content);
     }
 
     #[test]
-    fn markdown_extract() {
-        let content = "---\na:b\n---\ncontent";
-        let constructs = Constructs {
-            frontmatter: true,
-            ..Constructs::default()
+    fn decode_non_utf8_test() {
+        let record = GithubArticleRecord {
+            name: "logo.png".to_string(),
+            path: "images/logo.png".to_string(),
+            content: base64::prelude::BASE64_STANDARD.encode([0x89, 0x50, 0x4e, 0x47, 0xff]),
+            sha: "sha".to_string(),
+            url: String::new(),
+            encoding: "base64".to_string(),
+            html_url: String::new(),
         };
-        let ast = markdown::to_mdast(
-            content,
-            &markdown::ParseOptions {
-                constructs,
-                ..markdown::ParseOptions::default()
-            },
+        let error = record.decode_content().unwrap_err();
+        assert!(matches!(error, DecodeError::NotText(path) if path == "images/logo.png"));
+    }
+
+    #[actix_web::test]
+    async fn contents_api_test() {
+        let (base_url, mock) = mock_github::start().await;
+        let api = mock_api(&base_url);
+        let repo = "letterman/test-repo";
+        let path = "source/_posts/hello.md";
+
+        let created = api
+            .create_file(repo, path, "create", "hello")
+            .await
+            .unwrap();
+        assert_eq!(created.path, path);
+        assert_eq!(mock.file(repo, path).unwrap().content, "hello");
+
+        // creating an existing file without sha is rejected
+        assert!(api
+            .create_file(repo, path, "create", "hello")
+            .await
+            .is_err());
+
+        let updated = api
+            .update_file(repo, path, &created.sha, "update", "world")
+            .await
+            .unwrap();
+        assert_ne!(created.sha, updated.sha);
+
+        // the sha of the first version is outdated now
+        assert!(api
+            .update_file(repo, path, &created.sha, "update", "again")
+            .await
+            .is_err());
+
+        let file = api.get_file(repo, path).await.unwrap();
+        assert_eq!(file.content, "world");
+        assert_eq!(file.sha, updated.sha);
+        assert!(api.get_file(repo, "missing.md").await.is_err());
+
+        // the sha of the first version is outdated, the file is kept
+        assert!(api
+            .delete_file(repo, path, &created.sha, "delete")
+            .await
+            .is_err());
+        api.delete_file(repo, path, &updated.sha, "delete")
+            .await
+            .unwrap();
+        assert!(mock.file(repo, path).is_none());
+        assert!(api.get_file(repo, path).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn git_data_test() {
+        let (base_url, mock) = mock_github::start().await;
+        let assets_dir = std::env::temp_dir().join(format!(
+            "letterman-assets-{}",
+            crate::utils::snowflake::next_id()
+        ));
+        std::fs::create_dir_all(assets_dir.join("posts/images")).unwrap();
+        std::fs::write(assets_dir.join("posts/images/a.png"), "png").unwrap();
+        let api = GithubApi {
+            assets_dir: Some(assets_dir.clone()),
+            ..mock_api(&base_url).with_mode(GithubWriteMode::GitData)
+        };
+        let repo = "letterman/test-repo";
+        let path = "posts/hello.md";
+
+        let created = api
+            .create_file(repo, path, "create", "![a](images/a.png)")
+            .await
+            .unwrap();
+        assert!(created.commit.is_some());
+        assert_eq!(created.sha, mock.file(repo, path).unwrap().sha);
+        assert_eq!(
+            mock.file(repo, "posts/images/a.png").unwrap().content,
+            "png"
+        );
+        // the article and the image are written in one commit
+        assert_eq!(mock.git.lock().unwrap().commits.len(), 1);
+
+        // the article exists already
+        assert!(api
+            .create_file(repo, path, "create", "hello")
+            .await
+            .is_err());
+
+        let updated = api
+            .update_file(repo, path, &created.sha, "update", "hello")
+            .await
+            .unwrap();
+        assert_ne!(created.commit, updated.commit);
+        assert_eq!(mock.file(repo, path).unwrap().content, "hello");
+        assert!(api
+            .update_file(repo, path, &created.sha, "update", "again")
+            .await
+            .is_err());
+        assert_eq!(mock.git.lock().unwrap().commits.len(), 2);
+        std::fs::remove_dir_all(assets_dir).unwrap();
+    }
+
+    fn pull_request_record(post: &Post, repo: &str, file: &ForgeFile) -> GithubRecord {
+        use crate::traits::DocumentConvert;
+        let mut doc = InsertableGithubRecord::new(
+            post.post_id(),
+            post.version().to_string(),
+            file.path.clone(),
+            file.sha.clone(),
+            repo.to_string(),
+            file.url.clone(),
         )
-        .unwrap();
+        .with_pull_request(file.pull_request.clone())
+        .to_doc();
+        doc.insert("_id", mongodb::bson::oid::ObjectId::new());
+        mongodb::bson::from_document(doc).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn pull_request_test() {
+        use crate::types::pull_requests::PullRequestState;
+
+        let (base_url, mock) = mock_github::start().await;
+        let repo = "letterman/test-repo";
+        let path = "posts/hello.md";
+        let main = mock_api(&base_url)
+            .create_file(repo, path, "create", "hello")
+            .await
+            .unwrap();
+        let post = Post::new(
+            1,
+            1,
+            "Hello".to_string(),
+            Default::default(),
+            "world".to_string(),
+            "0a1b2c3d4e5f".to_string(),
+            String::new(),
+            Default::default(),
+            Default::default(),
+        );
 
-        println!("{:#?}", ast);
+        let mut api = mock_api(&base_url).with_pull_request(true);
+        api.prepare(&post, &[]).await.unwrap();
+        let updated = api
+            .update_file(repo, path, &main.sha, "update", "world")
+            .await
+            .unwrap();
+        let pull_request = updated.pull_request.clone().unwrap();
+        assert_eq!(pull_request.state, PullRequestState::Open);
+        assert_eq!(pull_request.branch, "letterman/1-hello-0a1b2c3d");
+        // the default branch is untouched until the pull request is merged
+        assert_eq!(mock.file(repo, path).unwrap().content, "hello");
+        assert_eq!(
+            mock.file_on(repo, &pull_request.branch, path)
+                .unwrap()
+                .content,
+            "world"
+        );
+
+        // the next sync pushes to the open pull request
+        let records = [pull_request_record(&post, repo, &updated)];
+        let mut api = mock_api(&base_url).with_pull_request(true);
+        api.prepare(&post, &records).await.unwrap();
+        let file = api.get_file(repo, path).await.unwrap();
+        assert_eq!(file.content, "world");
+        let again = api
+            .update_file(repo, path, &file.sha, "update", "again")
+            .await
+            .unwrap();
+        assert_eq!(again.pull_request.unwrap().number, pull_request.number);
+        assert_eq!(mock.pulls.lock().unwrap().len(), 1);
+
+        mock.merge(pull_request.number);
+        let merged = api
+            .get_pull_request(repo, pull_request.number)
+            .await
+            .unwrap();
+        assert_eq!(merged.state, PullRequestState::Merged);
+        // a merged pull request is not pushed to any more
+        let mut api = mock_api(&base_url).with_pull_request(true);
+        api.prepare(&post, &records).await.unwrap();
+        assert!(api.read_branch().is_none());
     }
 
     #[actix_web::test]
-    async fn context_test() -> Result<(), Box<dyn std::error::Error>> {
-        dotenv::dotenv().ok();
-        let db = mongodb_database().await?;
-        {
-            let mut ctx = Context::new();
-            let new_records: Vec<GithubRecord> = GithubRecordQueryerByPostId(7191634464299159554)
-                .execute(db.clone())
-                .await?;
-            if !new_records.is_empty() {
-                ctx.set(GITHUB_SYNC_RECORDS_KEY.to_string(), new_records);
-            }
-            let records: Option<Vec<GithubRecord>> = ctx.get(GITHUB_SYNC_RECORDS_KEY);
-            assert!(records.is_some())
+    async fn commit_options_test() {
+        use crate::types::commits::GitIdentity;
+
+        let (base_url, mock) = mock_github::start().await;
+        let repo = "letterman/test-repo";
+        let path = "posts/hello.md";
+        let bot = GitIdentity {
+            name: "letterman".to_string(),
+            email: "bot@example.com".to_string(),
+        };
+        let api = mock_api(&base_url).with_commit_options(CommitOptions {
+            message: Some("{action} {title} (+{additions} -{deletions})".to_string()),
+            branch: Some("blog".to_string()),
+            committer: Some(bot.clone()),
+            author: None,
+        });
+        // the branch must exist
+        assert!(api
+            .create_file(repo, path, "create", "hello")
+            .await
+            .is_err());
+        mock.git
+            .lock()
+            .unwrap()
+            .heads
+            .insert(format!("{repo}/blog"), "initial".to_string());
+
+        let post = Post::new(
+            1,
+            1,
+            "Hello".to_string(),
+            Default::default(),
+            String::new(),
+            String::new(),
+            String::new(),
+            Default::default(),
+            Default::default(),
+        );
+        let change = Change::new(ChangeKind::Create, path, &post).with_stats("", "a\nb\n");
+        let message = api.commit_message(&change);
+        assert_eq!(message, "create Hello (+2 -0)");
+        api.create_file(repo, path, &message, "hello")
+            .await
+            .unwrap();
+        assert!(mock.file(repo, path).is_none());
+        assert_eq!(mock.file_on(repo, "blog", path).unwrap().content, "hello");
+        assert_eq!(api.get_file(repo, path).await.unwrap().content, "hello");
+
+        let commits = mock.commits.lock().unwrap();
+        let commit = commits.last().unwrap();
+        assert_eq!(commit.message, "create Hello (+2 -0)");
+        let committer = commit.committer.as_ref().unwrap();
+        assert_eq!(
+            (&*committer.name, &*committer.email),
+            ("letterman", "bot@example.com")
+        );
+        assert!(commit.author.is_none());
+    }
+
+    #[actix_web::test]
+    async fn retry_test() {
+        let (base_url, mock) = mock_github::start().await;
+        let api = GithubApi {
+            retry: RetryPolicy {
+                max_retries: 2,
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_secs(1),
+            },
+            ..mock_api(&base_url)
+        };
+        let repo = "letterman/test-repo";
+        let path = "posts/hello.md";
+        let created = api
+            .create_file(repo, path, "create", "hello")
+            .await
+            .unwrap();
+
+        // server errors and secondary rate limits are retried
+        mock.fail_next(502, &[]);
+        mock.fail_next(403, &[("retry-after", "0")]);
+        assert_eq!(api.get_file(repo, path).await.unwrap().content, "hello");
+        for _ in 0..3 {
+            mock.fail_next(503, &[]);
+        }
+        assert!(matches!(
+            api.get_file(repo, path).await,
+            Err(SyncError::RemoteServer)
+        ));
+
+        // it doesn't wait an hour for the rate limit
+        let reset = (utils::time_utils::now().and_utc().timestamp() + 3600).to_string();
+        mock.fail_next(
+            403,
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", &reset),
+            ],
+        );
+        match api.get_file(repo, path).await {
+            Err(SyncError::RateLimited { reset_at }) => assert!(reset_at.is_some()),
+            other => panic!("unexpected result: {:?}", other.map(|file| file.sha)),
         }
 
-        Ok(())
+        assert!(matches!(
+            api.get_file(repo, "missing.md").await,
+            Err(SyncError::RemoteNotFound(_))
+        ));
+        assert!(matches!(
+            api.update_file(repo, path, "outdated", "update", "world")
+                .await,
+            Err(SyncError::RemoteConflict(_))
+        ));
+        mock.fail_next(422, &[]);
+        assert!(matches!(
+            api.update_file(repo, path, &created.sha, "update", "world")
+                .await,
+            Err(SyncError::RemoteValidation(_))
+        ));
     }
 
     #[test]
-    fn serialize_metadata_test() {
-        let v = vec![("title", "11")];
-        let mut m = HashMap::new();
-        m.insert("title".to_string(), "111".to_string());
-        println!("{}", serde_yaml::to_string(&v).unwrap());
-        println!("{}", serde_yaml::to_string(&m).unwrap())
+    fn api_url_test() {
+        let api = mock_api("https://github.example.com/api/v3/");
+        assert_eq!(
+            api.content_url("a/b", "c.md"),
+            "https://github.example.com/api/v3/repos/a/b/contents/c.md"
+        );
+        assert!(GithubApi::with_base_url("not a url", GithubAuth::Token(None)).is_err());
+        assert!(is_allowed_api_url(
+            "https://api.github.com/",
+            DEFAULT_GITHUB_API_URL
+        ));
+        assert!(!is_allowed_api_url(
+            "https://evil.example.com",
+            DEFAULT_GITHUB_API_URL
+        ));
     }
 
-    #[test]
-    fn extract_test() {
-        let content: &'static str = "---
-title: Remake | CS50 AI入门笔记
-top: false
-toc: true
-mathjax: true
-date: 2022-07-02 17:02:19
-password:
-summary:
-tags:
-    - 人工智能
-categories:
-    - Remake
----
-
-# Introduction  to  AI
-";
-        let constructs = Constructs {
-            frontmatter: true,
-            ..Constructs::default()
+    #[actix_web::test]
+    #[ignore = "requires MySQL and MongoDB"]
+    async fn pull_test() -> Result<(), Box<dyn std::error::Error>> {
+        dotenv::dotenv().ok();
+        let pool = database_pool()?;
+        let db = mongodb_database().await?;
+        let (base_url, _mock) = mock_github::start().await;
+        let syncer = || {
+            GithubSyncer::with_api(
+                Some("posts/hello.md".to_string()),
+                Some("letterman/test-repo".to_string()),
+                mock_api(&base_url),
+            )
         };
-        let ast = markdown::to_mdast(
-            content,
-            &markdown::ParseOptions {
-                constructs,
-                ..markdown::ParseOptions::default()
-            },
+        let post = saved_post(pool.clone()).await?;
+        synchronize(Box::new(syncer()), post.post_id(), pool.clone(), db.clone()).await?;
+        let remote_post = syncer().pull(&post, db.clone()).await?.unwrap();
+        assert_eq!(remote_post.title(), "Hello");
+        assert_eq!(remote_post.content(), "world");
+        Ok(())
+    }
+
+    #[test]
+    fn context_test() {
+        use crate::traits::DocumentConvert;
+        let mut doc = InsertableGithubRecord::new(
+            1,
+            "v1".to_string(),
+            "posts/hello.md".to_string(),
+            "sha".to_string(),
+            "letterman/test-repo".to_string(),
+            "url".to_string(),
         )
-        .unwrap();
-        println!("{:#?}", ast);
-        // let res = extract(content);
-        // println!("{:#?}", res.unwrap())
+        .to_doc();
+        doc.insert("_id", mongodb::bson::oid::ObjectId::new());
+        let record: GithubRecord = mongodb::bson::from_document(doc).unwrap();
+
+        let mut ctx = Context::new();
+        ctx.set("records".to_string(), vec![record]);
+        let records: Option<Vec<GithubRecord>> = ctx.get("records");
+        assert_eq!(records.unwrap()[0].path(), "posts/hello.md");
+        // the value is only returned as the type it's set with
+        assert!(ctx.get::<GithubRecord>("records").is_none());
+        assert!(ctx.get::<Vec<GithubRecord>>("missing").is_none());
+    }
+
+    #[actix_web::test]
+    async fn github_app_test() {
+        let app = || {
+            auth::GithubApp::new("1234".to_string(), 42, mock_github::MOCK_APP_PRIVATE_KEY).unwrap()
+        };
+        let repo = "letterman/test-repo";
+        let (base_url, mock) = mock_github::start().await;
+        let api = GithubApi::with_base_url(&base_url, GithubAuth::App(app())).unwrap();
+        api.create_file(repo, "a.md", "create", "a").await.unwrap();
+        // the token is shared by the syncers until it's about to expire
+        let api = GithubApi::with_base_url(&base_url, GithubAuth::App(app())).unwrap();
+        api.get_file(repo, "a.md").await.unwrap();
+        assert_eq!(*mock.installation_tokens.lock().unwrap(), vec!["ghs_42_1"]);
+        let authorizations = mock.authorizations.lock().unwrap().clone();
+        assert_eq!(
+            authorizations
+                .iter()
+                .filter(|authorization| *authorization == "Bearer ghs_42_1")
+                .count(),
+            2
+        );
+
+        let (base_url, mock) = mock_github::start().await;
+        *mock.token_ttl.lock().unwrap() = Some(30);
+        let api = GithubApi::with_base_url(&base_url, GithubAuth::App(app())).unwrap();
+        api.create_file(repo, "a.md", "create", "a").await.unwrap();
+        api.get_file(repo, "a.md").await.unwrap();
+        assert_eq!(
+            *mock.installation_tokens.lock().unwrap(),
+            vec!["ghs_42_1", "ghs_42_2"]
+        );
+
+        assert!(auth::GithubApp::new("1234".to_string(), 42, "not a key").is_err());
     }
 }
@@ -0,0 +1,226 @@
+//! authorize the requests to github with a personal access token, or as an installation
+//! of a github app, see
+//! https://docs.github.com/en/apps/creating-github-apps/authenticating-with-a-github-app/authenticating-as-a-github-app-installation
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use lazy_static::lazy_static;
+use reqwest::header::{self, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    operations::remote::{
+        forge::{ensure_success, token_or_env, ForgeSyncError},
+        types::SyncError,
+    },
+    utils,
+};
+
+/// a token which expires within it is refreshed, so it doesn't expire in the middle of a sync
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+lazy_static! {
+    /// installation tokens shared by all syncers, keyed by the app and the access tokens url
+    static ref INSTALLATION_TOKENS: Mutex<HashMap<String, CachedToken>> =
+        Mutex::new(HashMap::new());
+}
+
+/// provides the token of the authorization header
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    /// the requests for the token time out after `timeout`, as the ones of the syncer do
+    async fn token(&self, timeout: Duration) -> Result<String, SyncError>;
+}
+
+/// how the requests to github are authorized, it's picked per target by `SyncerFactory`
+pub enum GithubAuth {
+    /// personal access token, the default is `GITHUB_TOKEN` env
+    Token(Option<String>),
+    /// installation token of the github app
+    App(GithubApp),
+}
+
+impl GithubAuth {
+    pub(super) fn provider(self, base_url: &str) -> Result<Arc<dyn TokenProvider>, ForgeSyncError> {
+        match self {
+            GithubAuth::Token(token) => {
+                let token = token_or_env(token, "GITHUB_TOKEN")?;
+                if HeaderValue::from_str(&format!("Bearer {}", token)).is_err() {
+                    return Err(ForgeSyncError::UserError(
+                        "invalid github token".to_string(),
+                    ));
+                }
+                Ok(Arc::new(PersonalToken(token)))
+            }
+            GithubAuth::App(app) => Ok(Arc::new(InstallationToken::new(app, base_url)?)),
+        }
+    }
+}
+
+struct PersonalToken(String);
+
+#[async_trait]
+impl TokenProvider for PersonalToken {
+    async fn token(&self, _timeout: Duration) -> Result<String, SyncError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// a github app and its installation which has access to the repositories
+#[derive(Clone)]
+pub struct GithubApp {
+    /// the app id or the client id
+    app_id: String,
+    installation_id: i64,
+    private_key: EncodingKey,
+}
+
+impl GithubApp {
+    pub fn new(
+        app_id: String,
+        installation_id: i64,
+        private_key: &str,
+    ) -> Result<GithubApp, ForgeSyncError> {
+        let private_key = EncodingKey::from_rsa_pem(private_key.as_bytes()).map_err(|e| {
+            ForgeSyncError::UserError(format!("invalid github app private key: {}", e))
+        })?;
+        Ok(GithubApp {
+            app_id,
+            installation_id,
+            private_key,
+        })
+    }
+
+    /// the app of `GITHUB_APP_ID`, `GITHUB_APP_PRIVATE_KEY` (or the file of
+    /// `GITHUB_APP_PRIVATE_KEY_PATH`) and `GITHUB_APP_INSTALLATION_ID` envs,
+    /// the installation can be given per target. `None` if the app isn't configured.
+    pub fn from_env(installation_id: Option<i64>) -> Result<Option<GithubApp>, ForgeSyncError> {
+        let app_id = match (std::env::var("GITHUB_APP_ID"), installation_id) {
+            (Ok(app_id), _) => app_id,
+            (Err(_), Some(_)) => return Err(ForgeSyncError::NoToken("GITHUB_APP_ID")),
+            (Err(_), None) => return Ok(None),
+        };
+        let installation_id = match installation_id {
+            Some(installation_id) => installation_id,
+            None => std::env::var("GITHUB_APP_INSTALLATION_ID")
+                .ok()
+                .and_then(|id| id.parse().ok())
+                .ok_or(ForgeSyncError::NoToken("GITHUB_APP_INSTALLATION_ID"))?,
+        };
+        let private_key = match std::env::var("GITHUB_APP_PRIVATE_KEY") {
+            // the line breaks are usually escaped in env files
+            Ok(private_key) => private_key.replace("\\n", "\n"),
+            Err(_) => {
+                let path = std::env::var("GITHUB_APP_PRIVATE_KEY_PATH")
+                    .map_err(|_| ForgeSyncError::NoToken("GITHUB_APP_PRIVATE_KEY"))?;
+                std::fs::read_to_string(&path).map_err(|e| {
+                    ForgeSyncError::UserError(format!("failed to read {}: {}", path, e))
+                })?
+            }
+        };
+        GithubApp::new(app_id, installation_id, &private_key).map(Some)
+    }
+
+    /// the jwt which authenticates as the app, github accepts it for 10 minutes at most
+    fn jwt(&self, now: i64) -> Result<String, SyncError> {
+        let claims = AppClaims {
+            // in case the clock of github is behind
+            iat: now - 60,
+            exp: now + 9 * 60,
+            iss: self.app_id.clone(),
+        };
+        jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.private_key)
+            .map_err(|e| SyncError::Other(format!("failed to sign github app jwt: {}", e)))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AppClaims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessTokenResp {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    token: String,
+    /// in UTC
+    expires_at: NaiveDateTime,
+}
+
+/// the installation token of the app, it's cached until it's about to expire
+struct InstallationToken {
+    app: GithubApp,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl InstallationToken {
+    fn new(app: GithubApp, base_url: &str) -> Result<InstallationToken, ForgeSyncError> {
+        let mut header_map = header::HeaderMap::new();
+        header_map.insert(
+            header::USER_AGENT,
+            header::HeaderValue::from_static("letterman"),
+        );
+        header_map.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        let client = reqwest::Client::builder()
+            .default_headers(header_map)
+            .build()?;
+        Ok(InstallationToken {
+            url: format!(
+                "{}/app/installations/{}/access_tokens",
+                base_url.trim_end_matches('/'),
+                app.installation_id
+            ),
+            app,
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl TokenProvider for InstallationToken {
+    async fn token(&self, timeout: Duration) -> Result<String, SyncError> {
+        let key = format!("{}@{}", self.app.app_id, self.url);
+        let now = utils::time_utils::now();
+        let cached = INSTALLATION_TOKENS.lock().unwrap().get(&key).cloned();
+        if let Some(cached) = cached {
+            if cached.expires_at - now > chrono::Duration::seconds(REFRESH_MARGIN_SECS) {
+                return Ok(cached.token);
+            }
+        }
+        let resp = self
+            .client
+            .post(&self.url)
+            .bearer_auth(self.app.jwt(now.and_utc().timestamp())?)
+            .timeout(timeout)
+            .send()
+            .await?;
+        let resp = ensure_success(resp, "create github installation token").await?;
+        let resp = resp.json::<AccessTokenResp>().await?;
+        INSTALLATION_TOKENS.lock().unwrap().insert(
+            key,
+            CachedToken {
+                token: resp.token.clone(),
+                expires_at: resp.expires_at.naive_utc(),
+            },
+        );
+        Ok(resp.token)
+    }
+}
@@ -0,0 +1,195 @@
+//! write an article and its images in a single commit with the git data api.
+//! Blobs can be up to 100 MB, while the contents api is limited to 1 MB.
+
+use actix_web::web::block;
+use reqwest::StatusCode;
+
+use crate::{
+    operations::remote::{
+        assets,
+        forge::{ensure_success, ForgeFile},
+        types::SyncError,
+    },
+    types::git_data::{
+        BlobResp, CommitResp, ContentMetaResp, CreateBlobParam, CreateCommitParam, CreateTreeParam,
+        GitObject, RefResp, RepositoryResp, TreeEntry, UpdateRefParam,
+    },
+};
+
+use super::GithubApi;
+
+impl GithubApi {
+    pub(super) fn repo_url(&self, repo: &str) -> String {
+        format!("{}/repos/{repo}", self.base_url)
+    }
+
+    /// commit the article and the images it references onto the branch, the default branch
+    /// is used if it's not given. `sha` is the current blob of the article, the commit is
+    /// rejected if the article is changed since then, or created by someone else if it's new.
+    pub(super) async fn commit_article(
+        &self,
+        repo: &str,
+        branch: Option<&str>,
+        path: &str,
+        sha: Option<&str>,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError> {
+        let assets = {
+            let (path, content, assets_dir) = (
+                path.to_string(),
+                content.to_string(),
+                self.assets_dir.clone(),
+            );
+            block(move || assets::collect(&path, &content, assets_dir.as_deref())).await??
+        };
+        let branch = match branch {
+            Some(branch) => branch.to_string(),
+            None => self.base_branch(repo).await?,
+        };
+        let head = self.head(repo, &branch).await?;
+        let current = self.content_meta(repo, path, &head).await?;
+        if current.as_ref().map(|file| file.sha.as_str()) != sha {
+            return Err(SyncError::RemoteConflict(format!("{} of {}", path, repo)));
+        }
+
+        let mut tree = vec![TreeEntry::file(
+            path,
+            &self.create_blob(repo, content.as_bytes()).await?,
+        )];
+        for asset in assets {
+            let blob = self.create_blob(repo, &asset.content).await?;
+            tree.push(TreeEntry::file(&asset.path, &blob));
+        }
+        let base_tree = self.get_commit(repo, &head).await?.tree.sha;
+        let tree = self.create_tree(repo, &base_tree, tree).await?;
+        let commit = self.create_commit(repo, message, &tree, &head).await?;
+        self.update_ref(repo, &branch, &commit).await?;
+
+        let file = self
+            .content_meta(repo, path, &commit)
+            .await?
+            .ok_or(SyncError::RemoteServer)?;
+        Ok(ForgeFile {
+            path: file.path,
+            sha: file.sha,
+            url: file.html_url,
+            content: String::new(),
+            commit: Some(commit),
+            pull_request: None,
+        })
+    }
+
+    /// the branch given by the commit options, or the default branch of the repository
+    pub(super) async fn base_branch(&self, repo: &str) -> Result<String, SyncError> {
+        match &self.commit.branch {
+            Some(branch) => Ok(branch.clone()),
+            None => self.default_branch(repo).await,
+        }
+    }
+
+    async fn default_branch(&self, repo: &str) -> Result<String, SyncError> {
+        let resp = self.send(self.client.get(self.repo_url(repo))).await?;
+        let resp = ensure_success(resp, "get github repository").await?;
+        Ok(resp.json::<RepositoryResp>().await?.default_branch)
+    }
+
+    /// sha of the commit the branch points to
+    pub(super) async fn head(&self, repo: &str, branch: &str) -> Result<String, SyncError> {
+        let url = format!("{}/git/ref/heads/{branch}", self.repo_url(repo));
+        let resp = self.send(self.client.get(url)).await?;
+        let resp = ensure_success(resp, "get github ref").await?;
+        Ok(resp.json::<RefResp>().await?.object.sha)
+    }
+
+    /// metadata of the file at the commit, `None` if it doesn't exist
+    async fn content_meta(
+        &self,
+        repo: &str,
+        path: &str,
+        commit: &str,
+    ) -> Result<Option<ContentMetaResp>, SyncError> {
+        let resp = self
+            .send(
+                self.client
+                    .get(self.content_url(repo, path))
+                    .query(&[("ref", commit)]),
+            )
+            .await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let resp = ensure_success(resp, "get github content").await?;
+        Ok(Some(resp.json::<ContentMetaResp>().await?))
+    }
+
+    pub(super) async fn get_blob(&self, repo: &str, sha: &str) -> Result<BlobResp, SyncError> {
+        let url = format!("{}/git/blobs/{sha}", self.repo_url(repo));
+        let resp = self.send(self.client.get(url)).await?;
+        let resp = ensure_success(resp, "get github blob").await?;
+        Ok(resp.json::<BlobResp>().await?)
+    }
+
+    async fn create_blob(&self, repo: &str, content: &[u8]) -> Result<String, SyncError> {
+        let url = format!("{}/git/blobs", self.repo_url(repo));
+        let resp = self
+            .send(self.client.post(url).json(&CreateBlobParam::new(content)))
+            .await?;
+        let resp = ensure_success(resp, "create github blob").await?;
+        Ok(resp.json::<GitObject>().await?.sha)
+    }
+
+    async fn get_commit(&self, repo: &str, sha: &str) -> Result<CommitResp, SyncError> {
+        let url = format!("{}/git/commits/{sha}", self.repo_url(repo));
+        let resp = self.send(self.client.get(url)).await?;
+        let resp = ensure_success(resp, "get github commit").await?;
+        Ok(resp.json::<CommitResp>().await?)
+    }
+
+    async fn create_tree(
+        &self,
+        repo: &str,
+        base_tree: &str,
+        tree: Vec<TreeEntry>,
+    ) -> Result<String, SyncError> {
+        let url = format!("{}/git/trees", self.repo_url(repo));
+        let resp = self
+            .send(
+                self.client
+                    .post(url)
+                    .json(&CreateTreeParam::new(base_tree, tree)),
+            )
+            .await?;
+        let resp = ensure_success(resp, "create github tree").await?;
+        Ok(resp.json::<GitObject>().await?.sha)
+    }
+
+    async fn create_commit(
+        &self,
+        repo: &str,
+        message: &str,
+        tree: &str,
+        parent: &str,
+    ) -> Result<String, SyncError> {
+        let url = format!("{}/git/commits", self.repo_url(repo));
+        let resp = self
+            .send(
+                self.client
+                    .post(url)
+                    .json(&CreateCommitParam::new(message, tree, parent).signed_by(&self.commit)),
+            )
+            .await?;
+        let resp = ensure_success(resp, "create github commit").await?;
+        Ok(resp.json::<GitObject>().await?.sha)
+    }
+
+    /// fast-forward the branch, it fails if the branch is moved by someone else meanwhile
+    async fn update_ref(&self, repo: &str, branch: &str, commit: &str) -> Result<(), SyncError> {
+        let url = format!("{}/git/refs/heads/{branch}", self.repo_url(repo));
+        let resp = self
+            .send(self.client.patch(url).json(&UpdateRefParam::new(commit)))
+            .await?;
+        ensure_success(resp, "update github ref").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! publish articles through pull requests, for repositories whose default branch
+//! requires reviews.
+
+use crate::{
+    operations::remote::{forge::ensure_success, types::SyncError},
+    types::{
+        github_record::GithubRecord,
+        posts::Post,
+        pull_requests::{
+            branch_name, CreatePullRequestParam, CreateRefParam, PullRequest, PullRequestResp,
+            PullRequestState,
+        },
+    },
+};
+
+use super::GithubApi;
+
+/// branch which the post is pushed to
+#[derive(Debug, Clone)]
+pub(super) struct ReviewBranch {
+    pub(super) name: String,
+    /// the branch has an open pull request, so the article is read from it too
+    pub(super) open: bool,
+    pub(super) title: String,
+}
+
+impl GithubApi {
+    /// keep pushing to the open pull request of the post, or start a new branch
+    pub(super) async fn prepare_review(
+        &mut self,
+        post: &Post,
+        records: &[GithubRecord],
+    ) -> Result<(), SyncError> {
+        let latest = records.iter().find_map(|record| {
+            record
+                .pull_request()
+                .map(|pull_request| (record.repository(), pull_request))
+        });
+        let open = match latest {
+            Some((repo, pull_request)) if pull_request.state == PullRequestState::Open => {
+                // it may be merged or closed since the last sync
+                let pull_request = self.get_pull_request(repo, pull_request.number).await?;
+                (pull_request.state == PullRequestState::Open).then_some(pull_request.branch)
+            }
+            _ => None,
+        };
+        self.review = Some(ReviewBranch {
+            open: open.is_some(),
+            name: open.unwrap_or_else(|| branch_name(post.post_id(), post.title(), post.version())),
+            title: format!("Publish {}", post.title()),
+        });
+        Ok(())
+    }
+
+    /// branch to read the article from, the default branch is used if it's `None`
+    pub(super) fn read_branch(&self) -> Option<&str> {
+        match &self.review {
+            Some(review) if review.open => Some(&review.name),
+            _ => self.commit.branch.as_deref(),
+        }
+    }
+
+    /// branch to write the article to, a review branch is created from the base branch
+    /// if needed. The default branch is used if it's `None`.
+    pub(super) async fn write_branch(&self, repo: &str) -> Result<Option<String>, SyncError> {
+        let review = match &self.review {
+            Some(review) => review,
+            None => return Ok(self.commit.branch.clone()),
+        };
+        if !review.open {
+            self.ensure_branch(repo, &review.name).await?;
+        }
+        Ok(Some(review.name.clone()))
+    }
+
+    async fn ensure_branch(&self, repo: &str, branch: &str) -> Result<(), SyncError> {
+        match self.head(repo, branch).await {
+            Err(SyncError::RemoteNotFound(_)) => {}
+            result => return result.map(|_| ()),
+        }
+        let base = self.base_branch(repo).await?;
+        let head = self.head(repo, &base).await?;
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/git/refs", self.repo_url(repo)))
+                    .json(&CreateRefParam::branch(branch, &head)),
+            )
+            .await?;
+        ensure_success(resp, "create github branch").await?;
+        Ok(())
+    }
+
+    /// the open pull request of the branch, a new one is opened if there isn't
+    pub(super) async fn open_pull_request(
+        &self,
+        repo: &str,
+        branch: &str,
+    ) -> Result<PullRequest, SyncError> {
+        let owner = repo.split('/').next().unwrap_or(repo);
+        let resp = self
+            .send(
+                self.client
+                    .get(format!("{}/pulls", self.repo_url(repo)))
+                    .query(&[
+                        ("head", format!("{owner}:{branch}")),
+                        ("state", "open".to_string()),
+                    ]),
+            )
+            .await?;
+        let resp = ensure_success(resp, "list github pull requests").await?;
+        if let Some(pull_request) = resp
+            .json::<Vec<PullRequestResp>>()
+            .await?
+            .into_iter()
+            .next()
+        {
+            return Ok(pull_request.into());
+        }
+        let title = match &self.review {
+            Some(review) => review.title.clone(),
+            None => format!("Publish {branch}"),
+        };
+        let base = self.base_branch(repo).await?;
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/pulls", self.repo_url(repo)))
+                    .json(&CreatePullRequestParam::new(&title, branch, &base)),
+            )
+            .await?;
+        let resp = ensure_success(resp, "create github pull request").await?;
+        Ok(resp.json::<PullRequestResp>().await?.into())
+    }
+
+    pub(crate) async fn get_pull_request(
+        &self,
+        repo: &str,
+        number: i64,
+    ) -> Result<PullRequest, SyncError> {
+        let url = format!("{}/pulls/{number}", self.repo_url(repo));
+        let resp = self.send(self.client.get(url)).await?;
+        let resp = ensure_success(resp, "get github pull request").await?;
+        Ok(resp.json::<PullRequestResp>().await?.into())
+    }
+}
@@ -0,0 +1,154 @@
+//! back off and retry the requests rejected by rate limits or failed by server errors.
+
+use std::time::Duration;
+
+use log::warn;
+use reqwest::{header::HeaderMap, RequestBuilder, Response, StatusCode};
+
+use crate::{operations::remote::types::SyncError, utils};
+
+use super::GithubApi;
+
+/// how the requests are retried
+#[derive(Debug, Clone)]
+pub(super) struct RetryPolicy {
+    pub(super) max_retries: u32,
+    /// delay of the first retry of a server error, it doubles for every retry
+    pub(super) base_delay: Duration,
+    /// the longest time to wait, e.g. the rate limit resets in an hour,
+    /// it fails with the reset time rather than waiting for it
+    pub(super) max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Done,
+    /// retry with the exponential backoff
+    Backoff,
+    /// retry after the time given by the rate limit headers
+    Wait(Duration),
+}
+
+/// decide from the rate limit headers, see
+/// https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api
+fn verdict(status: StatusCode, headers: &HeaderMap, now: i64) -> Verdict {
+    if status.is_server_error() {
+        return Verdict::Backoff;
+    }
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return Verdict::Done;
+    }
+    let header = |name: &str| -> Option<i64> { headers.get(name)?.to_str().ok()?.parse().ok() };
+    if let Some(seconds) = header("retry-after") {
+        return Verdict::Wait(Duration::from_secs(seconds.max(0) as u64));
+    }
+    match (header("x-ratelimit-remaining"), header("x-ratelimit-reset")) {
+        (Some(0), Some(reset)) => Verdict::Wait(Duration::from_secs((reset - now).max(0) as u64)),
+        (Some(0), None) => Verdict::Backoff,
+        _ if status == StatusCode::TOO_MANY_REQUESTS => Verdict::Backoff,
+        // the token has no permission
+        _ => Verdict::Done,
+    }
+}
+
+impl GithubApi {
+    /// authorize and send the request, it's retried if the rate limit is exceeded or
+    /// the server fails. The response of a server error is returned after the last retry.
+    pub(super) async fn send(&self, req: RequestBuilder) -> Result<Response, SyncError> {
+        let req = req
+            .bearer_auth(self.auth.token(self.timeout).await?)
+            .timeout(self.timeout);
+        let mut retries = 0;
+        loop {
+            let attempt = req
+                .try_clone()
+                .ok_or(SyncError::Other("the request can't be retried".to_string()))?;
+            let resp = attempt.send().await?;
+            let status = resp.status();
+            let now = utils::time_utils::now();
+            let delay = match verdict(status, resp.headers(), now.and_utc().timestamp()) {
+                Verdict::Done => return Ok(resp),
+                Verdict::Backoff => self.retry.base_delay * 2u32.pow(retries),
+                Verdict::Wait(delay) => delay,
+            };
+            if retries >= self.retry.max_retries || delay > self.retry.max_delay {
+                if status.is_server_error() {
+                    return Ok(resp);
+                }
+                return Err(SyncError::RateLimited {
+                    reset_at: chrono::Duration::from_std(delay)
+                        .ok()
+                        .map(|delay| now + delay),
+                });
+            }
+            warn!(
+                "github responds {} to {}, retry in {:?}",
+                status,
+                resp.url(),
+                delay
+            );
+            actix_rt::time::sleep(delay).await;
+            retries += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod retry_test {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn verdict_test() {
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, HeaderValue::from_str(value).unwrap());
+            }
+            headers
+        };
+        let now = 1_700_000_000;
+        assert_eq!(verdict(StatusCode::OK, &headers(&[]), now), Verdict::Done);
+        assert_eq!(
+            verdict(StatusCode::BAD_GATEWAY, &headers(&[]), now),
+            Verdict::Backoff
+        );
+        assert_eq!(
+            verdict(
+                StatusCode::FORBIDDEN,
+                &headers(&[("retry-after", "30")]),
+                now
+            ),
+            Verdict::Wait(Duration::from_secs(30))
+        );
+        let exhausted = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1700000600"),
+        ]);
+        assert_eq!(
+            verdict(StatusCode::FORBIDDEN, &exhausted, now),
+            Verdict::Wait(Duration::from_secs(600))
+        );
+        assert_eq!(
+            verdict(StatusCode::TOO_MANY_REQUESTS, &headers(&[]), now),
+            Verdict::Backoff
+        );
+        // no permission rather than rate limited
+        let remaining = headers(&[("x-ratelimit-remaining", "4999")]);
+        assert_eq!(
+            verdict(StatusCode::FORBIDDEN, &remaining, now),
+            Verdict::Done
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::header::{self, HeaderValue};
+
+use crate::{
+    operations::forge_record::{ForgeRecordCreator, GitlabRecordQueryerByPostId},
+    traits::MongoAction,
+    types::{
+        forge_record::{
+            GitlabDeleteFileParam, GitlabFile, GitlabRecord, GitlabWriteFileParam,
+            InsertableForgeRecord,
+        },
+        posts::{Post, SyncReq},
+        Platform,
+    },
+};
+
+use super::{
+    forge::{
+        ensure_success, token_or_env, ForgeApi, ForgeFile, ForgeSyncError, ForgeSyncer,
+        REQUEST_TIMEOUT,
+    },
+    types::SyncError,
+};
+
+/// characters to encode in a path segment, everything except the unreserved ones
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+static DEFAULT_GITLAB_URL: &str = "https://gitlab.com";
+static DEFAULT_GITLAB_BRANCH: &str = "main";
+
+pub type GitlabSyncer = ForgeSyncer<GitlabApi>;
+
+impl GitlabSyncer {
+    pub fn new(
+        path: Option<String>,
+        repository: Option<String>,
+        branch: Option<String>,
+        token: Option<String>,
+    ) -> Result<GitlabSyncer, ForgeSyncError> {
+        Ok(ForgeSyncer::with_api(
+            path,
+            repository,
+            GitlabApi::new(branch, token)?,
+        ))
+    }
+}
+
+/// gitlab repository files api
+pub struct GitlabApi {
+    base_url: String,
+    branch: String,
+    client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl GitlabApi {
+    /// the token is `GITLAB_TOKEN` env if it's not given
+    pub fn new(branch: Option<String>, token: Option<String>) -> Result<GitlabApi, ForgeSyncError> {
+        let base_url = std::env::var("GITLAB_URL").unwrap_or(DEFAULT_GITLAB_URL.to_string());
+        let branch = branch
+            .or(std::env::var("GITLAB_BRANCH").ok())
+            .unwrap_or(DEFAULT_GITLAB_BRANCH.to_string());
+        let mut header_map = header::HeaderMap::new();
+        header_map.insert(
+            header::USER_AGENT,
+            header::HeaderValue::from_static("letterman"),
+        );
+        let token = token_or_env(token, "GITLAB_TOKEN")?;
+        header_map.insert(
+            "PRIVATE-TOKEN",
+            HeaderValue::from_str(&token)
+                .map_err(|_| ForgeSyncError::UserError("invalid gitlab token".to_string()))?,
+        );
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .default_headers(header_map)
+            .build()?;
+        Ok(GitlabApi {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            branch,
+            client,
+            timeout: REQUEST_TIMEOUT,
+        })
+    }
+
+    /// both the project path and the file path must be url-encoded as a single segment
+    fn file_url(&self, repo: &str, path: &str) -> String {
+        format!(
+            "{}/api/v4/projects/{}/repository/files/{}",
+            self.base_url,
+            utf8_percent_encode(repo, PATH_SEGMENT),
+            utf8_percent_encode(path, PATH_SEGMENT)
+        )
+    }
+
+    fn html_url(&self, repo: &str, path: &str) -> String {
+        format!("{}/{repo}/-/blob/{}/{path}", self.base_url, self.branch)
+    }
+
+    async fn gitlab_file(&self, repo: &str, path: &str) -> Result<GitlabFile, SyncError> {
+        let resp = self
+            .client
+            .get(self.file_url(repo, path))
+            .query(&[("ref", &self.branch)])
+            .timeout(self.timeout)
+            .send()
+            .await?;
+        let resp = ensure_success(resp, "get gitlab article").await?;
+        Ok(resp.json::<GitlabFile>().await?)
+    }
+
+    /// the latest commit of the file if its blob is still `sha`.
+    /// gitlab doesn't check the blob on writes, so the file changed since the last sync
+    /// is a conflict here, and the commit guards the write against the later changes.
+    async fn last_commit_of_blob(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+    ) -> Result<String, SyncError> {
+        let file = self.gitlab_file(repo, path).await?;
+        if file.blob_id != sha {
+            return Err(SyncError::RemoteConflict(format!(
+                "{} of {} is changed on gitlab since the last sync",
+                path, repo
+            )));
+        }
+        Ok(file.last_commit_id)
+    }
+
+    /// the write api only returns the path, so fetch the file again to get the blob id
+    async fn write_file(
+        &self,
+        req: reqwest::RequestBuilder,
+        repo: &str,
+        path: &str,
+        param: GitlabWriteFileParam,
+    ) -> Result<ForgeFile, SyncError> {
+        let resp = req.json(&param).timeout(self.timeout).send().await?;
+        ensure_success(resp, "gitlab write file").await?;
+        let mut file = self.get_file(repo, path).await?;
+        file.content = String::new();
+        Ok(file)
+    }
+}
+
+#[async_trait]
+impl ForgeApi for GitlabApi {
+    type Record = GitlabRecord;
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    async fn create_file(
+        &self,
+        repo: &str,
+        path: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError> {
+        let req = self.client.post(self.file_url(repo, path));
+        let param = GitlabWriteFileParam::new(&self.branch, message, content);
+        self.write_file(req, repo, path, param).await
+    }
+
+    async fn update_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+        content: &str,
+    ) -> Result<ForgeFile, SyncError> {
+        let last_commit_id = self.last_commit_of_blob(repo, path, sha).await?;
+        let req = self.client.put(self.file_url(repo, path));
+        let param =
+            GitlabWriteFileParam::new(&self.branch, message, content).based_on(&last_commit_id);
+        self.write_file(req, repo, path, param).await
+    }
+
+    async fn delete_file(
+        &self,
+        repo: &str,
+        path: &str,
+        sha: &str,
+        message: &str,
+    ) -> Result<(), SyncError> {
+        let last_commit_id = self.last_commit_of_blob(repo, path, sha).await?;
+        let param = GitlabDeleteFileParam::new(&self.branch, message).based_on(&last_commit_id);
+        let resp = self
+            .client
+            .delete(self.file_url(repo, path))
+            .json(&param)
+            .timeout(self.timeout)
+            .send()
+            .await?;
+        ensure_success(resp, "gitlab delete file").await?;
+        Ok(())
+    }
+
+    async fn get_file(&self, repo: &str, path: &str) -> Result<ForgeFile, SyncError> {
+        let file = self.gitlab_file(repo, path).await?.decode_content()?;
+        Ok(ForgeFile {
+            url: self.html_url(repo, &file.file_path),
+            path: file.file_path,
+            sha: file.blob_id,
+            content: file.content,
+            commit: None,
+            pull_request: None,
+        })
+    }
+
+    async fn query_records(
+        &self,
+        post_id: i64,
+        mongo_db: mongodb::Database,
+    ) -> Result<Vec<GitlabRecord>, SyncError> {
+        Ok(GitlabRecordQueryerByPostId(post_id)
+            .execute(mongo_db.clone())
+            .await?)
+    }
+
+    async fn create_record(
+        &self,
+        post: &Post,
+        repo: &str,
+        file: &ForgeFile,
+        previous_path: Option<&str>,
+        target: Option<&SyncReq>,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        ForgeRecordCreator(
+            InsertableForgeRecord::new(
+                Platform::Gitlab,
+                post.post_id(),
+                post.version().to_string(),
+                file.path.clone(),
+                file.sha.clone(),
+                repo.to_string(),
+                file.url.clone(),
+            )
+            .moved_from(previous_path)
+            .for_target(target),
+        )
+        .execute(mongo_db.clone())
+        .await?;
+        Ok(())
+    }
+
+    async fn create_tombstone(
+        &self,
+        record: &GitlabRecord,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        ForgeRecordCreator(InsertableForgeRecord::tombstone(Platform::Gitlab, record))
+            .execute(mongo_db.clone())
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod gitlab_sync_test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn file_url_test() {
+        let api = GitlabApi {
+            base_url: DEFAULT_GITLAB_URL.to_string(),
+            branch: DEFAULT_GITLAB_BRANCH.to_string(),
+            client: reqwest::Client::new(),
+            timeout: REQUEST_TIMEOUT,
+        };
+        assert_eq!(
+            api.file_url("group/blog", "source/_posts/a b.md"),
+            "https://gitlab.com/api/v4/projects/group%2Fblog/repository/files/source%2F_posts%2Fa%20b.md"
+        );
+        assert_eq!(
+            api.html_url("group/blog", "a.md"),
+            "https://gitlab.com/group/blog/-/blob/main/a.md"
+        );
+    }
+
+    /// a gitlab serving `a.md` at blob `b2` and commit `c2`, the bodies of the writes are kept
+    async fn mock_gitlab() -> (GitlabApi, Arc<Mutex<Vec<serde_json::Value>>>) {
+        use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+
+        let writes = Arc::new(Mutex::new(vec![]));
+        let received = writes.clone();
+        let server = HttpServer::new(move || {
+            let received = received.clone();
+            App::new().default_service(web::to(move |req: HttpRequest, body: web::Bytes| {
+                let received = received.clone();
+                async move {
+                    if req.method() != actix_web::http::Method::GET {
+                        received
+                            .lock()
+                            .unwrap()
+                            .push(serde_json::from_slice(&body).unwrap());
+                    }
+                    HttpResponse::Ok().json(serde_json::json!({
+                        "file_path": "a.md",
+                        "encoding": "text",
+                        "content": "remote",
+                        "blob_id": "b2",
+                        "last_commit_id": "c2",
+                    }))
+                }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        actix_rt::spawn(server.run());
+        let api = GitlabApi {
+            base_url: format!("http://{}", addr),
+            branch: DEFAULT_GITLAB_BRANCH.to_string(),
+            client: reqwest::Client::new(),
+            timeout: REQUEST_TIMEOUT,
+        };
+        (api, writes)
+    }
+
+    #[actix_web::test]
+    async fn changed_file_conflict_test() {
+        let (api, writes) = mock_gitlab().await;
+        // the file is changed on gitlab since the sync which recorded blob b1
+        let updated = api
+            .update_file("group/blog", "a.md", "b1", "update", "local")
+            .await;
+        assert!(matches!(updated, Err(SyncError::RemoteConflict(_))));
+        let deleted = api.delete_file("group/blog", "a.md", "b1", "delete").await;
+        assert!(matches!(deleted, Err(SyncError::RemoteConflict(_))));
+        assert!(writes.lock().unwrap().is_empty());
+
+        // the writes are based on the commit of the blob
+        api.update_file("group/blog", "a.md", "b2", "update", "local")
+            .await
+            .unwrap();
+        api.delete_file("group/blog", "a.md", "b2", "delete")
+            .await
+            .unwrap();
+        let writes = writes.lock().unwrap();
+        assert_eq!(writes.len(), 2);
+        assert!(writes.iter().all(|write| write["last_commit_id"] == "c2"));
+    }
+}
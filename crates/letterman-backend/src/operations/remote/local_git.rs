@@ -0,0 +1,702 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use actix_web::web::block;
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    operations::local_git_record::{LocalGitRecordCreator, LocalGitRecordQueryerByPostId},
+    traits::{MongoAction, MongoActionError},
+    types::{
+        local_git_record::{
+            CreateLocalGitRecordError, InsertableLocalGitRecord, LocalGitRecord,
+            QueryLocalGitRecordError,
+        },
+        posts::{Post, SyncReq},
+    },
+    utils,
+};
+
+use super::{
+    frontmatter::{extract, package},
+    transform::Pipeline,
+    types::{Context, SyncError, SyncStatus, SyncTarget},
+    SyncAction,
+};
+
+static LOCAL_GIT_SYNC_RECORDS_KEY: &str = "records";
+
+/// write posts into a local git working tree, e.g. the source folder of a hexo blog,
+/// and commit every push.
+pub struct LocalGitSyncer {
+    /// path relative to the repository root, required for the first time sync
+    path: Option<String>,
+    /// directory of the repository checkout, required for the first time sync
+    repository: Option<String>,
+    pipeline: Pipeline,
+    /// the request which creates the syncer, it's stored with the records
+    target: Option<SyncReq>,
+    ctx: Context,
+}
+
+#[async_trait]
+impl SyncAction for LocalGitSyncer {
+    fn render(&self, post: &Post) -> Result<String, SyncError> {
+        Ok(package(&self.pipeline.push(post)?)?)
+    }
+
+    async fn push_create(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        if self.path.is_none() || self.repository.is_none() {
+            return Err(LocalGitSyncError::UserError(
+                "path and repository is required for the first time sync".to_string(),
+            )
+            .into());
+        }
+        let repo = self.repository.clone().unwrap();
+        let path = self.path.clone().unwrap();
+        let content = self.render(post)?;
+        let message = format!("create {}", path);
+        let commit = {
+            let (repo, path) = (repository_dir(&repo)?, path.clone());
+            block(move || write_and_commit(&repo, &path, &content, &message)).await??
+        };
+        LocalGitRecordCreator(
+            InsertableLocalGitRecord::new(
+                post.post_id(),
+                post.version().to_string(),
+                path,
+                commit,
+                repo,
+            )
+            .for_target(self.target.as_ref()),
+        )
+        .execute(mongo_db.clone())
+        .await?;
+        Ok(())
+    }
+
+    async fn push_update(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => return Err(SyncError::NotFound),
+        };
+        let content = self.render(post)?;
+        let message = format!("update {}", record.path());
+        let commit = {
+            let (repo, path) = (
+                repository_dir(record.repository())?,
+                record.path().to_string(),
+            );
+            block(move || write_and_commit(&repo, &path, &content, &message)).await??
+        };
+        LocalGitRecordCreator(
+            InsertableLocalGitRecord::new(
+                post.post_id(),
+                post.version().to_string(),
+                record.path().to_string(),
+                commit,
+                record.repository().to_string(),
+            )
+            .for_target(self.target.as_ref()),
+        )
+        .execute(mongo_db.clone())
+        .await?;
+        Ok(())
+    }
+
+    async fn pull(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<Post>, SyncError> {
+        let remote = match self.fetch(post, mongo_db.clone()).await? {
+            Some(remote) => remote,
+            None => return Ok(None),
+        };
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        let record = records.unwrap().first().unwrap().clone();
+        let commit = {
+            let (repo, path) = (
+                repository_dir(record.repository())?,
+                record.path().to_string(),
+            );
+            block(move || last_commit(&repo, &path)).await??
+        };
+        LocalGitRecordCreator(
+            InsertableLocalGitRecord::new(
+                remote.post_id(),
+                remote.version().to_string(),
+                record.path().to_string(),
+                commit,
+                record.repository().to_string(),
+            )
+            .for_target(self.target.as_ref()),
+        )
+        .execute(mongo_db.clone())
+        .await?;
+        Ok(Some(remote))
+    }
+
+    async fn fetch(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<Post>, SyncError> {
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => return Ok(None),
+        };
+        let content = {
+            let (repo, path) = (
+                repository_dir(record.repository())?,
+                record.path().to_string(),
+            );
+            block(move || read_file(&repo, &path)).await??
+        };
+        let res = extract(&content)?;
+        let content = self.pipeline.pull(res.content)?;
+        let title = if let Some(title) = res.title {
+            title
+        } else {
+            post.title().to_string()
+        };
+        let version = utils::sha_utils::sha_post2(&title, &res.metadata, &content);
+        Ok(Some(Post::new(
+            utils::snowflake::next_id(),
+            post.post_id(),
+            title,
+            res.metadata,
+            content,
+            version,
+            post.version().to_string(),
+            utils::time_utils::now(),
+            utils::time_utils::now(),
+        )))
+    }
+
+    async fn synced_version(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<String>, SyncError> {
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        Ok(records.map(|records| records.first().unwrap().version().to_string()))
+    }
+
+    async fn target(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<SyncTarget, SyncError> {
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => {
+                return Ok(SyncTarget {
+                    repository: self.repository.clone(),
+                    path: self.path.clone(),
+                    content: None,
+                })
+            }
+        };
+        let (repo, path) = (record.repository().to_string(), record.path().to_string());
+        let content = {
+            let (repo, path) = (repository_dir(&repo)?, path.clone());
+            block(move || read_file(&repo, &path)).await??
+        };
+        Ok(SyncTarget {
+            repository: Some(repo),
+            path: Some(path),
+            content: Some(content),
+        })
+    }
+
+    async fn check_changed(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<SyncStatus, SyncError> {
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => return Ok(SyncStatus::NeverSynced),
+        };
+        let remote_changed = {
+            let (repo, path) = (
+                repository_dir(record.repository())?,
+                record.path().to_string(),
+            );
+            let commit = record.commit().to_string();
+            block(move || is_modified(&repo, &path, &commit)).await??
+        };
+        Ok(SyncStatus::new(
+            post.version() != record.version(),
+            remote_changed,
+        ))
+    }
+
+    async fn push_move(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => {
+                return Err(
+                    LocalGitSyncError::UserError("the post is not published".to_string()).into(),
+                )
+            }
+        };
+        let path = match &self.path {
+            Some(path) if path != record.path() => path.clone(),
+            _ => {
+                return Err(LocalGitSyncError::UserError(
+                    "a new path is required to move the article".to_string(),
+                )
+                .into())
+            }
+        };
+        let content = self.render(post)?;
+        let message = format!("move {} to {}", record.path(), path);
+        let commit = {
+            let (repo, from, to) = (
+                repository_dir(record.repository())?,
+                record.path().to_string(),
+                path.clone(),
+            );
+            block(move || move_and_commit(&repo, &from, &to, &content, &message)).await??
+        };
+        LocalGitRecordCreator(
+            InsertableLocalGitRecord::new(
+                post.post_id(),
+                post.version().to_string(),
+                path,
+                commit,
+                record.repository().to_string(),
+            )
+            .moved_from(Some(record.path()))
+            .for_target(self.target.as_ref()),
+        )
+        .execute(mongo_db.clone())
+        .await?;
+        self.ctx = Context::new();
+        Ok(())
+    }
+
+    async fn unpublish(
+        &mut self,
+        post: &Post,
+        mongo_db: mongodb::Database,
+    ) -> Result<(), SyncError> {
+        let records = self
+            .get_local_git_sync_records(post.post_id(), mongo_db.clone())
+            .await?;
+        let record = match records {
+            Some(records) => records.first().unwrap().clone(),
+            None => {
+                return Err(
+                    LocalGitSyncError::UserError("the post is not published".to_string()).into(),
+                )
+            }
+        };
+        let message = format!("delete {}", record.path());
+        let commit = {
+            let (repo, path) = (
+                repository_dir(record.repository())?,
+                record.path().to_string(),
+            );
+            block(move || remove_and_commit(&repo, &path, &message)).await??
+        };
+        LocalGitRecordCreator(InsertableLocalGitRecord::tombstone(&record, commit))
+            .execute(mongo_db.clone())
+            .await?;
+        self.ctx = Context::new();
+        Ok(())
+    }
+}
+
+impl LocalGitSyncer {
+    pub fn new(
+        path: Option<String>,
+        repository: Option<String>,
+    ) -> Result<LocalGitSyncer, LocalGitSyncError> {
+        if let Some(path) = &path {
+            validate_path(path)?;
+        }
+        if let Some(repository) = &repository {
+            git(
+                &repository_dir(repository)?,
+                &["rev-parse", "--is-inside-work-tree"],
+            )
+            .map_err(|_| {
+                LocalGitSyncError::UserError(format!("{} is not a git repository", repository))
+            })?;
+        }
+        Ok(LocalGitSyncer {
+            path,
+            repository,
+            pipeline: Pipeline::default(),
+            target: None,
+            ctx: Context::new(),
+        })
+    }
+
+    pub(super) fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub(super) fn with_target(mut self, target: SyncReq) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    async fn get_local_git_sync_records(
+        &mut self,
+        post_id: i64,
+        mongo_db: mongodb::Database,
+    ) -> Result<Option<Vec<LocalGitRecord>>, MongoActionError<QueryLocalGitRecordError>> {
+        let records: Option<Vec<LocalGitRecord>> = self.ctx.get(LOCAL_GIT_SYNC_RECORDS_KEY);
+        if records.is_some() {
+            return Ok(records);
+        }
+        let records = LocalGitRecordQueryerByPostId(post_id)
+            .execute(mongo_db.clone())
+            .await?;
+        if records.is_empty() {
+            return Ok(None);
+        }
+        // the article is unpublished, it's published again to the same place by default
+        if let Some(tombstone) = records.first().filter(|record| record.deleted()) {
+            self.path.get_or_insert(tombstone.path().to_string());
+            self.repository
+                .get_or_insert(tombstone.repository().to_string());
+            return Ok(None);
+        }
+        self.ctx
+            .set(LOCAL_GIT_SYNC_RECORDS_KEY.to_string(), records.clone());
+        Ok(Some(records))
+    }
+}
+
+/// directory of the repository, which must be under `LOCAL_GIT_ROOT` env.
+/// The repository of the request is resolved relative to the root.
+fn repository_dir(repository: &str) -> Result<String, LocalGitSyncError> {
+    let root = std::env::var("LOCAL_GIT_ROOT").map_err(|_| {
+        LocalGitSyncError::UserError(
+            "Please set LOCAL_GIT_ROOT env if you want to synchronize to local git repositories"
+                .to_string(),
+        )
+    })?;
+    resolve_repository(Path::new(&root), repository)
+}
+
+/// canonicalize the repository under the root, links and `..` can't escape it
+fn resolve_repository(root: &Path, repository: &str) -> Result<String, LocalGitSyncError> {
+    let root = root
+        .canonicalize()
+        .map_err(|_| LocalGitSyncError::UserError(format!("{} doesn't exist", root.display())))?;
+    let dir = root.join(repository).canonicalize().map_err(|_| {
+        LocalGitSyncError::UserError(format!("repository {} doesn't exist", repository))
+    })?;
+    if !dir.starts_with(&root) {
+        return Err(LocalGitSyncError::UserError(format!(
+            "repository {} is outside of LOCAL_GIT_ROOT",
+            repository
+        )));
+    }
+    dir.to_str().map(str::to_string).ok_or_else(|| {
+        LocalGitSyncError::UserError(format!("repository {} is not a valid path", repository))
+    })
+}
+
+/// reject absolute paths and paths escaping the repository
+fn validate_path(path: &str) -> Result<(), LocalGitSyncError> {
+    let escaped = Path::new(path)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if path.trim().is_empty() || escaped {
+        return Err(LocalGitSyncError::UserError(
+            "path must be a relative path inside the repository".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// run git in the repository and return the trimmed stdout
+fn git(repo: &str, args: &[&str]) -> Result<String, LocalGitSyncError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        return Err(LocalGitSyncError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn full_path(repo: &str, path: &str) -> Result<PathBuf, LocalGitSyncError> {
+    validate_path(path)?;
+    Ok(Path::new(repo).join(path))
+}
+
+/// write the article into the working tree and commit it
+/// return the hash of the commit containing the article
+fn write_and_commit(
+    repo: &str,
+    path: &str,
+    content: &str,
+    message: &str,
+) -> Result<String, LocalGitSyncError> {
+    let file = full_path(repo, path)?;
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&file, content)?;
+    git(repo, &["add", "--", path])?;
+    // `git diff --quiet` exits with 1 if there is any staged change
+    let unchanged = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["diff", "--cached", "--quiet", "--", path])
+        .status()?
+        .success();
+    if !unchanged {
+        git(repo, &["commit", "-m", message, "--", path])?;
+    }
+    last_commit(repo, path)
+}
+
+/// move the article to the new path with the content, both are committed together
+/// return the hash of the commit moving the article
+fn move_and_commit(
+    repo: &str,
+    from: &str,
+    to: &str,
+    content: &str,
+    message: &str,
+) -> Result<String, LocalGitSyncError> {
+    validate_path(from)?;
+    let file = full_path(repo, to)?;
+    if file.exists() {
+        return Err(LocalGitSyncError::UserError(format!(
+            "{} already exists",
+            to
+        )));
+    }
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    git(repo, &["mv", "--", from, to])?;
+    fs::write(&file, content)?;
+    git(repo, &["add", "--", to])?;
+    git(repo, &["commit", "-m", message, "--", from, to])?;
+    last_commit(repo, to)
+}
+
+/// remove the article from the working tree and commit the deletion
+/// return the hash of the commit deleting the article
+fn remove_and_commit(repo: &str, path: &str, message: &str) -> Result<String, LocalGitSyncError> {
+    validate_path(path)?;
+    git(repo, &["rm", "--quiet", "--", path])?;
+    git(repo, &["commit", "-m", message, "--", path])?;
+    last_commit(repo, path)
+}
+
+fn read_file(repo: &str, path: &str) -> Result<String, LocalGitSyncError> {
+    let file = full_path(repo, path)?;
+    Ok(fs::read_to_string(file)?)
+}
+
+/// hash of the latest commit touching the path
+fn last_commit(repo: &str, path: &str) -> Result<String, LocalGitSyncError> {
+    git(repo, &["log", "-1", "--format=%H", "--", path])
+}
+
+/// check if the article is committed or edited in the working tree since `commit`
+fn is_modified(repo: &str, path: &str, commit: &str) -> Result<bool, LocalGitSyncError> {
+    if last_commit(repo, path)? != commit {
+        return Ok(true);
+    }
+    Ok(!git(repo, &["status", "--porcelain", "--", path])?.is_empty())
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum LocalGitSyncError {
+    #[error("Git Error: {0}")]
+    Git(String),
+    #[error("IO Error: {0}")]
+    Io(String),
+    #[error("User Error: {0}")]
+    UserError(String),
+}
+
+impl From<std::io::Error> for LocalGitSyncError {
+    fn from(item: std::io::Error) -> Self {
+        LocalGitSyncError::Io(item.to_string())
+    }
+}
+
+impl From<LocalGitSyncError> for SyncError {
+    fn from(val: LocalGitSyncError) -> Self {
+        match val {
+            LocalGitSyncError::Git(_) | LocalGitSyncError::Io(_) => {
+                SyncError::Other(val.to_string())
+            }
+            LocalGitSyncError::UserError(e) => SyncError::UserError(e),
+        }
+    }
+}
+
+impl From<MongoActionError<QueryLocalGitRecordError>> for SyncError {
+    fn from(value: MongoActionError<QueryLocalGitRecordError>) -> Self {
+        match value {
+            MongoActionError::Error(QueryLocalGitRecordError::Database(_)) => SyncError::Database,
+            MongoActionError::Pool(_) => SyncError::Database,
+        }
+    }
+}
+
+impl From<MongoActionError<CreateLocalGitRecordError>> for SyncError {
+    fn from(value: MongoActionError<CreateLocalGitRecordError>) -> Self {
+        match value {
+            MongoActionError::Error(CreateLocalGitRecordError::Database(_)) => SyncError::Database,
+            MongoActionError::Pool(_) => SyncError::Database,
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_git_sync_test {
+    use super::*;
+
+    fn init_repo() -> String {
+        let dir = std::env::temp_dir().join(format!("letterman-{}", utils::snowflake::next_id()));
+        fs::create_dir_all(&dir).unwrap();
+        let repo = dir.to_str().unwrap().to_string();
+        git(&repo, &["init"]).unwrap();
+        git(&repo, &["config", "user.name", "letterman"]).unwrap();
+        git(&repo, &["config", "user.email", "letterman@localhost"]).unwrap();
+        repo
+    }
+
+    #[test]
+    fn validate_path_test() {
+        assert!(validate_path("source/_posts/a.md").is_ok());
+        assert!(validate_path("./a.md").is_ok());
+        assert!(validate_path("../a.md").is_err());
+        assert!(validate_path("/etc/passwd").is_err());
+        assert!(validate_path("").is_err());
+    }
+
+    #[test]
+    fn resolve_repository_test() {
+        let root = std::env::temp_dir().join(format!("letterman-{}", utils::snowflake::next_id()));
+        fs::create_dir_all(root.join("blog")).unwrap();
+        let blog = root.canonicalize().unwrap().join("blog");
+        let blog = blog.to_str().unwrap();
+        assert_eq!(resolve_repository(&root, "blog").unwrap(), blog);
+        assert_eq!(resolve_repository(&root, "./blog/").unwrap(), blog);
+        // an absolute path is accepted only if it's under the root
+        assert_eq!(resolve_repository(&root, blog).unwrap(), blog);
+        assert!(resolve_repository(&root, "/etc").is_err());
+        assert!(resolve_repository(&root, "../").is_err());
+        assert!(resolve_repository(&root, "blog/../..").is_err());
+        assert!(resolve_repository(&root, "missing").is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/etc", root.join("link")).unwrap();
+            assert!(resolve_repository(&root, "link").is_err());
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn write_and_commit_test() {
+        let repo = init_repo();
+        let path = "source/_posts/hello.md";
+        let first = write_and_commit(&repo, path, "---\ntitle: a\n---\nhello", "create").unwrap();
+        assert_eq!(read_file(&repo, path).unwrap(), "---\ntitle: a\n---\nhello");
+
+        // nothing changed, no new commit
+        let same = write_and_commit(&repo, path, "---\ntitle: a\n---\nhello", "update").unwrap();
+        assert_eq!(first, same);
+
+        let second = write_and_commit(&repo, path, "---\ntitle: a\n---\nworld", "update").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(git(&repo, &["log", "-1", "--format=%s"]).unwrap(), "update");
+
+        assert!(!is_modified(&repo, path, &second).unwrap());
+        assert!(is_modified(&repo, path, &first).unwrap());
+        fs::write(full_path(&repo, path).unwrap(), "edited").unwrap();
+        assert!(is_modified(&repo, path, &second).unwrap());
+        fs::remove_dir_all(repo).unwrap();
+    }
+    #[test]
+    fn move_and_commit_test() {
+        let repo = init_repo();
+        let (from, to) = ("source/_posts/hello.md", "source/_posts/2024/hello.md");
+        let created = write_and_commit(&repo, from, "hello", "create").unwrap();
+        let moved = move_and_commit(&repo, from, to, "hello world", "move").unwrap();
+        assert_ne!(created, moved);
+        assert!(read_file(&repo, from).is_err());
+        assert_eq!(read_file(&repo, to).unwrap(), "hello world");
+        assert_eq!(last_commit(&repo, from).unwrap(), moved);
+        assert!(!is_modified(&repo, to, &moved).unwrap());
+
+        // the target must not exist
+        write_and_commit(&repo, from, "hello", "create").unwrap();
+        assert!(move_and_commit(&repo, from, to, "hello", "move").is_err());
+        fs::remove_dir_all(repo).unwrap();
+    }
+
+    #[test]
+    fn remove_and_commit_test() {
+        let repo = init_repo();
+        let path = "source/_posts/hello.md";
+        let created = write_and_commit(&repo, path, "hello", "create").unwrap();
+        let deleted = remove_and_commit(&repo, path, "delete").unwrap();
+        assert_ne!(created, deleted);
+        assert!(read_file(&repo, path).is_err());
+        assert_eq!(git(&repo, &["log", "-1", "--format=%s"]).unwrap(), "delete");
+
+        // published again at the same path
+        let recreated = write_and_commit(&repo, path, "hello", "create").unwrap();
+        assert_ne!(deleted, recreated);
+        assert_eq!(read_file(&repo, path).unwrap(), "hello");
+        fs::remove_dir_all(repo).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+//! three-way merge of a post, used when both the local post and the remote article
+//! are changed since the last synchronization.
+
+use std::collections::{BTreeSet, HashMap};
+
+use diffy::{DiffOptions, HunkRange, Line};
+
+use crate::types::posts::{Metadata, Post};
+
+use super::types::MergeConflict;
+
+/// title, metadata and content merged from the ancestor, local and remote post
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Merged {
+    pub(super) title: String,
+    pub(super) metadata: Metadata,
+    pub(super) content: String,
+}
+
+impl Merged {
+    pub(super) fn is_same(&self, post: &Post) -> bool {
+        self.title == post.title()
+            && &self.metadata == post.metadata()
+            && self.content == post.content()
+    }
+}
+
+/// merge the changes of local and remote since base,
+/// return all conflicts if both sides change the same field or overlapping lines
+pub(super) fn merge(
+    base: &Post,
+    local: &Post,
+    remote: &Post,
+) -> Result<Merged, Vec<MergeConflict>> {
+    let mut conflicts = vec![];
+
+    let title = match merge_value(
+        Some(base.title()),
+        Some(local.title()),
+        Some(remote.title()),
+    ) {
+        Ok(title) => title.unwrap_or_default().to_string(),
+        Err(_) => {
+            conflicts.push(MergeConflict::Title {
+                base: base.title().to_string(),
+                local: local.title().to_string(),
+                remote: remote.title().to_string(),
+            });
+            local.title().to_string()
+        }
+    };
+
+    let keys: BTreeSet<&String> = base
+        .metadata()
+        .keys()
+        .chain(local.metadata().keys())
+        .chain(remote.metadata().keys())
+        .collect();
+    let mut metadata = HashMap::new();
+    for key in keys {
+        let (b, l, r) = (
+            base.metadata().get(key),
+            local.metadata().get(key),
+            remote.metadata().get(key),
+        );
+        match merge_value(b, l, r) {
+            Ok(Some(value)) => {
+                metadata.insert(key.clone(), value.clone());
+            }
+            Ok(None) => {}
+            Err(_) => conflicts.push(MergeConflict::Metadata {
+                key: key.clone(),
+                base: b.cloned(),
+                local: l.cloned(),
+                remote: r.cloned(),
+            }),
+        }
+    }
+
+    let content = match merge_text(base.content(), local.content(), remote.content()) {
+        Ok(content) => content,
+        Err(mut content_conflicts) => {
+            conflicts.append(&mut content_conflicts);
+            String::new()
+        }
+    };
+
+    if conflicts.is_empty() {
+        Ok(Merged {
+            title,
+            metadata,
+            content,
+        })
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// merge a single value, `None` means the value is absent
+fn merge_value<T: PartialEq>(
+    base: Option<T>,
+    local: Option<T>,
+    remote: Option<T>,
+) -> Result<Option<T>, ()> {
+    if local == remote || remote == base {
+        Ok(local)
+    } else if local == base {
+        Ok(remote)
+    } else {
+        Err(())
+    }
+}
+
+/// line based three-way merge, which is the diff3 algorithm
+fn merge_text(base: &str, local: &str, remote: &str) -> Result<String, Vec<MergeConflict>> {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let local: Vec<&str> = local.split_inclusive('\n').collect();
+    let remote: Vec<&str> = remote.split_inclusive('\n').collect();
+    let local_matches = matches(&base, &local);
+    let remote_matches = matches(&base, &remote);
+
+    let mut merged: Vec<&str> = vec![];
+    let mut conflicts = vec![];
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    while i < base.len() || j < local.len() || k < remote.len() {
+        if i < base.len() && local_matches[i] == Some(j) && remote_matches[i] == Some(k) {
+            // stable line, which is not changed by both sides
+            merged.push(base[i]);
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+        // find the next stable line, lines before it are changed by at least one side
+        let next =
+            (i..base.len()).find(|&n| local_matches[n].is_some() && remote_matches[n].is_some());
+        let (ni, nj, nk) = match next {
+            Some(n) => (n, local_matches[n].unwrap(), remote_matches[n].unwrap()),
+            None => (base.len(), local.len(), remote.len()),
+        };
+        let (b, l, r) = (&base[i..ni], &local[j..nj], &remote[k..nk]);
+        if l == b || l == r {
+            merged.extend_from_slice(r);
+        } else if r == b {
+            merged.extend_from_slice(l);
+        } else {
+            conflicts.push(MergeConflict::Content {
+                line: i + 1,
+                base: trim_lines(b),
+                local: trim_lines(l),
+                remote: trim_lines(r),
+            });
+        }
+        (i, j, k) = (ni, nj, nk);
+    }
+    if conflicts.is_empty() {
+        Ok(merged.concat())
+    } else {
+        Err(conflicts)
+    }
+}
+
+fn trim_lines(lines: &[&str]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| line.trim_end_matches(['\n', '\r']).to_string())
+        .collect()
+}
+
+/// map every line of `base` to the line of `other` which is kept by their myers diff
+fn matches(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let (base_text, other_text) = (base.concat(), other.concat());
+    let patch = DiffOptions::new()
+        .set_context_len(0)
+        .create_patch(&base_text, &other_text);
+    let mut result = vec![None; base.len()];
+    let (mut x, mut y) = (0, 0);
+    for hunk in patch.hunks() {
+        // the lines between the hunks are kept
+        while x < hunk_start(hunk.old_range()) {
+            result[x] = Some(y);
+            x += 1;
+            y += 1;
+        }
+        for line in hunk.lines() {
+            match line {
+                Line::Context(_) => {
+                    result[x] = Some(y);
+                    x += 1;
+                    y += 1;
+                }
+                Line::Delete(_) => x += 1,
+                Line::Insert(_) => y += 1,
+            }
+        }
+    }
+    while x < base.len() {
+        result[x] = Some(y);
+        x += 1;
+        y += 1;
+    }
+    result
+}
+
+/// 0-based index of the range, the start of an empty range is the line before it
+fn hunk_start(range: HunkRange) -> usize {
+    if range.is_empty() {
+        range.start()
+    } else {
+        range.start() - 1
+    }
+}
+
+#[cfg(test)]
+mod merge_test {
+    use serde_json::Value;
+
+    use crate::types::posts::Post;
+
+    use super::*;
+
+    fn post(title: &str, metadata: &[(&str, &str)], content: &str) -> Post {
+        let metadata: Metadata = metadata
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::from(*v)))
+            .collect();
+        Post::new(
+            1,
+            1,
+            title.to_string(),
+            metadata,
+            content.to_string(),
+            "".to_string(),
+            "".to_string(),
+            Default::default(),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn merge_text_test() {
+        let base = "a\nb\nc\nd\n";
+        assert_eq!(
+            merge_text(base, "a\nB\nc\nd\n", "a\nb\nc\nD\n").unwrap(),
+            "a\nB\nc\nD\n"
+        );
+        assert_eq!(
+            merge_text(base, "x\na\nb\nc\nd\n", "a\nb\nc\nd\ny\n").unwrap(),
+            "x\na\nb\nc\nd\ny\n"
+        );
+        assert_eq!(
+            merge_text(base, "a\nc\nd\n", "a\nc\nd\n").unwrap(),
+            "a\nc\nd\n"
+        );
+        assert_eq!(merge_text(base, base, "").unwrap(), "");
+
+        // long articles changed at both ends are merged without a table of every line pair
+        let lines: String = (0..20000).map(|n| format!("line {n}\n")).collect();
+        let local = format!("TITLE\n{lines}END\n");
+        let changed = lines.replace("line 10000\n", "LINE 10000\n");
+        let remote = format!("title\n{changed}end\n");
+        assert_eq!(
+            merge_text(&format!("title\n{lines}end\n"), &local, &remote).unwrap(),
+            format!("TITLE\n{changed}END\n")
+        );
+
+        let conflicts = merge_text(base, "a\nB\nc\nd\n", "a\nb2\nc\nd\n").unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        match &conflicts[0] {
+            MergeConflict::Content {
+                line,
+                base,
+                local,
+                remote,
+            } => {
+                assert_eq!(*line, 2);
+                assert_eq!(base, &vec!["b".to_string()]);
+                assert_eq!(local, &vec!["B".to_string()]);
+                assert_eq!(remote, &vec!["b2".to_string()]);
+            }
+            _ => panic!("expect content conflict"),
+        }
+    }
+
+    #[test]
+    fn merge_post_test() {
+        let base = post("title", &[("tags", "a"), ("draft", "true")], "a\nb\n");
+        let local = post("new title", &[("tags", "a")], "a\nb\nc\n");
+        let remote = post(
+            "title",
+            &[("tags", "a"), ("draft", "true"), ("lang", "en")],
+            "z\na\nb\n",
+        );
+        let merged = merge(&base, &local, &remote).unwrap();
+        assert_eq!(merged.title, "new title");
+        assert_eq!(merged.content, "z\na\nb\nc\n");
+        assert_eq!(merged.metadata.len(), 2);
+        assert_eq!(merged.metadata.get("lang").unwrap(), "en");
+        assert!(!merged.metadata.contains_key("draft"));
+
+        let remote = post("remote title", &[("tags", "b")], "a\nb\n");
+        let local = post("local title", &[("tags", "c")], "a\nb\n");
+        let conflicts = merge(&base, &local, &remote).unwrap_err();
+        assert_eq!(conflicts.len(), 2);
+        assert!(matches!(conflicts[0], MergeConflict::Title { .. }));
+        assert!(matches!(conflicts[1], MergeConflict::Metadata { ref key, .. } if key == "tags"));
+    }
+}
@@ -0,0 +1,619 @@
+//! pipeline which transforms the content of a post for a target platform, see `TransformRule`.
+//! The markdown is parsed with the `markdown` crate, and the nodes are rewritten in place
+//! by their positions, so the rest of the content stays as it's written.
+
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use markdown::mdast::Node;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use regex::{Captures, Regex};
+
+use crate::types::{
+    posts::Post,
+    transforms::{CalloutStyle, RegexScope, TransformRule},
+};
+
+use super::{forge::ForgeSyncError, types::SyncError};
+
+lazy_static! {
+    /// the first line of a callout, e.g. `[!note] Title`, `[!faq]- Folded`
+    static ref CALLOUT_HEAD: Regex = Regex::new(r"^\[!([\w-]+)\][+-]?[ \t]*(.*)$").unwrap();
+    static ref HEXO_NOTE: Regex =
+        Regex::new(r"(?m)^\{% note ([\w-]+) %\}\n((?s:.*?))^\{% endnote %\}$").unwrap();
+    static ref HEXO_NOTE_TITLE: Regex = Regex::new(r"^\*\*(.*)\*\*$").unwrap();
+    static ref HTML_CALLOUT: Regex =
+        Regex::new(r#"(?m)^<div class="callout callout-([\w-]+)">\n((?s:.*?))^</div>$"#).unwrap();
+    static ref HTML_CALLOUT_TITLE: Regex =
+        Regex::new(r#"^<p class="callout-title">(.*)</p>$"#).unwrap();
+    /// `[[page#heading|text]]`, embeds like `![[image.png]]` are matched to be skipped
+    static ref WIKI_LINK: Regex =
+        Regex::new(r"(!?)\[\[([^\[\]|#]*)(#[^\[\]|]*)?(?:\|([^\[\]]*))?\]\]").unwrap();
+    static ref INLINE_LINK: Regex = Regex::new(r"^\[([^\[\]]*)\]\(([^\s()]+)\)$").unwrap();
+}
+
+/// characters which can't be in the destination of an inline link
+const LINK_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'(')
+    .add(b')')
+    .add(b'%');
+
+/// a transformation of the markdown content, the frontmatter isn't included
+pub(super) trait Transform: Send + Sync {
+    /// transform the content to be pushed
+    fn push(&self, content: &str) -> Result<String, SyncError>;
+
+    /// revert the transformation of the content pulled
+    fn pull(&self, content: &str) -> Result<String, SyncError>;
+}
+
+/// the transforms of a target, the content is left as it is if there are none
+#[derive(Default)]
+pub(super) struct Pipeline(Vec<Box<dyn Transform>>);
+
+impl Pipeline {
+    pub(super) fn new(rules: &[TransformRule]) -> Result<Pipeline, ForgeSyncError> {
+        let mut transforms: Vec<Box<dyn Transform>> = vec![];
+        for rule in rules {
+            transforms.push(match rule {
+                TransformRule::Callout { style } => Box::new(Callout(*style)),
+                TransformRule::WikiLink { prefix, suffix } => {
+                    if prefix.is_empty() {
+                        return Err(ForgeSyncError::UserError(
+                            "prefix of wiki-links can't be empty".to_string(),
+                        ));
+                    }
+                    Box::new(WikiLink {
+                        prefix: prefix.clone(),
+                        suffix: suffix.clone(),
+                    })
+                }
+                TransformRule::ImagePath { from, to } => Box::new(ImagePath {
+                    from: from.clone(),
+                    to: to.clone(),
+                }),
+                TransformRule::Regex {
+                    pattern,
+                    replacement,
+                    scope,
+                    reverse,
+                } => Box::new(RegexTransform {
+                    pattern: compile(pattern)?,
+                    replacement: replacement.clone(),
+                    scope: *scope,
+                    reverse: match reverse {
+                        Some(reverse) => {
+                            Some((compile(&reverse.pattern)?, reverse.replacement.clone()))
+                        }
+                        None => None,
+                    },
+                }),
+            });
+        }
+        Ok(Pipeline(transforms))
+    }
+
+    /// the post whose content is transformed in order
+    pub(super) fn push(&self, post: &Post) -> Result<Post, SyncError> {
+        let content = self.push_content(post.content().to_string())?;
+        Ok(post.clone().with_content(content))
+    }
+
+    pub(super) fn push_content(&self, content: String) -> Result<String, SyncError> {
+        self.0
+            .iter()
+            .try_fold(content, |content, transform| transform.push(&content))
+    }
+
+    /// revert the transforms of the content pulled in reverse order
+    pub(super) fn pull(&self, content: String) -> Result<String, SyncError> {
+        self.0
+            .iter()
+            .rev()
+            .try_fold(content, |content, transform| transform.pull(&content))
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, ForgeSyncError> {
+    Regex::new(pattern)
+        .map_err(|e| ForgeSyncError::UserError(format!("invalid regex {}: {}", pattern, e)))
+}
+
+/// `content[start..end]` is replaced with `text`
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// apply the edits, the ones overlapping with a previous edit are dropped
+fn apply(content: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|edit| edit.start);
+    let mut result = String::with_capacity(content.len());
+    let mut last = 0;
+    for edit in edits {
+        if edit.start < last {
+            continue;
+        }
+        result.push_str(&content[last..edit.start]);
+        result.push_str(&edit.text);
+        last = edit.end;
+    }
+    result.push_str(&content[last..]);
+    result
+}
+
+fn parse(content: &str) -> Result<Node, SyncError> {
+    Ok(markdown::to_mdast(
+        content,
+        &markdown::ParseOptions::default(),
+    )?)
+}
+
+/// visit the nodes in order, the children are visited if `visit` returns true
+fn walk<'a>(node: &'a Node, visit: &mut impl FnMut(&'a Node) -> bool) {
+    if visit(node) {
+        for child in node.children().into_iter().flatten() {
+            walk(child, visit);
+        }
+    }
+}
+
+fn span(node: &Node) -> Option<(usize, usize)> {
+    node.position()
+        .map(|position| (position.start.offset, position.end.offset))
+}
+
+/// edit the source of the text nodes, code and html are not text nodes
+fn edit_text(content: &str, edit: impl Fn(&str) -> Option<String>) -> Result<String, SyncError> {
+    let ast = parse(content)?;
+    let mut edits = vec![];
+    walk(&ast, &mut |node| {
+        if let (Node::Text(_), Some((start, end))) = (node, span(node)) {
+            if let Some(text) = edit(&content[start..end]) {
+                edits.push(Edit { start, end, text });
+            }
+        }
+        true
+    });
+    Ok(apply(content, edits))
+}
+
+/// Obsidian callouts out of the top level blockquotes
+struct Callout(CalloutStyle);
+
+impl Callout {
+    fn render(&self, kind: &str, title: &str, body: &str) -> String {
+        let mut lines = vec![];
+        match self.0 {
+            CalloutStyle::HexoTag => {
+                lines.push(format!("{{% note {} %}}", kind));
+                if !title.is_empty() {
+                    lines.push(format!("**{}**", title));
+                    if !body.is_empty() {
+                        lines.push(String::new());
+                    }
+                }
+                if !body.is_empty() {
+                    lines.push(body.to_string());
+                }
+                lines.push("{% endnote %}".to_string());
+            }
+            CalloutStyle::Html => {
+                lines.push(format!(r#"<div class="callout callout-{}">"#, kind));
+                if !title.is_empty() {
+                    lines.push(format!(r#"<p class="callout-title">{}</p>"#, title));
+                }
+                if !body.is_empty() {
+                    lines.push(String::new());
+                    lines.push(body.to_string());
+                }
+                // the body is parsed as markdown after a blank line
+                lines.push(String::new());
+                lines.push("</div>".to_string());
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// the callout of the inner lines of a hexo note or a html callout
+    fn revert(&self, caps: &Captures) -> String {
+        let title_pattern: &Regex = match self.0 {
+            CalloutStyle::HexoTag => &HEXO_NOTE_TITLE,
+            CalloutStyle::Html => &HTML_CALLOUT_TITLE,
+        };
+        let mut lines: Vec<&str> = caps[2].lines().collect();
+        let mut title = "";
+        if let Some(line) = lines.first() {
+            if let Some(title_caps) = title_pattern.captures(line) {
+                title = title_caps.get(1).map_or("", |m| m.as_str());
+                lines.remove(0);
+            }
+        }
+        if lines.first() == Some(&"") {
+            lines.remove(0);
+        }
+        if self.0 == CalloutStyle::Html && lines.last() == Some(&"") {
+            lines.pop();
+        }
+        let mut head = format!("> [!{}]", &caps[1]);
+        if !title.is_empty() {
+            head = format!("{} {}", head, title);
+        }
+        std::iter::once(head)
+            .chain(lines.into_iter().map(|line| match line {
+                "" => ">".to_string(),
+                line => format!("> {}", line),
+            }))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Transform for Callout {
+    fn push(&self, content: &str) -> Result<String, SyncError> {
+        let ast = parse(content)?;
+        let mut edits = vec![];
+        for node in ast.children().into_iter().flatten() {
+            let (start, end) = match (node, span(node)) {
+                (Node::BlockQuote(_), Some(span)) => span,
+                _ => continue,
+            };
+            let lines: Vec<&str> = content[start..end].lines().map(unquote).collect();
+            let caps = match lines.first().and_then(|line| CALLOUT_HEAD.captures(line)) {
+                Some(caps) => caps,
+                None => continue,
+            };
+            edits.push(Edit {
+                start,
+                end,
+                text: self.render(&caps[1], caps[2].trim(), &lines[1..].join("\n")),
+            });
+        }
+        Ok(apply(content, edits))
+    }
+
+    fn pull(&self, content: &str) -> Result<String, SyncError> {
+        let pattern: &Regex = match self.0 {
+            CalloutStyle::HexoTag => &HEXO_NOTE,
+            CalloutStyle::Html => &HTML_CALLOUT,
+        };
+        Ok(pattern
+            .replace_all(content, |caps: &Captures| self.revert(caps))
+            .into_owned())
+    }
+}
+
+/// the line of a blockquote without the `>` marker
+fn unquote(line: &str) -> &str {
+    let line = line.trim_start();
+    match line.strip_prefix('>') {
+        Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+        None => line,
+    }
+}
+
+/// Obsidian wiki-links to links under `prefix`
+struct WikiLink {
+    prefix: String,
+    suffix: String,
+}
+
+impl WikiLink {
+    fn link(&self, caps: &Captures) -> String {
+        if !caps[1].is_empty() {
+            return caps[0].to_string();
+        }
+        let page = caps[2].trim();
+        let anchor = caps.get(3).map_or("", |m| m.as_str());
+        let text = match caps.get(4) {
+            Some(text) => text.as_str(),
+            None if page.is_empty() => anchor.trim_start_matches('#'),
+            None => page,
+        };
+        let anchor = utf8_percent_encode(anchor, LINK_ENCODE_SET).to_string();
+        if page.is_empty() {
+            return format!("[{}]({})", text, anchor);
+        }
+        format!(
+            "[{}]({}{}{}{})",
+            text,
+            self.prefix,
+            utf8_percent_encode(page, LINK_ENCODE_SET),
+            self.suffix,
+            anchor
+        )
+    }
+
+    /// the wiki-link of an inline link under the prefix
+    fn wiki_link(&self, source: &str) -> Option<String> {
+        let caps = INLINE_LINK.captures(source)?;
+        let text = &caps[1];
+        let url = caps[2].strip_prefix(self.prefix.as_str())?;
+        let (page, anchor) = match url.find('#') {
+            Some(idx) => url.split_at(idx),
+            None => (url, ""),
+        };
+        let page = percent_decode_str(page.strip_suffix(self.suffix.as_str())?)
+            .decode_utf8()
+            .ok()?;
+        let anchor = percent_decode_str(anchor).decode_utf8().ok()?;
+        if text == page {
+            Some(format!("[[{}{}]]", page, anchor))
+        } else {
+            Some(format!("[[{}{}|{}]]", page, anchor, text))
+        }
+    }
+}
+
+impl Transform for WikiLink {
+    fn push(&self, content: &str) -> Result<String, SyncError> {
+        edit_text(content, |text| {
+            if !WIKI_LINK.is_match(text) {
+                return None;
+            }
+            Some(
+                WIKI_LINK
+                    .replace_all(text, |caps: &Captures| self.link(caps))
+                    .into_owned(),
+            )
+        })
+    }
+
+    fn pull(&self, content: &str) -> Result<String, SyncError> {
+        let ast = parse(content)?;
+        let mut edits = vec![];
+        walk(&ast, &mut |node| {
+            if let (Node::Link(link), Some((start, end))) = (node, span(node)) {
+                if link.url.starts_with(&self.prefix) {
+                    if let Some(text) = self.wiki_link(&content[start..end]) {
+                        edits.push(Edit { start, end, text });
+                    }
+                }
+                return false;
+            }
+            true
+        });
+        Ok(apply(content, edits))
+    }
+}
+
+/// the prefix of the destinations of images
+struct ImagePath {
+    from: String,
+    to: String,
+}
+
+/// replace the prefix `from` of the destinations of the images and of the definitions
+/// which are referenced by images
+fn rewrite_images(content: &str, from: &str, to: &str) -> Result<String, SyncError> {
+    let ast = parse(content)?;
+    let mut references = HashSet::new();
+    walk(&ast, &mut |node| {
+        if let Node::ImageReference(reference) = node {
+            references.insert(reference.identifier.clone());
+        }
+        true
+    });
+    let mut edits = vec![];
+    walk(&ast, &mut |node| {
+        let (url, marker) = match node {
+            Node::Image(image) => (&image.url, "]("),
+            Node::Definition(definition) if references.contains(&definition.identifier) => {
+                (&definition.url, "]:")
+            }
+            _ => return true,
+        };
+        if let (true, Some((start, end))) = (url.starts_with(from), span(node)) {
+            let source = &content[start..end];
+            if let Some(idx) = source.find(marker) {
+                let rest = &source[idx + marker.len()..];
+                let offset = idx + marker.len() + (rest.len() - rest.trim_start().len());
+                let offset = offset + usize::from(source[offset..].starts_with('<'));
+                if source[offset..].starts_with(from) {
+                    edits.push(Edit {
+                        start: start + offset,
+                        end: start + offset + from.len(),
+                        text: to.to_string(),
+                    });
+                }
+            }
+        }
+        true
+    });
+    Ok(apply(content, edits))
+}
+
+impl Transform for ImagePath {
+    fn push(&self, content: &str) -> Result<String, SyncError> {
+        rewrite_images(content, &self.from, &self.to)
+    }
+
+    fn pull(&self, content: &str) -> Result<String, SyncError> {
+        rewrite_images(content, &self.to, &self.from)
+    }
+}
+
+struct RegexTransform {
+    pattern: Regex,
+    replacement: String,
+    scope: RegexScope,
+    /// the pattern and the replacement to revert the transform
+    reverse: Option<(Regex, String)>,
+}
+
+impl RegexTransform {
+    fn replace(
+        &self,
+        content: &str,
+        pattern: &Regex,
+        replacement: &str,
+    ) -> Result<String, SyncError> {
+        match self.scope {
+            RegexScope::Content => Ok(pattern.replace_all(content, replacement).into_owned()),
+            RegexScope::Text => edit_text(content, |text| {
+                if !pattern.is_match(text) {
+                    return None;
+                }
+                Some(pattern.replace_all(text, replacement).into_owned())
+            }),
+        }
+    }
+}
+
+impl Transform for RegexTransform {
+    fn push(&self, content: &str) -> Result<String, SyncError> {
+        self.replace(content, &self.pattern, &self.replacement)
+    }
+
+    fn pull(&self, content: &str) -> Result<String, SyncError> {
+        match &self.reverse {
+            Some((pattern, replacement)) => self.replace(content, pattern, replacement),
+            None => Ok(content.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod transform_test {
+    use crate::types::transforms::RegexReplace;
+
+    use super::*;
+
+    fn pipeline(rules: Vec<TransformRule>) -> Pipeline {
+        Pipeline::new(&rules).unwrap()
+    }
+
+    fn push(pipeline: &Pipeline, content: &str) -> String {
+        let post = Post::new(
+            1,
+            1,
+            "title".to_string(),
+            Default::default(),
+            content.to_string(),
+            "".to_string(),
+            "".to_string(),
+            Default::default(),
+            Default::default(),
+        );
+        pipeline.push(&post).unwrap().content().to_string()
+    }
+
+    #[test]
+    fn callout_test() {
+        let content = "# Title\n\n> [!warning] Be careful\n> first\n>\n> **second**\n\n> quote\n";
+        let hexo = pipeline(vec![TransformRule::Callout {
+            style: CalloutStyle::HexoTag,
+        }]);
+        let pushed = push(&hexo, content);
+        assert_eq!(
+            pushed,
+            "# Title\n\n{% note warning %}\n**Be careful**\n\nfirst\n\n**second**\n{% endnote %}\n\n> quote\n"
+        );
+        assert_eq!(hexo.pull(pushed).unwrap(), content);
+
+        let html = pipeline(vec![TransformRule::Callout {
+            style: CalloutStyle::Html,
+        }]);
+        let pushed = push(&html, "> [!tip]\n> text\n");
+        assert_eq!(
+            pushed,
+            "<div class=\"callout callout-tip\">\n\ntext\n\n</div>\n"
+        );
+        assert_eq!(html.pull(pushed).unwrap(), "> [!tip]\n> text\n");
+    }
+
+    #[test]
+    fn wiki_link_test() {
+        let content = "See [[Hello World]], [[Rust#Error Handling|errors]] and ![[logo.png]].\n\n`[[code]]`\n";
+        let wiki = pipeline(vec![TransformRule::WikiLink {
+            prefix: "/posts/".to_string(),
+            suffix: "/".to_string(),
+        }]);
+        let pushed = push(&wiki, content);
+        assert_eq!(
+            pushed,
+            "See [Hello World](/posts/Hello%20World/), [errors](/posts/Rust/#Error%20Handling) and ![[logo.png]].\n\n`[[code]]`\n"
+        );
+        assert_eq!(wiki.pull(pushed).unwrap(), content);
+        // other links are left as they are
+        let other = "[home](/about/) and [Rust](/posts/Rust)\n";
+        assert_eq!(wiki.pull(other.to_string()).unwrap(), other);
+        assert!(Pipeline::new(&[TransformRule::WikiLink {
+            prefix: String::new(),
+            suffix: String::new(),
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn image_path_test() {
+        let content = "![a](attachments/a.png) ![b][b] [c](attachments/c.pdf)\n\n[b]: <attachments/b.png>\n\n```\n![a](attachments/a.png)\n```\n";
+        let images = pipeline(vec![TransformRule::ImagePath {
+            from: "attachments/".to_string(),
+            to: "/images/".to_string(),
+        }]);
+        let pushed = push(&images, content);
+        assert_eq!(
+            pushed,
+            "![a](/images/a.png) ![b][b] [c](attachments/c.pdf)\n\n[b]: </images/b.png>\n\n```\n![a](attachments/a.png)\n```\n"
+        );
+        assert_eq!(images.pull(pushed).unwrap(), content);
+    }
+
+    #[test]
+    fn regex_test() {
+        let content = "a -> b and `a -> b`\n";
+        let regex = pipeline(vec![TransformRule::Regex {
+            pattern: "(\\w) -> (\\w)".to_string(),
+            replacement: "$1 → $2".to_string(),
+            scope: RegexScope::Text,
+            reverse: Some(RegexReplace {
+                pattern: "(\\w) → (\\w)".to_string(),
+                replacement: "$1 -> $2".to_string(),
+            }),
+        }]);
+        let pushed = push(&regex, content);
+        assert_eq!(pushed, "a → b and `a -> b`\n");
+        assert_eq!(regex.pull(pushed).unwrap(), content);
+
+        assert!(Pipeline::new(&[TransformRule::Regex {
+            pattern: "(".to_string(),
+            replacement: String::new(),
+            scope: RegexScope::Content,
+            reverse: None,
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn pipeline_order_test() {
+        let pipeline = pipeline(vec![
+            TransformRule::Regex {
+                pattern: "a".to_string(),
+                replacement: "b".to_string(),
+                scope: RegexScope::Content,
+                reverse: Some(RegexReplace {
+                    pattern: "b".to_string(),
+                    replacement: "a".to_string(),
+                }),
+            },
+            TransformRule::Regex {
+                pattern: "b".to_string(),
+                replacement: "c".to_string(),
+                scope: RegexScope::Content,
+                reverse: Some(RegexReplace {
+                    pattern: "c".to_string(),
+                    replacement: "b".to_string(),
+                }),
+            },
+        ]);
+        assert_eq!(push(&pipeline, "a"), "c");
+        assert_eq!(pipeline.pull("c".to_string()).unwrap(), "a");
+    }
+}
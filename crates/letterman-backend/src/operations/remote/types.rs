@@ -4,15 +4,21 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use actix_web::error::BlockingError;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
 use thiserror::Error;
 
 use crate::{
     traits::DbActionError,
-    types::posts::{CreatePostError, QueryPostError},
+    types::{
+        credentials::QueryCredentialError,
+        posts::{CreatePostError, QueryPostError, SyncReq},
+        serialize_as_string,
+    },
 };
 
-
-
 #[derive(Debug, Default)]
 pub struct Context {
     data: Arc<Mutex<HashMap<String, Box<dyn Any + Send>>>>,
@@ -36,6 +42,151 @@ impl Context {
     }
 }
 
+/// state of a post compared with the last synchronized version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncStatus {
+    /// neither the post nor the remote article is changed
+    UpToDate,
+    /// only the post is changed
+    LocalAhead,
+    /// only the remote article is changed
+    RemoteAhead,
+    /// both the post and the remote article are changed
+    Diverged,
+    /// the post is never synchronized to the platform
+    NeverSynced,
+}
+
+impl SyncStatus {
+    pub fn new(local_changed: bool, remote_changed: bool) -> SyncStatus {
+        match (local_changed, remote_changed) {
+            (false, false) => SyncStatus::UpToDate,
+            (true, false) => SyncStatus::LocalAhead,
+            (false, true) => SyncStatus::RemoteAhead,
+            (true, true) => SyncStatus::Diverged,
+        }
+    }
+}
+
+/// where the post is published and what is published there currently
+#[derive(Debug, Clone, Default)]
+pub struct SyncTarget {
+    pub repository: Option<String>,
+    pub path: Option<String>,
+    /// raw content of the published file, `None` if it's never published
+    pub content: Option<String>,
+}
+
+/// what a sync operation is going to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncPlan {
+    /// nothing to do
+    Skip,
+    /// create the article in outer platform
+    Create,
+    /// overwrite the article in outer platform
+    Update,
+    /// merge both sides, then store and push the merged version
+    Merge,
+    /// import the article in outer platform as the latest version
+    Pull,
+}
+
+/// result of a sync operation in preview mode, nothing is written
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPreview {
+    pub plan: SyncPlan,
+    pub status: SyncStatus,
+    pub repository: Option<String>,
+    pub path: Option<String>,
+    /// the file which will be written, it's the remote file when pulling
+    pub content: Option<String>,
+    /// unified diff from the content to be overwritten to the new content
+    pub diff: String,
+    /// conflicts which stop the merge
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// result of synchronizing a post to one of the targets
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetResult {
+    pub target: SyncReq,
+    pub outcome: TargetOutcome,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum TargetOutcome {
+    Synced {
+        plan: SyncPlan,
+    },
+    /// the article is up to date, or only the remote article is changed
+    Skipped,
+    Failed {
+        message: String,
+        /// the same request may succeed later, e.g. a network error
+        retryable: bool,
+        conflict: Option<ConflictReport>,
+    },
+}
+
+impl From<Result<SyncPlan, SyncError>> for TargetOutcome {
+    fn from(value: Result<SyncPlan, SyncError>) -> Self {
+        match value {
+            Ok(SyncPlan::Skip) => TargetOutcome::Skipped,
+            Ok(plan) => TargetOutcome::Synced { plan },
+            Err(e) => TargetOutcome::Failed {
+                message: e.to_string(),
+                retryable: e.is_retryable(),
+                conflict: match e {
+                    SyncError::Conflict(report) => Some(report),
+                    _ => None,
+                },
+            },
+        }
+    }
+}
+
+/// result of synchronizing one of the posts in a bulk operation
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkSyncResult {
+    #[serde(serialize_with = "serialize_as_string")]
+    pub post_id: i64,
+    #[serde(flatten)]
+    pub result: TargetResult,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BulkSyncReport {
+    pub total: usize,
+    pub synced: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub results: Vec<BulkSyncResult>,
+}
+
+impl BulkSyncReport {
+    pub fn new(results: Vec<BulkSyncResult>) -> BulkSyncReport {
+        let mut report = BulkSyncReport {
+            total: results.len(),
+            ..Default::default()
+        };
+        for result in &results {
+            match result.result.outcome {
+                TargetOutcome::Synced { .. } => report.synced += 1,
+                TargetOutcome::Skipped => report.skipped += 1,
+                TargetOutcome::Failed { .. } => report.failed += 1,
+            }
+        }
+        report.results = results;
+        report
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 pub enum SyncError {
     #[error("Database Error")]
@@ -44,18 +195,85 @@ pub enum SyncError {
     NotFound,
     #[error("System cannot decide push or pull")]
     Ambiguous,
+    #[error("Local post and remote article are changed in conflicting ways")]
+    Conflict(ConflictReport),
     #[error("Failed to request remote server")]
     RemoteServer,
+    #[error("Rate limit of remote server is exceeded{}", reset_hint(.reset_at))]
+    RateLimited {
+        /// when the rate limit resets, in UTC
+        reset_at: Option<NaiveDateTime>,
+    },
+    #[error("Remote file not found: {0}")]
+    RemoteNotFound(String),
+    #[error("Remote file is changed since the last sync: {0}")]
+    RemoteConflict(String),
+    #[error("Remote server rejected the request: {0}")]
+    RemoteValidation(String),
     #[error("User error: {0}")]
     UserError(String),
     #[error("Network error: {0}")]
     NetworkError(String),
-    #[error("Decode Error")]
-    Decode,
+    #[error("Decode Error: {0}")]
+    Decode(String),
     #[error("Unknown error: {0}")]
     Other(String),
 }
 
+/// conflicts found when merging local post and remote article
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictReport {
+    #[serde(serialize_with = "serialize_as_string")]
+    pub post_id: i64,
+    /// version of the last synchronized post, which is the common ancestor
+    pub base_version: String,
+    pub local_version: String,
+    pub remote_version: String,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "field", rename_all = "camelCase")]
+pub enum MergeConflict {
+    Title {
+        base: String,
+        local: String,
+        remote: String,
+    },
+    /// `None` means the key is absent
+    Metadata {
+        key: String,
+        base: Option<Value>,
+        local: Option<Value>,
+        remote: Option<Value>,
+    },
+    /// lines changed by both sides, `line` is the first line number in base
+    Content {
+        line: usize,
+        base: Vec<String>,
+        local: Vec<String>,
+        remote: Vec<String>,
+    },
+}
+
+impl SyncError {
+    /// whether the failure is transient, so retrying the same request may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            SyncError::RemoteServer | SyncError::NetworkError(_) | SyncError::RateLimited { .. }
+        )
+    }
+}
+
+fn reset_hint(reset_at: &Option<NaiveDateTime>) -> String {
+    match reset_at {
+        Some(reset_at) => format!(", retry after {} UTC", reset_at.format("%Y-%m-%d %H:%M:%S")),
+        None => String::new(),
+    }
+}
+
 impl From<DbActionError<QueryPostError>> for SyncError {
     fn from(value: DbActionError<QueryPostError>) -> Self {
         match value {
@@ -93,9 +311,32 @@ impl From<CreatePostError> for SyncError {
     }
 }
 
+impl From<DbActionError<QueryCredentialError>> for SyncError {
+    fn from(value: DbActionError<QueryCredentialError>) -> Self {
+        match value {
+            DbActionError::Error(QueryCredentialError::Database) => SyncError::Database,
+            DbActionError::Error(e) => SyncError::UserError(e.to_string()),
+            DbActionError::Pool(_) => SyncError::Database,
+            DbActionError::Canceled => SyncError::Database,
+        }
+    }
+}
+
+impl From<BlockingError> for SyncError {
+    fn from(_: BlockingError) -> Self {
+        SyncError::Other("The request is canceled".to_string())
+    }
+}
+
 impl From<reqwest::Error> for SyncError {
     fn from(value: reqwest::Error) -> Self {
-        SyncError::Other(value.to_string())
+        if value.is_timeout() || value.is_connect() || value.is_request() || value.is_body() {
+            SyncError::NetworkError(value.to_string())
+        } else if value.is_decode() {
+            SyncError::Other(format!("failed to decode the response: {}", value))
+        } else {
+            SyncError::Other(value.to_string())
+        }
     }
 }
 
@@ -0,0 +1,207 @@
+//! render markdown to sanitized html, with GFM, footnotes, math and highlighted code,
+//! so the content can be previewed as a target receives it.
+
+use diesel::{r2d2::ConnectionManager, MysqlConnection};
+use lazy_static::lazy_static;
+use markdown::{CompileOptions, Constructs, Options, ParseOptions};
+use r2d2::Pool;
+use regex::{Captures, Regex};
+use syntect::{
+    highlighting::ThemeSet,
+    html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+use crate::{
+    traits::DbAction,
+    types::render::{RenderReq, RenderedVO},
+};
+
+use super::{
+    posts::{LatestPostQueryerByPostId, PostQueryerByPostIdAndVersion},
+    remote::{self, types::SyncError},
+};
+
+/// the classes of the highlighted code are prefixed, so they don't clash with the page
+const HIGHLIGHT_CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+pub const DEFAULT_HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+lazy_static! {
+    static ref SYNTAX_SET: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    static ref THEME_SET: ThemeSet = ThemeSet::load_defaults();
+    static ref CODE_BLOCK: Regex =
+        Regex::new(r#"<pre><code class="language-([^"]+)">((?s:.*?))</code></pre>"#).unwrap();
+    static ref SANITIZER: ammonia::Builder<'static> = {
+        let mut builder = ammonia::Builder::default();
+        builder
+            .add_tags(["section", "input"])
+            .add_generic_attributes(["class", "id", "aria-describedby", "aria-label"])
+            .add_generic_attribute_prefixes(["data-footnote"])
+            .add_tag_attributes("input", ["type", "checked", "disabled"])
+            .add_tag_attributes("td", ["align"])
+            .add_tag_attributes("th", ["align"]);
+        builder
+    };
+}
+
+/// render the post or the markdown of the request, the transforms of the target are applied
+/// to the content first
+pub(crate) async fn render(
+    req: RenderReq,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+) -> Result<RenderedVO, SyncError> {
+    let (title, content) = match (req.post_id, req.content) {
+        (Some(post_id), _) => {
+            let post = match req.version {
+                Some(version) => {
+                    PostQueryerByPostIdAndVersion(post_id, version)
+                        .execute(pool)
+                        .await?
+                }
+                None => LatestPostQueryerByPostId(post_id).execute(pool).await?,
+            };
+            let post = match &req.target {
+                Some(target) => remote::transform_post(&post, target.transforms())?,
+                None => post,
+            };
+            (Some(post.title().to_string()), post.content().to_string())
+        }
+        (None, Some(content)) => match &req.target {
+            Some(target) => (
+                None,
+                remote::transform_content(content, target.transforms())?,
+            ),
+            None => (None, content),
+        },
+        (None, None) => return Err(SyncError::UserError("content is required".to_string())),
+    };
+    Ok(RenderedVO {
+        title,
+        html: render_html(&content)?,
+    })
+}
+
+/// sanitized html of the markdown
+pub fn render_html(content: &str) -> Result<String, markdown::message::Message> {
+    let options = Options {
+        parse: ParseOptions {
+            constructs: Constructs {
+                math_flow: true,
+                math_text: true,
+                ..Constructs::gfm()
+            },
+            ..ParseOptions::gfm()
+        },
+        compile: CompileOptions {
+            // raw html is kept for the transforms like callouts, it's sanitized at last
+            allow_dangerous_html: true,
+            ..CompileOptions::gfm()
+        },
+    };
+    let html = markdown::to_html_with_options(content, &options)?;
+    let html = CODE_BLOCK.replace_all(&html, |caps: &Captures| highlight(&caps[1], &caps[2]));
+    Ok(SANITIZER.clean(&html).to_string())
+}
+
+/// highlight the escaped code of a code block, it's left as it is if the language is unknown
+fn highlight(language: &str, escaped: &str) -> String {
+    let unchanged = || {
+        format!(
+            r#"<pre><code class="language-{}">{}</code></pre>"#,
+            language, escaped
+        )
+    };
+    let syntax = match SYNTAX_SET.find_syntax_by_token(language) {
+        Some(syntax) => syntax,
+        None => return unchanged(),
+    };
+    let code = unescape(escaped);
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, HIGHLIGHT_CLASS_STYLE);
+    for line in LinesWithEndings::from(&code) {
+        if generator
+            .parse_html_for_line_which_includes_newline(line)
+            .is_err()
+        {
+            return unchanged();
+        }
+    }
+    format!(
+        r#"<pre class="hl-code"><code class="language-{}">{}</code></pre>"#,
+        language,
+        generator.finalize()
+    )
+}
+
+/// revert the escaping of the code by `markdown`
+fn unescape(escaped: &str) -> String {
+    escaped
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// the css of the classes of the highlighted code, `None` if the theme doesn't exist
+pub fn highlight_css(theme: &str) -> Option<String> {
+    THEME_SET
+        .themes
+        .get(theme)
+        .and_then(|theme| css_for_theme_with_class_style(theme, HIGHLIGHT_CLASS_STYLE).ok())
+}
+
+#[cfg(test)]
+mod render_test {
+    use super::*;
+
+    #[test]
+    fn render_gfm_test() {
+        let html = render_html(
+            "| a | b |\n| :- | -: |\n| 1 | 2 |\n\n- [x] done\n\n~~old~~ text[^1]\n\n[^1]: note\n",
+        )
+        .unwrap();
+        assert!(html.contains("<table>"));
+        assert!(html.contains(r#"<td align="left">1</td>"#));
+        assert!(html.contains(r#"<input type="checkbox" disabled="" checked="""#));
+        assert!(html.contains("<del>old</del>"));
+        assert!(html.contains(r#"<section data-footnotes="" class="footnotes">"#));
+    }
+
+    #[test]
+    fn render_math_test() {
+        let html = render_html("$a^2$\n\n$$\n\\sum x\n$$\n").unwrap();
+        assert!(html.contains(r#"<code class="language-math math-inline">a^2</code>"#));
+        assert!(
+            html.contains("<pre><code class=\"language-math math-display\">\\sum x\n</code></pre>")
+        );
+    }
+
+    #[test]
+    fn highlight_test() {
+        let html = render_html("```rust\nfn main() { let s = \"<a>\"; }\n```\n").unwrap();
+        assert!(html.contains(r#"<pre class="hl-code"><code class="language-rust">"#));
+        assert!(html.contains(r#"<span class="hl-storage hl-type hl-function hl-rust">fn</span>"#));
+        assert!(html.contains("&lt;a&gt;"));
+        // unknown languages are left as they are
+        let html = render_html("```unknown\n<a>\n```\n").unwrap();
+        assert!(html.contains(r#"<pre><code class="language-unknown">&lt;a&gt;"#));
+        assert!(highlight_css(DEFAULT_HIGHLIGHT_THEME)
+            .unwrap()
+            .contains(".hl-"));
+        assert!(highlight_css("unknown").is_none());
+    }
+
+    #[test]
+    fn sanitize_test() {
+        let html = render_html(
+            "<script>alert(1)</script>\n\n<div class=\"callout\" onclick=\"x()\">\n\n[a](javascript:alert(1))\n\n</div>\n",
+        )
+        .unwrap();
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onclick"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains(r#"<div class="callout">"#));
+    }
+}
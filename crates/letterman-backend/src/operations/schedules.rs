@@ -0,0 +1,285 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    BoolExpressionMethods, Connection, ExpressionMethods, MysqlConnection, OptionalExtension,
+    QueryDsl, RunQueryDsl,
+};
+
+use crate::{
+    schema::{t_post, t_post_schedule},
+    traits::DbAction,
+    types::{
+        schedules::{
+            CreateScheduleError, CreateScheduleReq, DeleteScheduleError, InsertablePostSchedule,
+            PostSchedule, PostScheduleVO, QueryScheduleError, SchedulePageReq, ScheduleStatus,
+            UpdateScheduleError, UpdateScheduleReq,
+        },
+        Page,
+    },
+    utils,
+};
+
+use super::{jobs::lease_expire_time, pagination::Paginate};
+
+pub mod scheduler;
+
+/// schedule the version of the post, the latest version is used if it's not given
+pub struct ScheduleCreator(pub CreateScheduleReq);
+
+impl DbAction for ScheduleCreator {
+    type Item = PostSchedule;
+
+    type Error = CreateScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let req = self.0;
+        let mut query = t_post::table
+            .filter(t_post::post_id.eq(req.post_id))
+            .select(t_post::version)
+            .into_boxed();
+        query = match req.version {
+            Some(version) => query.filter(t_post::version.eq(version)),
+            None => query.filter(t_post::head.eq(true)),
+        };
+        let version: String = query
+            .first(conn)
+            .optional()?
+            .ok_or(CreateScheduleError::PostNotFound)?;
+        let schedule =
+            InsertablePostSchedule::new(req.post_id, version, &req.targets, req.publish_at)?;
+        let id = schedule.id;
+        diesel::insert_into(t_post_schedule::table)
+            .values(schedule)
+            .execute(conn)?;
+        Ok(t_post_schedule::table.find(id).first(conn)?)
+    }
+}
+
+pub struct ScheduleQueryer(pub i64);
+
+impl DbAction for ScheduleQueryer {
+    type Item = PostSchedule;
+
+    type Error = QueryScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        Ok(t_post_schedule::table.find(self.0).first(conn)?)
+    }
+}
+
+pub struct SchedulePageQueryer(pub SchedulePageReq);
+
+impl DbAction for SchedulePageQueryer {
+    type Item = Page<PostScheduleVO>;
+
+    type Error = QueryScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let mut query = t_post_schedule::table.into_boxed();
+        if let Some(post_id) = self.0.post_id {
+            query = query.filter(t_post_schedule::post_id.eq(post_id));
+        }
+        if let Some(status) = self.0.status {
+            query = query.filter(t_post_schedule::status.eq(status.to_string()));
+        }
+        let (schedules, total) = query
+            .order_by(t_post_schedule::publish_at.desc())
+            .paginate(self.0.page)
+            .page_size(self.0.page_size)
+            .load_and_count_pages::<PostSchedule>(conn)?;
+        let schedules = schedules.into_iter().map(PostScheduleVO::from).collect();
+        Ok(Page::new(total, self.0.page, schedules, self.0.page_size))
+    }
+}
+
+/// change a pending or failed schedule, it's pending again after that
+pub struct ScheduleUpdater(pub UpdateScheduleReq);
+
+impl DbAction for ScheduleUpdater {
+    type Item = PostSchedule;
+
+    type Error = UpdateScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let req = self.0;
+        conn.transaction(|conn| {
+            let schedule: PostSchedule = t_post_schedule::table
+                .find(req.id)
+                .for_update()
+                .first(conn)?;
+            if schedule.status != ScheduleStatus::Pending.to_string()
+                && schedule.status != ScheduleStatus::Failed.to_string()
+            {
+                return Err(UpdateScheduleError::Locked);
+            }
+            let version = match req.version {
+                Some(version) => t_post::table
+                    .filter(
+                        t_post::post_id
+                            .eq(schedule.post_id)
+                            .and(t_post::version.eq(&version)),
+                    )
+                    .select(t_post::version)
+                    .first::<String>(conn)
+                    .optional()?
+                    .ok_or(UpdateScheduleError::PostNotFound)?,
+                None => schedule.version,
+            };
+            let targets = match req.targets {
+                Some(targets) => serde_json::to_string(&targets)?,
+                None => schedule.targets,
+            };
+            diesel::update(t_post_schedule::table.find(req.id))
+                .set((
+                    t_post_schedule::version.eq(version),
+                    t_post_schedule::targets.eq(targets),
+                    t_post_schedule::publish_at.eq(req.publish_at.unwrap_or(schedule.publish_at)),
+                    t_post_schedule::status.eq(ScheduleStatus::Pending.to_string()),
+                    t_post_schedule::result.eq(None::<String>),
+                    t_post_schedule::update_time.eq(utils::time_utils::now()),
+                ))
+                .execute(conn)?;
+            Ok(t_post_schedule::table.find(req.id).first(conn)?)
+        })
+    }
+}
+
+/// cancel the schedule, it can't be deleted while it's running
+pub struct ScheduleDeleter(pub i64);
+
+impl DbAction for ScheduleDeleter {
+    type Item = ();
+
+    type Error = DeleteScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        conn.transaction(|conn| {
+            let schedule: PostSchedule = t_post_schedule::table
+                .find(self.0)
+                .for_update()
+                .first(conn)?;
+            if schedule.status == ScheduleStatus::Running.to_string() {
+                return Err(DeleteScheduleError::Running);
+            }
+            diesel::delete(t_post_schedule::table.find(self.0)).execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+/// take the earliest schedule whose publish time is reached and mark it as running
+pub struct DueScheduleClaimer;
+
+impl DbAction for DueScheduleClaimer {
+    type Item = Option<PostSchedule>;
+
+    type Error = UpdateScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let now = utils::time_utils::now();
+        conn.transaction(|conn| {
+            let schedule: Option<PostSchedule> = t_post_schedule::table
+                .filter(
+                    t_post_schedule::status
+                        .eq(ScheduleStatus::Pending.to_string())
+                        .and(t_post_schedule::publish_at.le(now)),
+                )
+                .order_by(t_post_schedule::publish_at.asc())
+                .for_update()
+                .skip_locked()
+                .first(conn)
+                .optional()?;
+            let schedule = match schedule {
+                Some(schedule) => schedule,
+                None => return Ok(None),
+            };
+            diesel::update(t_post_schedule::table.find(schedule.id))
+                .set((
+                    t_post_schedule::status.eq(ScheduleStatus::Running.to_string()),
+                    t_post_schedule::lease_expire_time.eq(Some(lease_expire_time())),
+                    t_post_schedule::update_time.eq(now),
+                ))
+                .execute(conn)?;
+            Ok(Some(PostSchedule {
+                status: ScheduleStatus::Running.to_string(),
+                lease_expire_time: Some(lease_expire_time()),
+                update_time: now,
+                ..schedule
+            }))
+        })
+    }
+}
+
+/// record the status and the results of the targets
+pub struct ScheduleFinisher(pub i64, pub ScheduleStatus, pub Option<String>);
+
+impl DbAction for ScheduleFinisher {
+    type Item = ();
+
+    type Error = UpdateScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::update(t_post_schedule::table.find(self.0))
+            .set((
+                t_post_schedule::status.eq(self.1.to_string()),
+                t_post_schedule::result.eq(self.2),
+                t_post_schedule::lease_expire_time.eq(None::<NaiveDateTime>),
+                t_post_schedule::update_time.eq(utils::time_utils::now()),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// extend the lease of the running schedule, so it isn't run again by the other schedulers
+pub struct ScheduleLeaseRenewer(pub i64);
+
+impl DbAction for ScheduleLeaseRenewer {
+    type Item = ();
+
+    type Error = UpdateScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::update(
+            t_post_schedule::table.filter(
+                t_post_schedule::id
+                    .eq(self.0)
+                    .and(t_post_schedule::status.eq(ScheduleStatus::Running.to_string())),
+            ),
+        )
+        .set(t_post_schedule::lease_expire_time.eq(Some(lease_expire_time())))
+        .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// run the schedules again whose lease is expired, their schedulers are gone,
+/// e.g. the server is restarted. The schedules run by the live schedulers are left alone.
+pub struct RunningScheduleResetter;
+
+impl DbAction for RunningScheduleResetter {
+    type Item = usize;
+
+    type Error = UpdateScheduleError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let now = utils::time_utils::now();
+        Ok(diesel::update(
+            t_post_schedule::table.filter(
+                t_post_schedule::status
+                    .eq(ScheduleStatus::Running.to_string())
+                    .and(
+                        // the schedules claimed before the leases are recorded have no lease
+                        t_post_schedule::lease_expire_time
+                            .is_null()
+                            .or(t_post_schedule::lease_expire_time.lt(now)),
+                    ),
+            ),
+        )
+        .set((
+            t_post_schedule::status.eq(ScheduleStatus::Pending.to_string()),
+            t_post_schedule::lease_expire_time.eq(None::<NaiveDateTime>),
+            t_post_schedule::update_time.eq(now),
+        ))
+        .execute(conn)?)
+    }
+}
@@ -0,0 +1,191 @@
+//! publish the scheduled versions of posts when their time comes.
+
+use std::time::Duration;
+
+use diesel::{r2d2::ConnectionManager, MysqlConnection};
+use log::{error, info};
+use r2d2::Pool;
+
+use crate::{
+    operations::{
+        jobs::JOB_LEASE_SECONDS,
+        posts::PostQueryerByPostIdAndVersion,
+        remote::{
+            self,
+            types::{SyncError, TargetOutcome, TargetResult},
+        },
+    },
+    traits::{DbAction, DbActionError},
+    types::{
+        posts::SyncReq,
+        schedules::{PostSchedule, ScheduleStatus, UpdateScheduleError},
+    },
+};
+
+use super::{DueScheduleClaimer, RunningScheduleResetter, ScheduleFinisher, ScheduleLeaseRenewer};
+
+/// how long the scheduler waits before looking for due schedules again
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// the lease of a running schedule is renewed several times before it expires
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(JOB_LEASE_SECONDS as u64 / 3);
+
+/// spawn the scheduler on the current system.
+/// interrupted schedules are published again before that, and periodically.
+pub async fn start(
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<(), DbActionError<UpdateScheduleError>> {
+    reset_expired(pool.clone()).await?;
+    actix_rt::spawn(schedule(pool.clone(), mongo_db));
+    actix_rt::spawn(async move {
+        loop {
+            actix_rt::time::sleep(Duration::from_secs(JOB_LEASE_SECONDS as u64)).await;
+            if let Err(e) = reset_expired(pool.clone()).await {
+                error!("failed to reset interrupted schedules: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn reset_expired(
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+) -> Result<(), DbActionError<UpdateScheduleError>> {
+    let reset = RunningScheduleResetter.execute(pool).await?;
+    if reset > 0 {
+        info!("{} interrupted schedules are pending again", reset);
+    }
+    Ok(())
+}
+
+/// renew the lease of the schedule until it's aborted
+async fn heartbeat(id: i64, pool: Pool<ConnectionManager<MysqlConnection>>) {
+    loop {
+        actix_rt::time::sleep(HEARTBEAT_INTERVAL).await;
+        if let Err(e) = ScheduleLeaseRenewer(id).execute(pool.clone()).await {
+            error!("failed to renew the lease of schedule {}: {}", id, e);
+        }
+    }
+}
+
+async fn schedule(pool: Pool<ConnectionManager<MysqlConnection>>, mongo_db: mongodb::Database) {
+    loop {
+        match DueScheduleClaimer.execute(pool.clone()).await {
+            Ok(Some(schedule)) => run(schedule, pool.clone(), mongo_db.clone()).await,
+            Ok(None) => actix_rt::time::sleep(POLL_INTERVAL).await,
+            Err(e) => {
+                error!("failed to claim schedule: {}", e);
+                actix_rt::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// publish the version to every target and record the results in the schedule
+async fn run(
+    schedule: PostSchedule,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) {
+    let finished = match schedule.targets() {
+        Ok(targets) => {
+            let heartbeat = actix_rt::spawn(heartbeat(schedule.id, pool.clone()));
+            let results = publish(&schedule, targets, pool.clone(), mongo_db).await;
+            heartbeat.abort();
+            for result in &results {
+                if let TargetOutcome::Failed { message, .. } = &result.outcome {
+                    error!(
+                        "failed to publish schedule {} to {:?}: {}",
+                        schedule.id, result.target, message
+                    );
+                }
+            }
+            let status = schedule_status(&results);
+            let results = serde_json::to_string(&results).ok();
+            ScheduleFinisher(schedule.id, status, results)
+                .execute(pool)
+                .await
+        }
+        Err(e) => {
+            error!("invalid targets of schedule {}: {}", schedule.id, e);
+            ScheduleFinisher(schedule.id, ScheduleStatus::Failed, None)
+                .execute(pool)
+                .await
+        }
+    };
+    if let Err(e) = finished {
+        error!("failed to update schedule {}: {}", schedule.id, e);
+    }
+}
+
+/// the schedule is failed if any of the targets is failed
+fn schedule_status(results: &[TargetResult]) -> ScheduleStatus {
+    if results
+        .iter()
+        .any(|r| matches!(r.outcome, TargetOutcome::Failed { .. }))
+    {
+        ScheduleStatus::Failed
+    } else {
+        ScheduleStatus::Published
+    }
+}
+
+async fn publish(
+    schedule: &PostSchedule,
+    targets: Vec<SyncReq>,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Vec<TargetResult> {
+    let post = PostQueryerByPostIdAndVersion(schedule.post_id, schedule.version.clone())
+        .execute(pool.clone())
+        .await;
+    match post {
+        Ok(post) => remote::publish_post(targets, post, pool, mongo_db).await,
+        Err(e) => {
+            let e = SyncError::from(e);
+            targets
+                .into_iter()
+                .map(|target| TargetResult {
+                    target,
+                    outcome: TargetOutcome::Failed {
+                        message: e.to_string(),
+                        retryable: e.is_retryable(),
+                        conflict: None,
+                    },
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod scheduler_test {
+    use crate::operations::remote::types::SyncPlan;
+
+    use super::*;
+
+    fn result(outcome: TargetOutcome) -> TargetResult {
+        TargetResult {
+            target: SyncReq::LocalGit(Default::default()),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn schedule_status_test() {
+        let mut results = vec![
+            result(TargetOutcome::Synced {
+                plan: SyncPlan::Create,
+            }),
+            result(TargetOutcome::Skipped),
+        ];
+        assert_eq!(schedule_status(&results), ScheduleStatus::Published);
+        results.push(result(TargetOutcome::Failed {
+            message: "Network Error".to_string(),
+            retryable: true,
+            conflict: None,
+        }));
+        assert_eq!(schedule_status(&results), ScheduleStatus::Failed);
+    }
+}
@@ -0,0 +1,183 @@
+use diesel::{
+    r2d2::ConnectionManager, BoolExpressionMethods, ExpressionMethods, MysqlConnection, QueryDsl,
+    RunQueryDsl,
+};
+use log::{error, info};
+use r2d2::Pool;
+
+use crate::{
+    operations::{
+        github_record::GithubRecordQueryerByPath,
+        posts::{LatestPostQueryerByPostId, PostLatestSyncRecordQueryer},
+        remote::{
+            self,
+            factory::SyncerFactory,
+            github,
+            types::{SyncError, SyncStatus},
+        },
+    },
+    schema::t_post_divergence,
+    traits::{DbAction, DbActionError, MongoAction},
+    types::{
+        commits::CommitOptions,
+        posts::{SyncRecord, SyncReq},
+        webhooks::{
+            DivergenceError, GithubPushEvent, InsertablePostDivergence, PostDivergence,
+            PostDivergenceVO,
+        },
+        Platform,
+    },
+};
+
+pub struct DivergenceCreator(pub InsertablePostDivergence);
+
+impl DbAction for DivergenceCreator {
+    type Item = ();
+
+    type Error = DivergenceError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::insert_into(t_post_divergence::table)
+            .values(self.0)
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// remove the divergences of the file, after the post and the remote article agree again
+pub struct DivergenceClearer(pub i64, pub Platform, pub String, pub String);
+
+impl DbAction for DivergenceClearer {
+    type Item = ();
+
+    type Error = DivergenceError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        diesel::delete(
+            t_post_divergence::table.filter(
+                t_post_divergence::post_id
+                    .eq(self.0)
+                    .and(t_post_divergence::platform.eq(self.1.to_string()))
+                    .and(t_post_divergence::repository.eq(self.2))
+                    .and(t_post_divergence::path.eq(self.3)),
+            ),
+        )
+        .execute(conn)?;
+        Ok(())
+    }
+}
+
+pub struct DivergenceQueryerByPostId(pub i64);
+
+impl DbAction for DivergenceQueryerByPostId {
+    type Item = Vec<PostDivergenceVO>;
+
+    type Error = DivergenceError;
+
+    fn db_action(self, conn: &mut MysqlConnection) -> Result<Self::Item, Self::Error> {
+        let divergences: Vec<PostDivergence> = t_post_divergence::table
+            .filter(t_post_divergence::post_id.eq(self.0))
+            .order_by(t_post_divergence::create_time.desc())
+            .load(conn)?;
+        Ok(divergences
+            .into_iter()
+            .map(PostDivergenceVO::from)
+            .collect())
+    }
+}
+
+/// import the articles changed by the push to the branch their targets commit to
+pub(crate) async fn handle_github_push(
+    event: GithubPushEvent,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) {
+    let repository = &event.repository.full_name;
+    for path in event.changed_paths() {
+        let result = handle_github_file(&event, &path, pool.clone(), mongo_db.clone()).await;
+        if let Err(e) = result {
+            error!("failed to import {} of {}: {}", path, repository, e);
+        }
+    }
+}
+
+async fn handle_github_file(
+    event: &GithubPushEvent,
+    path: &str,
+    pool: Pool<ConnectionManager<MysqlConnection>>,
+    mongo_db: mongodb::Database,
+) -> Result<(), SyncError> {
+    let repository = event.repository.full_name.as_str();
+    let commit = event.after.as_str();
+    let record = match GithubRecordQueryerByPath(repository.to_string(), path.to_string())
+        .execute(mongo_db.clone())
+        .await?
+    {
+        Some(record) if !record.deleted() => record,
+        // not an article of letterman, or it's unpublished
+        _ => return Ok(()),
+    };
+    let post_id = record.post_id();
+    // the post may be published to another place since then
+    let is_latest = PostLatestSyncRecordQueryer(post_id)
+        .execute(mongo_db.clone())
+        .await?
+        .iter()
+        .any(|latest| {
+            matches!(latest, SyncRecord::Github(latest)
+                if latest.repository() == repository && latest.path() == path)
+        });
+    if !is_latest {
+        return Ok(());
+    }
+
+    // the target is rebuilt with its own host, branch, credential and transforms
+    let target = SyncReq::from_record(&SyncRecord::Github(record))?;
+    if let SyncReq::Github(github) = &target {
+        let api_url = github.api_url().unwrap_or_else(github::default_api_url);
+        let branch = github.commit_options().or(CommitOptions::from_env()).branch;
+        if !event.is_sent_by(&api_url) || !event.is_branch(branch.as_deref()) {
+            return Ok(());
+        }
+    }
+    let syncer = SyncerFactory::create(target, pool.clone()).await?;
+    let post = LatestPostQueryerByPostId(post_id)
+        .execute(pool.clone())
+        .await?;
+    let status = remote::import_remote_changes(syncer, &post, pool.clone(), mongo_db).await?;
+    match status {
+        SyncStatus::Diverged => {
+            DivergenceCreator(InsertablePostDivergence::new(
+                post_id,
+                Platform::Github,
+                repository.to_string(),
+                path.to_string(),
+                post.version().to_string(),
+                commit.to_string(),
+            ))
+            .execute(pool)
+            .await?;
+            info!(
+                "post {} is diverged from {} of {}",
+                post_id, path, repository
+            );
+        }
+        _ => {
+            DivergenceClearer(
+                post_id,
+                Platform::Github,
+                repository.to_string(),
+                path.to_string(),
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+impl From<DbActionError<DivergenceError>> for SyncError {
+    fn from(_: DbActionError<DivergenceError>) -> Self {
+        SyncError::Database
+    }
+}
@@ -1,2 +1,7 @@
+pub mod credentials;
+pub mod jobs;
 pub mod posts;
+pub mod render;
+pub mod schedules;
+pub mod webhooks;
 pub mod common;
@@ -0,0 +1,135 @@
+use actix_web::web::{Data, Json, Path, Query};
+use actix_web::HttpResponse;
+
+use crate::operations::credentials::{
+    CredentialCreator, CredentialDeleter, CredentialPageQueryer, CredentialQueryer,
+    CredentialUpdater,
+};
+use crate::traits::{DbAction, DbActionError, Validate};
+use crate::types::credentials::{
+    CreateCredentialError, CreateCredentialReq, CredentialPageReq, CredentialVO,
+    DeleteCredentialError, QueryCredentialError, UpdateCredentialError, UpdateCredentialReq,
+};
+use crate::types::CommonResult;
+use crate::State;
+
+use super::posts::PostResponseError;
+
+pub(crate) async fn create_credential(
+    state: Data<State>,
+    req: Json<CreateCredentialReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let credential = CredentialCreator(req).execute(state.pool.clone()).await?;
+    Ok(
+        HttpResponse::Ok().json(CommonResult::success_with_data(CredentialVO::from(
+            credential,
+        ))),
+    )
+}
+
+pub(crate) async fn get_credential(
+    state: Data<State>,
+    id: Path<i64>,
+) -> Result<HttpResponse, PostResponseError> {
+    let id = id.into_inner();
+    let credential = CredentialQueryer(id).execute(state.pool.clone()).await?;
+    Ok(
+        HttpResponse::Ok().json(CommonResult::success_with_data(CredentialVO::from(
+            credential,
+        ))),
+    )
+}
+
+pub(crate) async fn get_credential_list(
+    state: Data<State>,
+    req: Query<CredentialPageReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let page = CredentialPageQueryer(req)
+        .execute(state.pool.clone())
+        .await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(page)))
+}
+
+pub(crate) async fn update_credential(
+    state: Data<State>,
+    req: Json<UpdateCredentialReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let credential = CredentialUpdater(req).execute(state.pool.clone()).await?;
+    Ok(
+        HttpResponse::Ok().json(CommonResult::success_with_data(CredentialVO::from(
+            credential,
+        ))),
+    )
+}
+
+pub(crate) async fn delete_credential(
+    state: Data<State>,
+    id: Path<i64>,
+) -> Result<HttpResponse, PostResponseError> {
+    let id = id.into_inner();
+    CredentialDeleter(id).execute(state.pool.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::<()>::success()))
+}
+
+impl From<DbActionError<CreateCredentialError>> for PostResponseError {
+    fn from(item: DbActionError<CreateCredentialError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                CreateCredentialError::Database => PostResponseError::Database,
+                CreateCredentialError::Duplicate | CreateCredentialError::Secret(_) => {
+                    PostResponseError::UserError { msg: e.to_string() }
+                }
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<QueryCredentialError>> for PostResponseError {
+    fn from(item: DbActionError<QueryCredentialError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                QueryCredentialError::Database => PostResponseError::Database,
+                QueryCredentialError::NotFound => PostResponseError::NotFound,
+                QueryCredentialError::PlatformMismatch(_) | QueryCredentialError::Secret(_) => {
+                    PostResponseError::UserError { msg: e.to_string() }
+                }
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<UpdateCredentialError>> for PostResponseError {
+    fn from(item: DbActionError<UpdateCredentialError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                UpdateCredentialError::Database => PostResponseError::Database,
+                UpdateCredentialError::NotFound => PostResponseError::NotFound,
+                UpdateCredentialError::Duplicate | UpdateCredentialError::Secret(_) => {
+                    PostResponseError::UserError { msg: e.to_string() }
+                }
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<DeleteCredentialError>> for PostResponseError {
+    fn from(item: DbActionError<DeleteCredentialError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                DeleteCredentialError::Database => PostResponseError::Database,
+                DeleteCredentialError::NotFound => PostResponseError::NotFound,
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
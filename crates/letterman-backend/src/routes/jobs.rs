@@ -0,0 +1,28 @@
+use actix_web::web::{Data, Path, Query};
+use actix_web::HttpResponse;
+
+use crate::operations::jobs::{JobPageQueryer, JobQueryer};
+use crate::traits::{DbAction, Validate};
+use crate::types::jobs::{JobPageReq, SyncJobVO};
+use crate::types::CommonResult;
+use crate::State;
+
+use super::posts::PostResponseError;
+
+pub(crate) async fn get_job(
+    state: Data<State>,
+    id: Path<i64>,
+) -> Result<HttpResponse, PostResponseError> {
+    let id = id.into_inner();
+    let job = JobQueryer(id).execute(state.pool.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(SyncJobVO::from(job))))
+}
+
+pub(crate) async fn get_job_list(
+    state: Data<State>,
+    req: Query<JobPageReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let page = JobPageQueryer(req).execute(state.pool.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(page)))
+}
@@ -7,6 +7,7 @@ use diesel::r2d2::ConnectionManager;
 use diesel::MysqlConnection;
 use r2d2::Pool;
 
+use crate::operations::jobs::JobCreator;
 use crate::operations::posts::{
     BatchPostQueryerByPostIdAndVersion, LatestPostQueryerByPostIds, PagePostSyncRecordQueryer,
     PostCreator, PostDeleter, PostLatestSyncRecordQueryer, PostPageQueryer, PostQueryer,
@@ -14,14 +15,19 @@ use crate::operations::posts::{
 };
 use crate::operations::remote;
 use crate::operations::remote::factory::SyncerFactory;
-use crate::operations::remote::types::SyncError;
+use crate::operations::remote::types::{ConflictReport, SyncError};
+use crate::operations::webhooks::DivergenceQueryerByPostId;
 use crate::traits::{DbAction, DbActionError, MongoAction, MongoActionError, Validate};
+use crate::types::forge_record::ForgeRecordVO;
 use crate::types::github_record::GithubRecordVO;
+use crate::types::jobs::{CreateJobError, InsertableSyncJob, JobCreatedVO, JobKind, QueryJobError};
+use crate::types::local_git_record::LocalGitRecordVO;
 use crate::types::posts::{
-    CreatePostError, DeletePostError, Post, PostPageReq, QueryPostError, QuerySyncRecordError,
-    RevertPostError, RevertPostReq, SyncPageReq, SyncRecord, SyncRecordVO, SyncReq,
-    UpdatePostError, UpdatePostReq,
+    BulkSyncReq, CreatePostError, DeletePostError, Post, PostPageReq, PublishReq, QueryPostError,
+    QuerySyncRecordError, RevertPostError, RevertPostReq, SyncModeReq, SyncPageReq, SyncRecord,
+    SyncRecordVO, SyncReq, UpdatePostError, UpdatePostReq,
 };
+use crate::types::webhooks::DivergenceError;
 use crate::types::{Page, PageValidationError};
 use crate::{
     types::{posts::CreatePostReq, CommonResult},
@@ -49,6 +55,12 @@ pub enum PostResponseError {
     Canceled,
     #[error("Post not found")]
     NotFound,
+    #[error("Merge Conflict")]
+    Conflict(ConflictReport),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    RateLimited(String),
     #[error("Server Error: {0}")]
     Other(String),
 }
@@ -108,10 +120,26 @@ pub(crate) async fn delete_post(
 pub(crate) async fn synchronize(
     state: Data<State>,
     post_id: Path<i64>,
+    mode: Query<SyncModeReq>,
     req: Json<SyncReq>,
 ) -> Result<HttpResponse, PostResponseError> {
     let post_id = post_id.into_inner();
-    let syncer = SyncerFactory::create(req.into_inner())?;
+    let mode = mode.into_inner().validate()?;
+    let req = req.into_inner();
+    if mode.background {
+        return enqueue(&state, post_id, JobKind::Synchronize, &req).await;
+    }
+    let syncer = SyncerFactory::create(req, state.pool.clone()).await?;
+    if mode.preview {
+        let preview = remote::preview_synchronize(
+            syncer,
+            post_id,
+            state.pool.clone(),
+            state.mongodb_database.clone(),
+        )
+        .await?;
+        return Ok(HttpResponse::Ok().json(CommonResult::success_with_data(preview)));
+    }
     remote::synchronize(
         syncer,
         post_id,
@@ -122,13 +150,120 @@ pub(crate) async fn synchronize(
     Ok(HttpResponse::Ok().json(CommonResult::<()>::success()))
 }
 
+pub(crate) async fn publish(
+    state: Data<State>,
+    post_id: Path<i64>,
+    req: Json<PublishReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let post_id = post_id.into_inner();
+    let req = req.into_inner().validate()?;
+    let results = remote::publish(
+        req.targets,
+        post_id,
+        state.pool.clone(),
+        state.mongodb_database.clone(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(results)))
+}
+
+/// move the article to the path of the request, the old file is removed
+pub(crate) async fn move_post(
+    state: Data<State>,
+    post_id: Path<i64>,
+    mode: Query<SyncModeReq>,
+    req: Json<SyncReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let post_id = post_id.into_inner();
+    let mode = mode.into_inner().validate()?;
+    let req = req.into_inner();
+    if mode.background {
+        return enqueue(&state, post_id, JobKind::Move, &req).await;
+    }
+    let syncer = SyncerFactory::create(req, state.pool.clone()).await?;
+    remote::move_post(
+        syncer,
+        post_id,
+        state.pool.clone(),
+        state.mongodb_database.clone(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(CommonResult::<()>::success()))
+}
+
+/// delete the article from the platform, the post itself is kept
+pub(crate) async fn unpublish(
+    state: Data<State>,
+    post_id: Path<i64>,
+    mode: Query<SyncModeReq>,
+    req: Json<SyncReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let post_id = post_id.into_inner();
+    let mode = mode.into_inner().validate()?;
+    let req = req.into_inner();
+    if mode.background {
+        return enqueue(&state, post_id, JobKind::Unpublish, &req).await;
+    }
+    let syncer = SyncerFactory::create(req, state.pool.clone()).await?;
+    remote::unpublish(
+        syncer,
+        post_id,
+        state.pool.clone(),
+        state.mongodb_database.clone(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(CommonResult::<()>::success()))
+}
+
+/// refresh the state of the pull request which publishes the post, e.g. after it's merged
+pub(crate) async fn refresh_pull_request(
+    state: Data<State>,
+    post_id: Path<i64>,
+    req: Json<SyncReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let pull_request = remote::refresh_pull_request(
+        req.into_inner(),
+        post_id.into_inner(),
+        state.pool.clone(),
+        state.mongodb_database.clone(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(pull_request)))
+}
+
+pub(crate) async fn bulk_synchronize(
+    state: Data<State>,
+    req: Json<BulkSyncReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let report =
+        remote::bulk_synchronize(req, state.pool.clone(), state.mongodb_database.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(report)))
+}
+
 pub(crate) async fn force_pull(
     state: Data<State>,
     post_id: Path<i64>,
+    mode: Query<SyncModeReq>,
     req: Json<SyncReq>,
 ) -> Result<HttpResponse, PostResponseError> {
     let post_id = post_id.into_inner();
-    let syncer = SyncerFactory::create(req.into_inner())?;
+    let mode = mode.into_inner().validate()?;
+    let req = req.into_inner();
+    if mode.background {
+        return enqueue(&state, post_id, JobKind::Pull, &req).await;
+    }
+    let syncer = SyncerFactory::create(req, state.pool.clone()).await?;
+    if mode.preview {
+        let preview = remote::preview_force_pull(
+            syncer,
+            post_id,
+            state.pool.clone(),
+            state.mongodb_database.clone(),
+        )
+        .await?;
+        return Ok(HttpResponse::Ok().json(CommonResult::success_with_data(preview)));
+    }
     remote::force_pull(
         syncer,
         post_id,
@@ -142,10 +277,26 @@ pub(crate) async fn force_pull(
 pub(crate) async fn force_push(
     state: Data<State>,
     post_id: Path<i64>,
+    mode: Query<SyncModeReq>,
     req: Json<SyncReq>,
 ) -> Result<HttpResponse, PostResponseError> {
     let post_id = post_id.into_inner();
-    let syncer = SyncerFactory::create(req.into_inner())?;
+    let mode = mode.into_inner().validate()?;
+    let req = req.into_inner();
+    if mode.background {
+        return enqueue(&state, post_id, JobKind::Push, &req).await;
+    }
+    let syncer = SyncerFactory::create(req, state.pool.clone()).await?;
+    if mode.preview {
+        let preview = remote::preview_force_push(
+            syncer,
+            post_id,
+            state.pool.clone(),
+            state.mongodb_database.clone(),
+        )
+        .await?;
+        return Ok(HttpResponse::Ok().json(CommonResult::success_with_data(preview)));
+    }
     remote::force_push(
         syncer,
         post_id,
@@ -200,6 +351,18 @@ pub(crate) async fn get_latest_sync_records(
     Ok(HttpResponse::Ok().json(CommonResult::success_with_data(list)))
 }
 
+/// remote changes which can't be imported because the post is changed too
+pub(crate) async fn get_divergences(
+    state: Data<State>,
+    post_id: Path<i64>,
+) -> Result<HttpResponse, PostResponseError> {
+    let post_id = post_id.into_inner();
+    let list = DivergenceQueryerByPostId(post_id)
+        .execute(state.pool.clone())
+        .await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(list)))
+}
+
 pub(crate) async fn revert_post(
     state: Data<State>,
     req: Json<RevertPostReq>,
@@ -209,25 +372,31 @@ pub(crate) async fn revert_post(
     Ok(HttpResponse::Ok().json(CommonResult::<()>::success()))
 }
 
+/// check and save the sync operation as a job, which is executed by the workers later
+async fn enqueue(
+    state: &State,
+    post_id: i64,
+    kind: JobKind,
+    req: &SyncReq,
+) -> Result<HttpResponse, PostResponseError> {
+    SyncerFactory::validate(req, state.pool.clone()).await?;
+    let job = InsertableSyncJob::new(post_id, kind, req).map_err(CreateJobError::from)?;
+    let job_id = JobCreator(job).execute(state.pool.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(JobCreatedVO { job_id })))
+}
+
 async fn convert_sync_records(
     data: Vec<SyncRecord>,
     pool: Pool<ConnectionManager<MysqlConnection>>,
 ) -> Result<Vec<SyncRecordVO>, PostResponseError> {
     let ids: Vec<(i64, String)> = data
         .iter()
-        .map(|r| match r {
-            SyncRecord::Github(r) => (r.post_id(), r.version().to_string()),
-        })
+        .map(|r| (r.post_id(), r.version().to_string()))
         .collect();
     let map = BatchPostQueryerByPostIdAndVersion(ids)
         .execute(pool.clone())
         .await?;
-    let post_ids: Vec<i64> = data
-        .iter()
-        .map(|r| match r {
-            SyncRecord::Github(r) => r.post_id(),
-        })
-        .collect();
+    let post_ids: Vec<i64> = data.iter().map(|r| r.post_id()).collect();
     let latest_posts: HashMap<i64, Post> = LatestPostQueryerByPostIds(post_ids)
         .execute(pool.clone())
         .await?
@@ -236,18 +405,26 @@ async fn convert_sync_records(
         .collect();
     let list: Vec<_> = data
         .into_iter()
-        .map(|p| match p {
-            SyncRecord::Github(p) => {
-                let post = map
-                    .get(&(p.post_id(), p.version().to_string()))
-                    .cloned()
-                    .unwrap_or_default();
-                let latest = latest_posts.get(&p.post_id()).cloned().unwrap_or_default();
-                SyncRecordVO::Github(GithubRecordVO::package(
-                    p,
-                    post.clone(),
-                    latest.version().to_string(),
-                ))
+        .map(|p| {
+            let post = map
+                .get(&(p.post_id(), p.version().to_string()))
+                .cloned()
+                .unwrap_or_default();
+            let latest = latest_posts.get(&p.post_id()).cloned().unwrap_or_default();
+            let latest_version = latest.version().to_string();
+            match p {
+                SyncRecord::Github(p) => {
+                    SyncRecordVO::Github(GithubRecordVO::package(p, post, latest_version))
+                }
+                SyncRecord::Gitea(p) => {
+                    SyncRecordVO::Forge(ForgeRecordVO::from_gitea(p, post, latest_version))
+                }
+                SyncRecord::Gitlab(p) => {
+                    SyncRecordVO::Forge(ForgeRecordVO::from_gitlab(p, post, latest_version))
+                }
+                SyncRecord::LocalGit(p) => {
+                    SyncRecordVO::LocalGit(LocalGitRecordVO::package(p, post, latest_version))
+                }
             }
         })
         .collect();
@@ -256,8 +433,16 @@ async fn convert_sync_records(
 
 impl ResponseError for PostResponseError {
     fn error_response(&self) -> actix_web::HttpResponse {
-        let body =
-            serde_json::to_string(&CommonResult::<()>::fail_with_msg(&self.to_string())).unwrap();
+        let body = match self {
+            Self::Conflict(report) => serde_json::to_string(&CommonResult::with_data(
+                false,
+                1,
+                &self.to_string(),
+                report,
+            )),
+            _ => serde_json::to_string(&CommonResult::<()>::fail_with_msg(&self.to_string())),
+        }
+        .unwrap();
         actix_web::HttpResponse::build(self.status_code())
             .insert_header(actix_web::http::header::ContentType::json())
             .body(body)
@@ -268,11 +453,54 @@ impl ResponseError for PostResponseError {
             Self::ValidationError { .. } => StatusCode::BAD_REQUEST,
             Self::UserError { .. } => StatusCode::OK,
             Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 
+impl From<CreateJobError> for PostResponseError {
+    fn from(item: CreateJobError) -> Self {
+        match item {
+            CreateJobError::Database => PostResponseError::Database,
+            CreateJobError::Serialize(msg) => PostResponseError::UserError { msg },
+        }
+    }
+}
+
+impl From<DbActionError<CreateJobError>> for PostResponseError {
+    fn from(item: DbActionError<CreateJobError>) -> Self {
+        match item {
+            DbActionError::Error(e) => e.into(),
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<QueryJobError>> for PostResponseError {
+    fn from(item: DbActionError<QueryJobError>) -> Self {
+        match item {
+            DbActionError::Error(QueryJobError::Database) => PostResponseError::Database,
+            DbActionError::Error(QueryJobError::NotFound) => PostResponseError::NotFound,
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<DivergenceError>> for PostResponseError {
+    fn from(item: DbActionError<DivergenceError>) -> Self {
+        match item {
+            DbActionError::Error(DivergenceError::Database) => PostResponseError::Database,
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
 impl From<PageValidationError> for PostResponseError {
     fn from(item: PageValidationError) -> Self {
         PostResponseError::ValidationError {
@@ -385,10 +613,19 @@ impl From<SyncError> for PostResponseError {
             SyncError::Ambiguous => PostResponseError::UserError {
                 msg: item.to_string(),
             },
+            SyncError::Conflict(report) => PostResponseError::Conflict(report),
             SyncError::RemoteServer => PostResponseError::Other(item.to_string()),
+            SyncError::RateLimited { .. } => PostResponseError::RateLimited(item.to_string()),
+            SyncError::RemoteNotFound(_)
+            | SyncError::RemoteConflict(_)
+            | SyncError::RemoteValidation(_) => PostResponseError::UserError {
+                msg: item.to_string(),
+            },
             SyncError::UserError(e) => PostResponseError::UserError { msg: e },
             SyncError::NetworkError(e) => PostResponseError::Other(e),
-            SyncError::Decode => PostResponseError::Other(item.to_string()),
+            SyncError::Decode(_) => PostResponseError::UserError {
+                msg: item.to_string(),
+            },
             SyncError::Other(e) => PostResponseError::Other(e),
         }
     }
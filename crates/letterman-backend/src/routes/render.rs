@@ -0,0 +1,35 @@
+use actix_web::web::{Data, Json, Query};
+use actix_web::HttpResponse;
+
+use crate::operations::render::{self, DEFAULT_HIGHLIGHT_THEME};
+use crate::traits::Validate;
+use crate::types::render::{HighlightCssReq, RenderReq};
+use crate::types::CommonResult;
+use crate::State;
+
+use super::posts::PostResponseError;
+
+/// render a post version or the markdown submitted to html, as the target receives it
+pub(crate) async fn render_markdown(
+    state: Data<State>,
+    req: Json<RenderReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let rendered = render::render(req, state.pool.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(rendered)))
+}
+
+/// the stylesheet of the highlighted code in the rendered html
+pub(crate) async fn get_highlight_css(
+    req: Query<HighlightCssReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let theme = req.theme.as_deref().unwrap_or(DEFAULT_HIGHLIGHT_THEME);
+    match render::highlight_css(theme) {
+        Some(css) => Ok(HttpResponse::Ok()
+            .content_type("text/css; charset=utf-8")
+            .body(css)),
+        None => Err(PostResponseError::UserError {
+            msg: format!("unknown theme {}", theme),
+        }),
+    }
+}
@@ -0,0 +1,131 @@
+use actix_web::web::{Data, Json, Path, Query};
+use actix_web::HttpResponse;
+
+use crate::operations::schedules::{
+    ScheduleCreator, ScheduleDeleter, SchedulePageQueryer, ScheduleQueryer, ScheduleUpdater,
+};
+use crate::traits::{DbAction, DbActionError, Validate};
+use crate::types::schedules::{
+    CreateScheduleError, CreateScheduleReq, DeleteScheduleError, PostScheduleVO,
+    QueryScheduleError, SchedulePageReq, UpdateScheduleError, UpdateScheduleReq,
+};
+use crate::types::CommonResult;
+use crate::State;
+
+use super::posts::PostResponseError;
+
+pub(crate) async fn create_schedule(
+    state: Data<State>,
+    req: Json<CreateScheduleReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let schedule = ScheduleCreator(req).execute(state.pool.clone()).await?;
+    Ok(
+        HttpResponse::Ok().json(CommonResult::success_with_data(PostScheduleVO::from(
+            schedule,
+        ))),
+    )
+}
+
+pub(crate) async fn get_schedule(
+    state: Data<State>,
+    id: Path<i64>,
+) -> Result<HttpResponse, PostResponseError> {
+    let id = id.into_inner();
+    let schedule = ScheduleQueryer(id).execute(state.pool.clone()).await?;
+    Ok(
+        HttpResponse::Ok().json(CommonResult::success_with_data(PostScheduleVO::from(
+            schedule,
+        ))),
+    )
+}
+
+pub(crate) async fn get_schedule_list(
+    state: Data<State>,
+    req: Query<SchedulePageReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let page = SchedulePageQueryer(req).execute(state.pool.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::success_with_data(page)))
+}
+
+pub(crate) async fn update_schedule(
+    state: Data<State>,
+    req: Json<UpdateScheduleReq>,
+) -> Result<HttpResponse, PostResponseError> {
+    let req = req.into_inner().validate()?;
+    let schedule = ScheduleUpdater(req).execute(state.pool.clone()).await?;
+    Ok(
+        HttpResponse::Ok().json(CommonResult::success_with_data(PostScheduleVO::from(
+            schedule,
+        ))),
+    )
+}
+
+pub(crate) async fn delete_schedule(
+    state: Data<State>,
+    id: Path<i64>,
+) -> Result<HttpResponse, PostResponseError> {
+    let id = id.into_inner();
+    ScheduleDeleter(id).execute(state.pool.clone()).await?;
+    Ok(HttpResponse::Ok().json(CommonResult::<()>::success()))
+}
+
+impl From<DbActionError<CreateScheduleError>> for PostResponseError {
+    fn from(item: DbActionError<CreateScheduleError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                CreateScheduleError::Database => PostResponseError::Database,
+                CreateScheduleError::PostNotFound => PostResponseError::NotFound,
+                CreateScheduleError::Serialize(msg) => PostResponseError::UserError { msg },
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<QueryScheduleError>> for PostResponseError {
+    fn from(item: DbActionError<QueryScheduleError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                QueryScheduleError::Database => PostResponseError::Database,
+                QueryScheduleError::NotFound => PostResponseError::NotFound,
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<UpdateScheduleError>> for PostResponseError {
+    fn from(item: DbActionError<UpdateScheduleError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                UpdateScheduleError::Database => PostResponseError::Database,
+                UpdateScheduleError::NotFound | UpdateScheduleError::PostNotFound => {
+                    PostResponseError::NotFound
+                }
+                UpdateScheduleError::Locked | UpdateScheduleError::Serialize(_) => {
+                    PostResponseError::UserError { msg: e.to_string() }
+                }
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
+
+impl From<DbActionError<DeleteScheduleError>> for PostResponseError {
+    fn from(item: DbActionError<DeleteScheduleError>) -> Self {
+        match item {
+            DbActionError::Error(e) => match e {
+                DeleteScheduleError::Database => PostResponseError::Database,
+                DeleteScheduleError::NotFound => PostResponseError::NotFound,
+                DeleteScheduleError::Running => PostResponseError::UserError { msg: e.to_string() },
+            },
+            DbActionError::Pool(e) => PostResponseError::Pool(e),
+            DbActionError::Canceled => PostResponseError::Canceled,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use actix_web::web::{Bytes, Data};
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::operations::webhooks::handle_github_push;
+use crate::types::webhooks::GithubPushEvent;
+use crate::types::CommonResult;
+use crate::utils::signature_utils::verify_hmac_sha256;
+use crate::State;
+
+use super::posts::PostResponseError;
+
+/// receive the events of github, the body is signed with `GITHUB_WEBHOOK_SECRET` env.
+/// Changed articles are imported in background, because github waits only 10 seconds.
+pub(crate) async fn github_webhook(
+    state: Data<State>,
+    req: HttpRequest,
+    body: Bytes,
+) -> Result<HttpResponse, PostResponseError> {
+    let secret = std::env::var("GITHUB_WEBHOOK_SECRET")
+        .map_err(|_| PostResponseError::Other("GITHUB_WEBHOOK_SECRET is not set".to_string()))?;
+    let signature = req
+        .headers()
+        .get("X-Hub-Signature-256")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !verify_hmac_sha256(&secret, &body, signature) {
+        return Err(PostResponseError::Unauthorized(
+            "invalid signature".to_string(),
+        ));
+    }
+    let event = req
+        .headers()
+        .get("X-GitHub-Event")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if event == "push" {
+        let event: GithubPushEvent =
+            serde_json::from_slice(&body).map_err(|e| PostResponseError::UserError {
+                msg: format!("invalid push event: {}", e),
+            })?;
+        actix_rt::spawn(handle_github_push(
+            event,
+            state.pool.clone(),
+            state.mongodb_database.clone(),
+        ));
+    }
+    Ok(HttpResponse::Ok().json(CommonResult::<()>::success()))
+}
@@ -1,5 +1,20 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    t_credential (id) {
+        id -> Bigint,
+        #[max_length = 128]
+        name -> Varchar,
+        #[max_length = 128]
+        owner -> Varchar,
+        #[max_length = 32]
+        platform -> Varchar,
+        secret -> Text,
+        create_time -> Timestamp,
+        update_time -> Timestamp,
+    }
+}
+
 diesel::table! {
     t_github_post_record (id) {
         id -> Integer,
@@ -24,8 +39,7 @@ diesel::table! {
         post_id -> Bigint,
         #[max_length = 255]
         title -> Varchar,
-        #[max_length = 255]
-        metadata -> Varchar,
+        metadata -> Text,
         #[max_length = 256]
         version -> Varchar,
         #[max_length = 256]
@@ -51,8 +65,67 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    t_post_divergence (id) {
+        id -> Bigint,
+        post_id -> Bigint,
+        #[max_length = 32]
+        platform -> Varchar,
+        #[max_length = 255]
+        repository -> Varchar,
+        #[max_length = 255]
+        path -> Varchar,
+        #[max_length = 256]
+        local_version -> Varchar,
+        #[max_length = 64]
+        remote_commit -> Varchar,
+        create_time -> Timestamp,
+        update_time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    t_post_schedule (id) {
+        id -> Bigint,
+        post_id -> Bigint,
+        #[max_length = 256]
+        version -> Varchar,
+        targets -> Text,
+        publish_at -> Timestamp,
+        #[max_length = 32]
+        status -> Varchar,
+        result -> Nullable<Text>,
+        create_time -> Timestamp,
+        update_time -> Timestamp,
+        lease_expire_time -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    t_sync_job (id) {
+        id -> Bigint,
+        post_id -> Bigint,
+        #[max_length = 32]
+        kind -> Varchar,
+        request -> Text,
+        #[max_length = 32]
+        status -> Varchar,
+        attempts -> Integer,
+        max_attempts -> Integer,
+        next_run_time -> Timestamp,
+        last_error -> Nullable<Text>,
+        create_time -> Timestamp,
+        update_time -> Timestamp,
+        lease_expire_time -> Nullable<Timestamp>,
+    }
+}
+
 diesel::allow_tables_to_appear_in_same_query!(
+    t_credential,
     t_github_post_record,
     t_post,
     t_post_content,
+    t_post_divergence,
+    t_post_schedule,
+    t_sync_job,
 );
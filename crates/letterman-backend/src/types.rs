@@ -1,17 +1,26 @@
 use core::fmt;
-use std::{
-    collections::HashMap,
-    fmt::{Display, Formatter},
-};
+use std::fmt::{Display, Formatter};
 
-use mongodb::bson::Bson;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use mongodb::bson::{Bson, DateTime};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 use crate::traits::Validate;
 
+pub mod commits;
+pub mod credentials;
+pub mod forge_record;
+pub mod git_data;
 pub mod github_record;
+pub mod jobs;
+pub mod local_git_record;
 pub mod posts;
+pub mod pull_requests;
+pub mod render;
+pub mod schedules;
+pub mod transforms;
+pub mod webhooks;
 
 #[derive(Serialize)]
 pub struct CommonResult<T> {
@@ -116,7 +125,7 @@ impl<T> Page<T> {
     }
 }
 
-fn serialize_as_string<S>(x: &i64, s: S) -> Result<S::Ok, S::Error>
+pub(crate) fn serialize_as_string<S>(x: &i64, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
@@ -130,7 +139,26 @@ where
     s.parse::<i64>().map_err(serde::de::Error::custom)
 }
 
-fn serialize_metadata<S>(x: &HashMap<String, String>, s: S) -> Result<S::Ok, S::Error>
+fn serialize_option_as_string<S>(x: &Option<i64>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match x {
+        Some(x) => s.serialize_some(&x.to_string()),
+        None => s.serialize_none(),
+    }
+}
+
+fn deserialize_option_from_string<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| s.parse::<i64>().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn serialize_metadata<S>(x: &posts::Metadata, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
@@ -138,15 +166,59 @@ where
     value.serialize(s)
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+fn naive_date_time_from_bson_datetime<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let dt = DateTime::deserialize(deserializer)?;
+    Ok(dt.to_chrono().naive_utc())
+}
+
+fn serialize_naive_date_time<S>(time: &NaiveDateTime, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let time = Utc.from_utc_datetime(time);
+    let local = time.with_timezone(&Local);
+    s.serialize_str(local.format("%Y-%m-%d %H:%M:%S").to_string().as_str())
+}
+
+mod object_id_as_string {
+
+    use bson::oid::ObjectId;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(id: &str, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(id)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let oid = ObjectId::deserialize(deserializer)?;
+        Ok(oid.to_string())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub enum Platform {
     Github,
+    LocalGit,
+    Gitea,
+    Gitlab,
 }
 
 impl Display for Platform {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             Platform::Github => write!(f, "Github"),
+            Platform::LocalGit => write!(f, "LocalGit"),
+            Platform::Gitea => write!(f, "Gitea"),
+            Platform::Gitlab => write!(f, "Gitlab"),
         }
     }
 }
@@ -155,6 +227,9 @@ impl From<Platform> for Bson {
     fn from(item: Platform) -> Self {
         match item {
             Platform::Github => Bson::String("Github".to_string()),
+            Platform::LocalGit => Bson::String("LocalGit".to_string()),
+            Platform::Gitea => Bson::String("Gitea".to_string()),
+            Platform::Gitlab => Bson::String("Gitlab".to_string()),
         }
     }
 }
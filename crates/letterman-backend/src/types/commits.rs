@@ -0,0 +1,87 @@
+//! how articles are committed to github repositories.
+
+use serde::{Deserialize, Serialize};
+
+/// name and email of the committer or the author of a commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitIdentity {
+    pub name: String,
+    pub email: String,
+}
+
+impl GitIdentity {
+    /// read the identity from `{prefix}_NAME` and `{prefix}_EMAIL` envs, both are required
+    fn from_env(prefix: &str) -> Option<GitIdentity> {
+        Some(GitIdentity {
+            name: std::env::var(format!("{prefix}_NAME")).ok()?,
+            email: std::env::var(format!("{prefix}_EMAIL")).ok()?,
+        })
+    }
+}
+
+/// settings of the commits, github uses the owner of the token as both committer and author,
+/// the default branch and messages like `update {path}` if they're not given
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitOptions {
+    /// template of the commit message, see `Change::render` for the placeholders
+    pub message: Option<String>,
+    pub branch: Option<String>,
+    pub committer: Option<GitIdentity>,
+    pub author: Option<GitIdentity>,
+}
+
+impl CommitOptions {
+    /// the global settings: `GITHUB_COMMIT_MESSAGE`, `GITHUB_BRANCH`,
+    /// `GITHUB_COMMITTER_NAME`/`GITHUB_COMMITTER_EMAIL` and `GITHUB_AUTHOR_NAME`/`GITHUB_AUTHOR_EMAIL` envs
+    pub fn from_env() -> CommitOptions {
+        CommitOptions {
+            message: std::env::var("GITHUB_COMMIT_MESSAGE").ok(),
+            branch: std::env::var("GITHUB_BRANCH").ok(),
+            committer: GitIdentity::from_env("GITHUB_COMMITTER"),
+            author: GitIdentity::from_env("GITHUB_AUTHOR"),
+        }
+    }
+
+    /// settings of the request take precedence over the fallback
+    pub fn or(self, fallback: CommitOptions) -> CommitOptions {
+        CommitOptions {
+            message: self.message.or(fallback.message),
+            branch: self.branch.or(fallback.branch),
+            committer: self.committer.or(fallback.committer),
+            author: self.author.or(fallback.author),
+        }
+    }
+}
+
+#[cfg(test)]
+mod commits_test {
+    use super::*;
+
+    #[test]
+    fn or_test() {
+        let identity = |name: &str| GitIdentity {
+            name: name.to_string(),
+            email: format!("{name}@example.com"),
+        };
+        let req = CommitOptions {
+            message: Some("{action} {title}".to_string()),
+            committer: Some(identity("bot")),
+            ..Default::default()
+        };
+        let global = CommitOptions {
+            message: Some("{action} {path}".to_string()),
+            branch: Some("blog".to_string()),
+            committer: Some(identity("ci")),
+            author: Some(identity("alice")),
+        };
+        assert_eq!(
+            req.or(global),
+            CommitOptions {
+                message: Some("{action} {title}".to_string()),
+                branch: Some("blog".to_string()),
+                committer: Some(identity("bot")),
+                author: Some(identity("alice")),
+            }
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! tokens of the platforms, which are encrypted with `LETTERMAN_SECRET_KEY` env.
+
+use chrono::NaiveDateTime;
+use diesel::{deserialize::Queryable, prelude::Insertable, Selectable};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    traits::Validate,
+    utils::{self, cipher_utils},
+};
+
+use super::{
+    deserialize_from_string, posts::ValidateManipulatePostError, serialize_as_string,
+    serialize_naive_date_time, PageValidationError, Platform,
+};
+
+/// a token of the platform, which belongs to `owner`
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = crate::schema::t_credential)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct Credential {
+    pub id: i64,
+    pub name: String,
+    pub owner: String,
+    pub platform: String,
+    /// the encrypted token, see `cipher_utils::encrypt`
+    pub secret: String,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl Credential {
+    /// decrypt the token
+    pub fn token(&self) -> Result<String, SecretError> {
+        let key = cipher_utils::secret_key().ok_or(SecretError::NoKey)?;
+        cipher_utils::decrypt(&key, &self.secret).ok_or(SecretError::Decrypt)
+    }
+}
+
+/// encrypt the token with the key of `LETTERMAN_SECRET_KEY` env
+pub fn encrypt_token(token: &str) -> Result<String, SecretError> {
+    let key = cipher_utils::secret_key().ok_or(SecretError::NoKey)?;
+    cipher_utils::encrypt(&key, token).ok_or(SecretError::Encrypt)
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::t_credential)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct InsertableCredential {
+    pub id: i64,
+    pub name: String,
+    pub owner: String,
+    pub platform: String,
+    pub secret: String,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl InsertableCredential {
+    pub fn new(req: CreateCredentialReq) -> Result<InsertableCredential, SecretError> {
+        let now = utils::time_utils::now();
+        Ok(InsertableCredential {
+            id: utils::snowflake::next_id(),
+            secret: encrypt_token(&req.token)?,
+            name: req.name,
+            owner: req.owner,
+            platform: req.platform.to_string(),
+            create_time: now,
+            update_time: now,
+        })
+    }
+}
+
+/// the token is never returned
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialVO {
+    #[serde(serialize_with = "serialize_as_string")]
+    id: i64,
+    name: String,
+    owner: String,
+    platform: String,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    create_time: NaiveDateTime,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    update_time: NaiveDateTime,
+}
+
+impl From<Credential> for CredentialVO {
+    fn from(credential: Credential) -> Self {
+        CredentialVO {
+            id: credential.id,
+            name: credential.name,
+            owner: credential.owner,
+            platform: credential.platform,
+            create_time: credential.create_time,
+            update_time: credential.update_time,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCredentialReq {
+    pub name: String,
+    /// who the token belongs to, the name is unique for the owner
+    pub owner: String,
+    pub platform: Platform,
+    pub token: String,
+}
+
+impl Validate for CreateCredentialReq {
+    type Item = CreateCredentialReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        validate_text("name", &self.name)?;
+        validate_text("owner", &self.owner)?;
+        validate_text("token", &self.token)?;
+        if self.platform == Platform::LocalGit {
+            return Err(ValidateManipulatePostError {
+                field: "platform",
+                msg: "local git repositories don't need credentials",
+            });
+        }
+        Ok(self)
+    }
+}
+
+/// fields which are absent are not changed, the platform can't be changed
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCredentialReq {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub id: i64,
+    pub name: Option<String>,
+    pub owner: Option<String>,
+    pub token: Option<String>,
+}
+
+impl Validate for UpdateCredentialReq {
+    type Item = UpdateCredentialReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if let Some(name) = &self.name {
+            validate_text("name", name)?;
+        }
+        if let Some(owner) = &self.owner {
+            validate_text("owner", owner)?;
+        }
+        if let Some(token) = &self.token {
+            validate_text("token", token)?;
+        }
+        Ok(self)
+    }
+}
+
+fn validate_text(field: &'static str, text: &str) -> Result<(), ValidateManipulatePostError> {
+    if text.trim().is_empty() {
+        return Err(ValidateManipulatePostError {
+            field,
+            msg: "it can't be empty",
+        });
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialPageReq {
+    pub page: i32,
+    pub page_size: i32,
+    pub owner: Option<String>,
+    pub platform: Option<Platform>,
+}
+
+impl Validate for CredentialPageReq {
+    type Item = CredentialPageReq;
+
+    type Error = PageValidationError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if self.page <= 0 {
+            return Err(PageValidationError {
+                field: "page",
+                msg: "page must be greater than 0",
+            });
+        };
+        if self.page_size <= 0 {
+            return Err(PageValidationError {
+                field: "page_size",
+                msg: "page_size must be greater than 0",
+            });
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("Please set LETTERMAN_SECRET_KEY env (32 bytes in hex) to store credentials")]
+    NoKey,
+    #[error("Failed to encrypt the token")]
+    Encrypt,
+    #[error("Failed to decrypt the token, LETTERMAN_SECRET_KEY may be changed")]
+    Decrypt,
+}
+
+#[derive(Debug, Error)]
+pub enum QueryCredentialError {
+    #[error("Database Error")]
+    Database,
+    #[error("Credential not found")]
+    NotFound,
+    #[error("Credential is not for {0}")]
+    PlatformMismatch(Platform),
+    #[error("{0}")]
+    Secret(SecretError),
+}
+
+#[derive(Debug, Error)]
+pub enum CreateCredentialError {
+    #[error("Database Error")]
+    Database,
+    #[error("Credential name is used by the owner")]
+    Duplicate,
+    #[error("{0}")]
+    Secret(SecretError),
+}
+
+#[derive(Debug, Error)]
+pub enum UpdateCredentialError {
+    #[error("Database Error")]
+    Database,
+    #[error("Credential not found")]
+    NotFound,
+    #[error("Credential name is used by the owner")]
+    Duplicate,
+    #[error("{0}")]
+    Secret(SecretError),
+}
+
+#[derive(Debug, Error)]
+pub enum DeleteCredentialError {
+    #[error("Database Error")]
+    Database,
+    #[error("Credential not found")]
+    NotFound,
+}
+
+fn is_duplicate(error: &diesel::result::Error) -> bool {
+    matches!(
+        error,
+        diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)
+    )
+}
+
+impl From<diesel::result::Error> for QueryCredentialError {
+    fn from(item: diesel::result::Error) -> Self {
+        match item {
+            diesel::result::Error::NotFound => QueryCredentialError::NotFound,
+            _ => QueryCredentialError::Database,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for CreateCredentialError {
+    fn from(item: diesel::result::Error) -> Self {
+        if is_duplicate(&item) {
+            return CreateCredentialError::Duplicate;
+        }
+        CreateCredentialError::Database
+    }
+}
+
+impl From<diesel::result::Error> for UpdateCredentialError {
+    fn from(item: diesel::result::Error) -> Self {
+        match item {
+            diesel::result::Error::NotFound => UpdateCredentialError::NotFound,
+            item if is_duplicate(&item) => UpdateCredentialError::Duplicate,
+            _ => UpdateCredentialError::Database,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for DeleteCredentialError {
+    fn from(item: diesel::result::Error) -> Self {
+        match item {
+            diesel::result::Error::NotFound => DeleteCredentialError::NotFound,
+            _ => DeleteCredentialError::Database,
+        }
+    }
+}
+
+impl From<SecretError> for QueryCredentialError {
+    fn from(item: SecretError) -> Self {
+        QueryCredentialError::Secret(item)
+    }
+}
+
+impl From<SecretError> for CreateCredentialError {
+    fn from(item: SecretError) -> Self {
+        CreateCredentialError::Secret(item)
+    }
+}
+
+impl From<SecretError> for UpdateCredentialError {
+    fn from(item: SecretError) -> Self {
+        UpdateCredentialError::Secret(item)
+    }
+}
@@ -0,0 +1,449 @@
+use base64::Engine;
+use chrono::NaiveDateTime;
+use mongodb::bson::{doc, Bson, DateTime};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{traits::DocumentConvert, types::Platform, utils};
+
+use super::{
+    github_record::{DecodeError, GithubRecord},
+    naive_date_time_from_bson_datetime, object_id_as_string,
+    posts::{Post, SyncReq},
+    serialize_naive_date_time,
+};
+
+/// common view of the records of git forges, e.g. github, gitea and gitlab
+pub trait ForgeRecord {
+    fn post_id(&self) -> i64;
+    fn version(&self) -> &str;
+    fn path(&self) -> &str;
+    /// sha of the file blob in the remote repository
+    fn sha(&self) -> &str;
+    fn repository(&self) -> &str;
+    fn url(&self) -> &str;
+    /// whether the record is a tombstone of an unpublished article
+    fn deleted(&self) -> bool;
+    fn create_time(&self) -> &NaiveDateTime;
+    /// the request which synchronizes the target, `None` for the records written before
+    /// the targets are stored
+    fn target(&self) -> Option<&SyncReq>;
+}
+
+impl ForgeRecord for GithubRecord {
+    fn post_id(&self) -> i64 {
+        self.post_id()
+    }
+
+    fn version(&self) -> &str {
+        self.version()
+    }
+
+    fn path(&self) -> &str {
+        self.path()
+    }
+
+    fn sha(&self) -> &str {
+        self.sha()
+    }
+
+    fn repository(&self) -> &str {
+        self.repository()
+    }
+
+    fn url(&self) -> &str {
+        self.url()
+    }
+
+    fn deleted(&self) -> bool {
+        self.deleted()
+    }
+
+    fn create_time(&self) -> &NaiveDateTime {
+        self.create_time()
+    }
+
+    fn target(&self) -> Option<&SyncReq> {
+        self.target()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GiteaRecord {
+    #[serde(
+        rename = "_id",
+        serialize_with = "object_id_as_string::serialize",
+        deserialize_with = "object_id_as_string::deserialize"
+    )]
+    id: String,
+    post_id: i64,
+    version: String,
+    path: String,
+    sha: String,
+    repository: String,
+    url: String,
+    #[serde(default)]
+    deleted: bool,
+    /// path of the article before it's moved by this sync
+    #[serde(default)]
+    previous_path: Option<String>,
+    /// the request which synchronizes the target, bulk syncs and webhooks are rebuilt from it
+    #[serde(default)]
+    target: Option<SyncReq>,
+    #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
+    create_time: NaiveDateTime,
+    #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
+    update_time: NaiveDateTime,
+}
+
+impl ForgeRecord for GiteaRecord {
+    fn post_id(&self) -> i64 {
+        self.post_id
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn sha(&self) -> &str {
+        &self.sha
+    }
+
+    fn repository(&self) -> &str {
+        &self.repository
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn create_time(&self) -> &NaiveDateTime {
+        &self.create_time
+    }
+
+    fn target(&self) -> Option<&SyncReq> {
+        self.target.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GitlabRecord {
+    #[serde(
+        rename = "_id",
+        serialize_with = "object_id_as_string::serialize",
+        deserialize_with = "object_id_as_string::deserialize"
+    )]
+    id: String,
+    post_id: i64,
+    version: String,
+    path: String,
+    /// blob id of the file
+    sha: String,
+    /// full path of the project, e.g. `group/project`
+    repository: String,
+    url: String,
+    #[serde(default)]
+    deleted: bool,
+    /// path of the article before it's moved by this sync
+    #[serde(default)]
+    previous_path: Option<String>,
+    /// the request which synchronizes the target, bulk syncs and webhooks are rebuilt from it
+    #[serde(default)]
+    target: Option<SyncReq>,
+    #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
+    create_time: NaiveDateTime,
+    #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
+    update_time: NaiveDateTime,
+}
+
+impl ForgeRecord for GitlabRecord {
+    fn post_id(&self) -> i64 {
+        self.post_id
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn sha(&self) -> &str {
+        &self.sha
+    }
+
+    fn repository(&self) -> &str {
+        &self.repository
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    fn create_time(&self) -> &NaiveDateTime {
+        &self.create_time
+    }
+
+    fn target(&self) -> Option<&SyncReq> {
+        self.target.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgeRecordVO {
+    id: String,
+    post: Post,
+    path: String,
+    repository: String,
+    url: String,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    create_time: NaiveDateTime,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    update_time: NaiveDateTime,
+    platform: Platform,
+    version: String,
+    latest_version: String,
+    deleted: bool,
+    previous_path: Option<String>,
+}
+
+impl ForgeRecordVO {
+    pub fn from_gitea(record: GiteaRecord, post: Post, latest_version: String) -> Self {
+        Self {
+            id: record.id,
+            post,
+            path: record.path,
+            repository: record.repository,
+            url: record.url,
+            create_time: record.create_time,
+            update_time: record.update_time,
+            platform: Platform::Gitea,
+            version: record.version,
+            latest_version,
+            deleted: record.deleted,
+            previous_path: record.previous_path,
+        }
+    }
+
+    pub fn from_gitlab(record: GitlabRecord, post: Post, latest_version: String) -> Self {
+        Self {
+            id: record.id,
+            post,
+            path: record.path,
+            repository: record.repository,
+            url: record.url,
+            create_time: record.create_time,
+            update_time: record.update_time,
+            platform: Platform::Gitlab,
+            version: record.version,
+            latest_version,
+            deleted: record.deleted,
+            previous_path: record.previous_path,
+        }
+    }
+}
+
+pub struct InsertableForgeRecord {
+    pub platform: Platform,
+    pub post_id: i64,
+    pub version: String,
+    pub path: String,
+    pub sha: String,
+    pub repository: String,
+    pub url: String,
+    pub deleted: bool,
+    pub previous_path: Option<String>,
+    pub target: Option<SyncReq>,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl DocumentConvert for InsertableForgeRecord {
+    fn to_doc(self) -> mongodb::bson::Document {
+        doc! {
+            "post_id": self.post_id,
+            "version": self.version,
+            "path": self.path,
+            "sha": self.sha,
+            "repository": self.repository,
+            "url": self.url,
+            "deleted": self.deleted,
+            "previous_path": self.previous_path,
+            "target": mongodb::bson::to_bson(&self.target).unwrap_or(Bson::Null),
+            "platform": Bson::from(self.platform),
+            "create_time": DateTime::from_millis(self.create_time.and_utc().timestamp_millis()),
+            "update_time": DateTime::from_millis(self.update_time.and_utc().timestamp_millis()),
+        }
+    }
+}
+
+impl InsertableForgeRecord {
+    pub fn new(
+        platform: Platform,
+        post_id: i64,
+        version: String,
+        path: String,
+        sha: String,
+        repository: String,
+        url: String,
+    ) -> Self {
+        Self {
+            platform,
+            post_id,
+            version,
+            path,
+            sha,
+            repository,
+            url,
+            deleted: false,
+            previous_path: None,
+            target: None,
+            create_time: utils::time_utils::now(),
+            update_time: utils::time_utils::now(),
+        }
+    }
+
+    /// record the path the article is moved from
+    pub fn moved_from(mut self, previous_path: Option<&str>) -> Self {
+        self.previous_path = previous_path.map(str::to_string);
+        self
+    }
+
+    /// the request which synchronizes the target
+    pub fn for_target(mut self, target: Option<&SyncReq>) -> Self {
+        self.target = target.cloned();
+        self
+    }
+
+    /// tombstone of the record, which tells the article is unpublished
+    pub fn tombstone<R: ForgeRecord>(platform: Platform, record: &R) -> Self {
+        Self {
+            deleted: true,
+            target: record.target().cloned(),
+            ..Self::new(
+                platform,
+                record.post_id(),
+                record.version().to_string(),
+                record.path().to_string(),
+                record.sha().to_string(),
+                record.repository().to_string(),
+                record.url().to_string(),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum QueryForgeRecordError {
+    #[error("Database Error")]
+    Database(#[source] mongodb::error::Error),
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum CreateForgeRecordError {
+    #[error("Database error")]
+    Database(#[source] mongodb::error::Error),
+}
+
+/// payload of gitlab repository files api for creating and updating a file
+#[derive(Debug, Clone, Serialize)]
+pub struct GitlabWriteFileParam {
+    branch: String,
+    commit_message: String,
+    content: String,
+    encoding: &'static str,
+    /// gitlab rejects the write if the file is committed after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_commit_id: Option<String>,
+}
+
+impl GitlabWriteFileParam {
+    pub fn new(branch: &str, message: &str, content: &str) -> GitlabWriteFileParam {
+        GitlabWriteFileParam {
+            branch: branch.to_string(),
+            commit_message: message.to_string(),
+            content: base64::prelude::BASE64_STANDARD.encode(content),
+            encoding: "base64",
+            last_commit_id: None,
+        }
+    }
+
+    /// the write is based on the commit, see `last_commit_id`
+    pub fn based_on(self, last_commit_id: &str) -> GitlabWriteFileParam {
+        GitlabWriteFileParam {
+            last_commit_id: Some(last_commit_id.to_string()),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GitlabDeleteFileParam {
+    branch: String,
+    commit_message: String,
+    /// gitlab rejects the deletion if the file is committed after it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_commit_id: Option<String>,
+}
+
+impl GitlabDeleteFileParam {
+    pub fn new(branch: &str, message: &str) -> GitlabDeleteFileParam {
+        GitlabDeleteFileParam {
+            branch: branch.to_string(),
+            commit_message: message.to_string(),
+            last_commit_id: None,
+        }
+    }
+
+    /// the deletion is based on the commit, see `last_commit_id`
+    pub fn based_on(self, last_commit_id: &str) -> GitlabDeleteFileParam {
+        GitlabDeleteFileParam {
+            last_commit_id: Some(last_commit_id.to_string()),
+            ..self
+        }
+    }
+}
+
+/// schema of the file returned by gitlab repository files api
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitlabFile {
+    pub file_path: String,
+    pub encoding: String,
+    pub content: String,
+    pub blob_id: String,
+    /// the latest commit touching the file
+    #[serde(default)]
+    pub last_commit_id: String,
+}
+
+impl GitlabFile {
+    pub fn decode_content(self) -> Result<GitlabFile, DecodeError> {
+        match &*self.encoding {
+            "base64" => {
+                let content = self.content.replace('\n', "");
+                let content = base64::prelude::BASE64_STANDARD.decode(content)?;
+                Ok(GitlabFile {
+                    content: String::from_utf8(content)
+                        .map_err(|_| DecodeError::NotText(self.file_path.clone()))?,
+                    ..self
+                })
+            }
+            "text" => Ok(self),
+            _ => Err(DecodeError::UnsupportedEncoding(self.encoding.clone())),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! payloads of the github git data api (blobs, trees, commits and refs),
+//! which writes several files in one commit.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::commits::{CommitOptions, GitIdentity};
+
+/// how the github syncer writes articles into the repository
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GithubWriteMode {
+    /// one request per file with the repository contents api
+    #[default]
+    Contents,
+    /// the article and the images it references are written in a single commit
+    GitData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RepositoryResp {
+    pub default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefResp {
+    pub object: GitObject,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitObject {
+    pub sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitResp {
+    pub sha: String,
+    pub tree: GitObject,
+}
+
+/// metadata of a file from the contents api, the content is not needed
+#[derive(Debug, Deserialize)]
+pub struct ContentMetaResp {
+    pub path: String,
+    pub sha: String,
+    pub html_url: String,
+}
+
+/// a blob from the git data api, it's base64 encoded for files up to 100 MB
+#[derive(Debug, Deserialize)]
+pub struct BlobResp {
+    pub sha: String,
+    pub content: String,
+    pub encoding: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateBlobParam {
+    content: String,
+    encoding: &'static str,
+}
+
+impl CreateBlobParam {
+    pub fn new(content: &[u8]) -> CreateBlobParam {
+        CreateBlobParam {
+            content: base64::prelude::BASE64_STANDARD.encode(content),
+            encoding: "base64",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateTreeParam {
+    base_tree: String,
+    tree: Vec<TreeEntry>,
+}
+
+impl CreateTreeParam {
+    pub fn new(base_tree: &str, tree: Vec<TreeEntry>) -> CreateTreeParam {
+        CreateTreeParam {
+            base_tree: base_tree.to_string(),
+            tree,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TreeEntry {
+    path: String,
+    mode: &'static str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    sha: String,
+}
+
+impl TreeEntry {
+    /// a regular file whose content is the blob
+    pub fn file(path: &str, sha: &str) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            mode: "100644",
+            kind: "blob",
+            sha: sha.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateCommitParam {
+    message: String,
+    tree: String,
+    parents: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committer: Option<GitIdentity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<GitIdentity>,
+}
+
+impl CreateCommitParam {
+    pub fn new(message: &str, tree: &str, parent: &str) -> CreateCommitParam {
+        CreateCommitParam {
+            message: message.to_string(),
+            tree: tree.to_string(),
+            parents: vec![parent.to_string()],
+            committer: None,
+            author: None,
+        }
+    }
+
+    pub fn signed_by(self, options: &CommitOptions) -> CreateCommitParam {
+        CreateCommitParam {
+            committer: options.committer.clone(),
+            author: options.author.clone(),
+            ..self
+        }
+    }
+}
+
+/// move the branch to the commit, it's rejected unless it's a fast-forward
+#[derive(Debug, Serialize)]
+pub struct UpdateRefParam {
+    sha: String,
+    force: bool,
+}
+
+impl UpdateRefParam {
+    pub fn new(sha: &str) -> UpdateRefParam {
+        UpdateRefParam {
+            sha: sha.to_string(),
+            force: false,
+        }
+    }
+}
@@ -1,16 +1,20 @@
-use std::string::FromUtf8Error;
-
 use base64::Engine;
-use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use chrono::NaiveDateTime;
 use mongodb::bson::{doc, Bson, DateTime};
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
     operations::remote::types::SyncError, traits::DocumentConvert, types::Platform, utils,
 };
 
-use super::posts::Post;
+use super::{
+    commits::{CommitOptions, GitIdentity},
+    naive_date_time_from_bson_datetime, object_id_as_string,
+    posts::{Post, SyncReq},
+    pull_requests::PullRequest,
+    serialize_naive_date_time,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GithubRecord {
@@ -26,6 +30,21 @@ pub struct GithubRecord {
     sha: String,
     repository: String,
     url: String,
+    /// tombstone of an unpublished article, the file is deleted from the repository
+    #[serde(default)]
+    deleted: bool,
+    /// path of the article before it's moved by this sync
+    #[serde(default)]
+    previous_path: Option<String>,
+    /// sha of the commit which writes the article
+    #[serde(default)]
+    commit: Option<String>,
+    /// pull request which publishes the article, if it's not committed to the default branch
+    #[serde(default)]
+    pull_request: Option<PullRequest>,
+    /// the request which synchronizes the target, bulk syncs and webhooks are rebuilt from it
+    #[serde(default)]
+    target: Option<SyncReq>,
     #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
     create_time: NaiveDateTime,
     #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
@@ -33,6 +52,10 @@ pub struct GithubRecord {
 }
 
 impl GithubRecord {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
     pub fn post_id(&self) -> i64 {
         self.post_id
     }
@@ -56,6 +79,18 @@ impl GithubRecord {
     pub fn url(&self) -> &str {
         &self.url
     }
+
+    pub fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    pub fn pull_request(&self) -> Option<&PullRequest> {
+        self.pull_request.as_ref()
+    }
+
+    pub fn target(&self) -> Option<&SyncReq> {
+        self.target.as_ref()
+    }
     
     pub fn create_time(&self) -> &NaiveDateTime {
         &self.create_time
@@ -81,6 +116,10 @@ pub struct GithubRecordVO {
     platform: Platform,
     version: String,
     latest_version: String,
+    deleted: bool,
+    previous_path: Option<String>,
+    commit: Option<String>,
+    pull_request: Option<PullRequest>,
 }
 
 impl GithubRecordVO {
@@ -96,6 +135,10 @@ impl GithubRecordVO {
             platform: Platform::Github,
             version: record.version,
             latest_version,
+            deleted: record.deleted,
+            previous_path: record.previous_path,
+            commit: record.commit,
+            pull_request: record.pull_request,
         }
     }
 }
@@ -107,6 +150,11 @@ pub struct InsertableGithubRecord {
     pub sha: String,
     pub repository: String,
     pub url: String,
+    pub deleted: bool,
+    pub previous_path: Option<String>,
+    pub commit: Option<String>,
+    pub pull_request: Option<PullRequest>,
+    pub target: Option<SyncReq>,
     pub create_time: NaiveDateTime,
     pub update_time: NaiveDateTime,
 }
@@ -120,6 +168,11 @@ impl DocumentConvert for InsertableGithubRecord {
             "sha": self.sha,
             "repository": self.repository,
             "url": self.url,
+            "deleted": self.deleted,
+            "previous_path": self.previous_path,
+            "commit": self.commit,
+            "pull_request": mongodb::bson::to_bson(&self.pull_request).unwrap_or(Bson::Null),
+            "target": mongodb::bson::to_bson(&self.target).unwrap_or(Bson::Null),
             "platform": Bson::from(Platform::Github),
             "create_time": DateTime::from_millis(self.create_time.and_utc().timestamp_millis()),
             "update_time": DateTime::from_millis(self.update_time.and_utc().timestamp_millis()),
@@ -143,10 +196,53 @@ impl InsertableGithubRecord {
             sha,
             repository,
             url,
+            deleted: false,
+            previous_path: None,
+            commit: None,
+            pull_request: None,
+            target: None,
             create_time: utils::time_utils::now(),
             update_time: utils::time_utils::now(),
         }
     }
+
+    /// record the path the article is moved from
+    pub fn moved_from(mut self, previous_path: Option<&str>) -> Self {
+        self.previous_path = previous_path.map(str::to_string);
+        self
+    }
+
+    pub fn committed(mut self, commit: Option<&str>) -> Self {
+        self.commit = commit.map(str::to_string);
+        self
+    }
+
+    pub fn with_pull_request(mut self, pull_request: Option<PullRequest>) -> Self {
+        self.pull_request = pull_request;
+        self
+    }
+
+    /// the request which synchronizes the target
+    pub fn for_target(mut self, target: Option<&SyncReq>) -> Self {
+        self.target = target.cloned();
+        self
+    }
+
+    /// tombstone of the record, which tells the article is unpublished
+    pub fn tombstone(record: &GithubRecord) -> Self {
+        Self {
+            deleted: true,
+            target: record.target.clone(),
+            ..Self::new(
+                record.post_id,
+                record.version.clone(),
+                record.path.clone(),
+                record.sha.clone(),
+                record.repository.clone(),
+                record.url.clone(),
+            )
+        }
+    }
 }
 
 /// schema of response from github
@@ -163,12 +259,12 @@ pub struct GithubArticleRecord {
 
 impl GithubArticleRecord {
     pub fn decode_content(self) -> Result<GithubArticleRecord, DecodeError> {
-        println!("self.encoding: {:?}", self.encoding);
         match &*self.encoding {
             "base64" => {
                 let content = self.content.replace('\n', "");
                 let content = base64::prelude::BASE64_STANDARD.decode(content)?;
-                let content = String::from_utf8(content)?;
+                let content = String::from_utf8(content)
+                    .map_err(|_| DecodeError::NotText(self.path.clone()))?;
                 Ok(GithubArticleRecord {
                     name: self.name,
                     path: self.path,
@@ -179,6 +275,8 @@ impl GithubArticleRecord {
                     html_url: self.html_url,
                 })
             }
+            // the contents api doesn't return files larger than 1 MB
+            "none" => Err(DecodeError::NoContent(self.path.clone())),
             _ => Err(DecodeError::UnsupportedEncoding(self.encoding.clone())),
         }
     }
@@ -188,6 +286,15 @@ impl GithubArticleRecord {
 pub struct CreateContentParam {
     message: String,
     content: String,
+    /// the default branch is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    /// the owner of the token is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committer: Option<GitIdentity>,
+    /// the committer is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<GitIdentity>,
 }
 
 impl CreateContentParam {
@@ -195,6 +302,24 @@ impl CreateContentParam {
         CreateContentParam {
             message: message.to_string(),
             content: encode_content(content),
+            branch: None,
+            committer: None,
+            author: None,
+        }
+    }
+
+    pub fn on_branch(self, branch: Option<&str>) -> CreateContentParam {
+        CreateContentParam {
+            branch: branch.map(str::to_string),
+            ..self
+        }
+    }
+
+    pub fn signed_by(self, options: &CommitOptions) -> CreateContentParam {
+        CreateContentParam {
+            committer: options.committer.clone(),
+            author: options.author.clone(),
+            ..self
         }
     }
 }
@@ -204,6 +329,15 @@ pub struct UpdateContentParam {
     message: String,
     content: String,
     sha: String,
+    /// the default branch is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    /// the owner of the token is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committer: Option<GitIdentity>,
+    /// the committer is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<GitIdentity>,
 }
 
 impl UpdateContentParam {
@@ -212,6 +346,66 @@ impl UpdateContentParam {
             message: message.to_string(),
             content: encode_content(content),
             sha: sha.to_string(),
+            branch: None,
+            committer: None,
+            author: None,
+        }
+    }
+
+    pub fn on_branch(self, branch: Option<&str>) -> UpdateContentParam {
+        UpdateContentParam {
+            branch: branch.map(str::to_string),
+            ..self
+        }
+    }
+
+    pub fn signed_by(self, options: &CommitOptions) -> UpdateContentParam {
+        UpdateContentParam {
+            committer: options.committer.clone(),
+            author: options.author.clone(),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeleteContentParam {
+    message: String,
+    sha: String,
+    /// the default branch is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    /// the owner of the token is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    committer: Option<GitIdentity>,
+    /// the committer is used if it's not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<GitIdentity>,
+}
+
+impl DeleteContentParam {
+    pub fn new(message: &str, sha: &str) -> DeleteContentParam {
+        DeleteContentParam {
+            message: message.to_string(),
+            sha: sha.to_string(),
+            branch: None,
+            committer: None,
+            author: None,
+        }
+    }
+
+    pub fn on_branch(self, branch: Option<&str>) -> DeleteContentParam {
+        DeleteContentParam {
+            branch: branch.map(str::to_string),
+            ..self
+        }
+    }
+
+    pub fn signed_by(self, options: &CommitOptions) -> DeleteContentParam {
+        DeleteContentParam {
+            committer: options.committer.clone(),
+            author: options.author.clone(),
+            ..self
         }
     }
 }
@@ -223,6 +417,13 @@ fn encode_content(content: &str) -> String {
 #[derive(Debug, Deserialize, Clone)]
 pub struct WriteContentResp {
     pub content: WriteContentRespInner,
+    #[serde(default)]
+    pub commit: Option<WriteContentRespCommit>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WriteContentRespCommit {
+    pub sha: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -237,8 +438,10 @@ pub struct WriteContentRespInner {
 pub enum DecodeError {
     #[error("Decode Error: algorithm: {0}, error: {1}")]
     Decode(String, String),
-    #[error("Invalid content")]
-    Convert,
+    #[error("{0} is not a UTF-8 text file")]
+    NotText(String),
+    #[error("Content of {0} is not returned, it may be too large")]
+    NoContent(String),
     #[error("Unsupported encoding: {0}")]
     UnsupportedEncoding(String),
 }
@@ -249,16 +452,18 @@ impl From<base64::DecodeError> for DecodeError {
     }
 }
 
-impl From<FromUtf8Error> for DecodeError {
-    fn from(_: FromUtf8Error) -> Self {
-        DecodeError::Convert
+impl From<DecodeError> for SyncError {
+    fn from(value: DecodeError) -> Self {
+        SyncError::Decode(value.to_string())
     }
 }
 
-impl From<DecodeError> for SyncError {
-    fn from(_value: DecodeError) -> Self {
-        SyncError::Decode
-    }
+#[derive(Debug, Clone, Error)]
+pub enum UpdateGithubRecordError {
+    #[error("Database Error")]
+    Database(#[source] mongodb::error::Error),
+    #[error("Invalid record id")]
+    InvalidId,
 }
 
 #[derive(Debug, Clone, Error)]
@@ -268,42 +473,3 @@ pub enum QueryGithubRecordError {
     #[error("Post not found")]
     NotFound,
 }
-
-fn naive_date_time_from_bson_datetime<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let dt = DateTime::deserialize(deserializer)?;
-    Ok(dt.to_chrono().naive_utc())
-}
-
-fn serialize_naive_date_time<S>(time: &NaiveDateTime, s: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    let time = Utc.from_utc_datetime(time);
-    let local = time.with_timezone(&Local);
-    s.serialize_str(local.format("%Y-%m-%d %H:%M:%S").to_string().as_str())
-}
-
-mod object_id_as_string {
-
-    use bson::oid::ObjectId;
-
-    use super::*;
-
-    pub fn serialize<S>(id: &str, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(id)
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let oid = ObjectId::deserialize(deserializer)?;
-        Ok(oid.to_string())
-    }
-}
@@ -0,0 +1,267 @@
+use std::fmt::{self, Display, Formatter};
+
+use chrono::NaiveDateTime;
+use diesel::{deserialize::Queryable, prelude::Insertable, Selectable};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{traits::Validate, utils};
+
+use super::{posts::SyncReq, serialize_as_string, serialize_naive_date_time, PageValidationError};
+
+/// how many times a job is executed before it's marked as failed
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+/// sync operation executed by a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobKind {
+    Synchronize,
+    Push,
+    Pull,
+    Unpublish,
+    Move,
+}
+
+impl Display for JobKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            JobKind::Synchronize => write!(f, "synchronize"),
+            JobKind::Push => write!(f, "push"),
+            JobKind::Pull => write!(f, "pull"),
+            JobKind::Unpublish => write!(f, "unpublish"),
+            JobKind::Move => write!(f, "move"),
+        }
+    }
+}
+
+impl JobKind {
+    pub fn parse(kind: &str) -> Option<JobKind> {
+        match kind {
+            "synchronize" => Some(JobKind::Synchronize),
+            "push" => Some(JobKind::Push),
+            "pull" => Some(JobKind::Pull),
+            "unpublish" => Some(JobKind::Unpublish),
+            "move" => Some(JobKind::Move),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    /// waiting for the first run or a retry
+    Pending,
+    Running,
+    Succeeded,
+    /// failed with an unrecoverable error or too many attempts
+    Failed,
+}
+
+impl Display for JobStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            JobStatus::Pending => write!(f, "pending"),
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Succeeded => write!(f, "succeeded"),
+            JobStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = crate::schema::t_sync_job)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct SyncJob {
+    pub id: i64,
+    pub post_id: i64,
+    pub kind: String,
+    /// the `SyncReq` in json
+    pub request: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_run_time: NaiveDateTime,
+    pub last_error: Option<String>,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+    /// the running job is owned by its worker until then, `None` if it isn't running
+    pub lease_expire_time: Option<NaiveDateTime>,
+}
+
+impl SyncJob {
+    pub fn kind(&self) -> Option<JobKind> {
+        JobKind::parse(&self.kind)
+    }
+
+    pub fn request(&self) -> Result<SyncReq, serde_json::Error> {
+        serde_json::from_str(&self.request)
+    }
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::t_sync_job)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct InsertableSyncJob {
+    pub id: i64,
+    pub post_id: i64,
+    pub kind: String,
+    pub request: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub next_run_time: NaiveDateTime,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl InsertableSyncJob {
+    pub fn new(
+        post_id: i64,
+        kind: JobKind,
+        req: &SyncReq,
+    ) -> Result<InsertableSyncJob, serde_json::Error> {
+        let now = utils::time_utils::now();
+        Ok(InsertableSyncJob {
+            id: utils::snowflake::next_id(),
+            post_id,
+            kind: kind.to_string(),
+            request: serde_json::to_string(req)?,
+            status: JobStatus::Pending.to_string(),
+            attempts: 0,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            next_run_time: now,
+            create_time: now,
+            update_time: now,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncJobVO {
+    #[serde(serialize_with = "serialize_as_string")]
+    id: i64,
+    #[serde(serialize_with = "serialize_as_string")]
+    post_id: i64,
+    kind: String,
+    request: Value,
+    status: String,
+    attempts: i32,
+    max_attempts: i32,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    next_run_time: NaiveDateTime,
+    last_error: Option<String>,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    create_time: NaiveDateTime,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    update_time: NaiveDateTime,
+}
+
+impl From<SyncJob> for SyncJobVO {
+    fn from(job: SyncJob) -> Self {
+        SyncJobVO {
+            id: job.id,
+            post_id: job.post_id,
+            kind: job.kind,
+            request: serde_json::from_str(&job.request).unwrap_or(Value::Null),
+            status: job.status,
+            attempts: job.attempts,
+            max_attempts: job.max_attempts,
+            next_run_time: job.next_run_time,
+            last_error: job.last_error,
+            create_time: job.create_time,
+            update_time: job.update_time,
+        }
+    }
+}
+
+/// id of the job which is enqueued by a sync route
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobCreatedVO {
+    #[serde(serialize_with = "serialize_as_string")]
+    pub job_id: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobPageReq {
+    pub page: i32,
+    pub page_size: i32,
+    pub post_id: Option<i64>,
+    pub status: Option<JobStatus>,
+}
+
+impl Validate for JobPageReq {
+    type Item = JobPageReq;
+
+    type Error = PageValidationError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if self.page <= 0 {
+            return Err(PageValidationError {
+                field: "page",
+                msg: "page must be greater than 0",
+            });
+        };
+        if self.page_size <= 0 {
+            return Err(PageValidationError {
+                field: "page_size",
+                msg: "page_size must be greater than 0",
+            });
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QueryJobError {
+    #[error("Database Error")]
+    Database,
+    #[error("Job not found")]
+    NotFound,
+}
+
+#[derive(Debug, Error)]
+pub enum CreateJobError {
+    #[error("Database Error")]
+    Database,
+    #[error("Invalid sync request: {0}")]
+    Serialize(String),
+}
+
+#[derive(Debug, Error)]
+pub enum UpdateJobError {
+    #[error("Database Error")]
+    Database,
+}
+
+impl From<diesel::result::Error> for QueryJobError {
+    fn from(item: diesel::result::Error) -> Self {
+        match item {
+            diesel::result::Error::NotFound => QueryJobError::NotFound,
+            _ => QueryJobError::Database,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for CreateJobError {
+    fn from(_: diesel::result::Error) -> Self {
+        CreateJobError::Database
+    }
+}
+
+impl From<serde_json::Error> for CreateJobError {
+    fn from(item: serde_json::Error) -> Self {
+        CreateJobError::Serialize(item.to_string())
+    }
+}
+
+impl From<diesel::result::Error> for UpdateJobError {
+    fn from(_: diesel::result::Error) -> Self {
+        UpdateJobError::Database
+    }
+}
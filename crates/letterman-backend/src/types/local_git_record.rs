@@ -0,0 +1,213 @@
+use chrono::NaiveDateTime;
+use mongodb::bson::{doc, Bson, DateTime};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{traits::DocumentConvert, types::Platform, utils};
+
+use super::{
+    naive_date_time_from_bson_datetime, object_id_as_string,
+    posts::{Post, SyncReq},
+    serialize_naive_date_time,
+};
+
+/// record of a post written into a local git working tree
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LocalGitRecord {
+    #[serde(
+        rename = "_id",
+        serialize_with = "object_id_as_string::serialize",
+        deserialize_with = "object_id_as_string::deserialize"
+    )]
+    id: String,
+    post_id: i64,
+    version: String,
+    path: String,
+    commit: String,
+    repository: String,
+    /// tombstone of an unpublished article, the file is deleted from the repository
+    #[serde(default)]
+    deleted: bool,
+    /// path of the article before it's moved by this sync
+    #[serde(default)]
+    previous_path: Option<String>,
+    /// the request which synchronizes the target, bulk syncs and webhooks are rebuilt from it
+    #[serde(default)]
+    target: Option<SyncReq>,
+    #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
+    create_time: NaiveDateTime,
+    #[serde(deserialize_with = "naive_date_time_from_bson_datetime")]
+    update_time: NaiveDateTime,
+}
+
+impl LocalGitRecord {
+    pub fn post_id(&self) -> i64 {
+        self.post_id
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// path of the article relative to the repository root
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// hash of the commit which contains this version
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+
+    /// local directory of the repository checkout
+    pub fn repository(&self) -> &str {
+        &self.repository
+    }
+
+    pub fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    pub fn target(&self) -> Option<&SyncReq> {
+        self.target.as_ref()
+    }
+
+    pub fn create_time(&self) -> &NaiveDateTime {
+        &self.create_time
+    }
+
+    pub fn update_time(&self) -> &NaiveDateTime {
+        &self.update_time
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalGitRecordVO {
+    id: String,
+    post: Post,
+    path: String,
+    repository: String,
+    commit: String,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    create_time: NaiveDateTime,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    update_time: NaiveDateTime,
+    platform: Platform,
+    version: String,
+    latest_version: String,
+    deleted: bool,
+    previous_path: Option<String>,
+}
+
+impl LocalGitRecordVO {
+    pub fn package(record: LocalGitRecord, post: Post, latest_version: String) -> Self {
+        Self {
+            id: record.id,
+            post,
+            path: record.path,
+            repository: record.repository,
+            commit: record.commit,
+            create_time: record.create_time,
+            update_time: record.update_time,
+            platform: Platform::LocalGit,
+            version: record.version,
+            latest_version,
+            deleted: record.deleted,
+            previous_path: record.previous_path,
+        }
+    }
+}
+
+pub struct InsertableLocalGitRecord {
+    pub post_id: i64,
+    pub version: String,
+    pub path: String,
+    pub commit: String,
+    pub repository: String,
+    pub deleted: bool,
+    pub previous_path: Option<String>,
+    pub target: Option<SyncReq>,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl DocumentConvert for InsertableLocalGitRecord {
+    fn to_doc(self) -> mongodb::bson::Document {
+        doc! {
+            "post_id": self.post_id,
+            "version": self.version,
+            "path": self.path,
+            "commit": self.commit,
+            "repository": self.repository,
+            "deleted": self.deleted,
+            "previous_path": self.previous_path,
+            "target": mongodb::bson::to_bson(&self.target).unwrap_or(Bson::Null),
+            "platform": Bson::from(Platform::LocalGit),
+            "create_time": DateTime::from_millis(self.create_time.and_utc().timestamp_millis()),
+            "update_time": DateTime::from_millis(self.update_time.and_utc().timestamp_millis()),
+        }
+    }
+}
+
+impl InsertableLocalGitRecord {
+    pub fn new(
+        post_id: i64,
+        version: String,
+        path: String,
+        commit: String,
+        repository: String,
+    ) -> Self {
+        Self {
+            post_id,
+            version,
+            path,
+            commit,
+            repository,
+            deleted: false,
+            previous_path: None,
+            target: None,
+            create_time: utils::time_utils::now(),
+            update_time: utils::time_utils::now(),
+        }
+    }
+
+    /// record the path the article is moved from
+    pub fn moved_from(mut self, previous_path: Option<&str>) -> Self {
+        self.previous_path = previous_path.map(str::to_string);
+        self
+    }
+
+    /// the request which synchronizes the target
+    pub fn for_target(mut self, target: Option<&SyncReq>) -> Self {
+        self.target = target.cloned();
+        self
+    }
+
+    /// tombstone of the record, `commit` is the commit deleting the article
+    pub fn tombstone(record: &LocalGitRecord, commit: String) -> Self {
+        Self {
+            deleted: true,
+            target: record.target.clone(),
+            ..Self::new(
+                record.post_id,
+                record.version.clone(),
+                record.path.clone(),
+                commit,
+                record.repository.clone(),
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum QueryLocalGitRecordError {
+    #[error("Database Error")]
+    Database(#[source] mongodb::error::Error),
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum CreateLocalGitRecordError {
+    #[error("Database error")]
+    Database(#[source] mongodb::error::Error),
+}
@@ -4,6 +4,7 @@ use std::{
 };
 
 use crate::{
+    operations::remote::types::SyncError,
     routes::posts::PostResponseError,
     traits::Validate,
     utils::{self},
@@ -15,13 +16,26 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::{
-    deserialize_from_string,
+    commits::{CommitOptions, GitIdentity},
+    deserialize_from_string, deserialize_option_from_string,
+    forge_record::{ForgeRecord, ForgeRecordVO, GiteaRecord, GitlabRecord},
+    git_data::GithubWriteMode,
     github_record::{GithubRecord, GithubRecordVO},
-    serialize_as_string, serialize_metadata, PageValidationError, Platform,
+    local_git_record::{LocalGitRecord, LocalGitRecordVO},
+    serialize_as_string, serialize_metadata, serialize_option_as_string,
+    transforms::TransformRule,
+    PageValidationError, Platform,
 };
 
 use thiserror::Error;
 
+/// frontmatter of a post except the title, the values are strings, numbers, booleans,
+/// lists or maps like in YAML. Dates are kept as they are written, e.g. `2022-07-02 17:02:19`
+pub type Metadata = HashMap<String, Value>;
+
+/// `t_post.metadata` is a TEXT column
+const METADATA_MAX_LEN: usize = 65535;
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Post {
@@ -37,7 +51,7 @@ pub struct Post {
     post_id: i64,
     title: String,
     #[serde(serialize_with = "serialize_metadata")]
-    metadata: HashMap<String, String>,
+    metadata: Metadata,
     content: String,
     version: String,
     pre_version: String,
@@ -46,11 +60,12 @@ pub struct Post {
 }
 
 impl Post {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: i64,
         post_id: i64,
         title: String,
-        metadata: HashMap<String, String>,
+        metadata: Metadata,
         content: String,
         version: String,
         pre_version: String,
@@ -100,6 +115,12 @@ impl Post {
         &self.title
     }
 
+    /// the same post with another content, e.g. the content transformed for a target
+    pub fn with_content(mut self, content: String) -> Self {
+        self.content = content;
+        self
+    }
+
     pub fn to_po(self, head: bool) -> (InsertableBasePost, InsertablePostContent) {
         let base = InsertableBasePost {
             id: utils::snowflake::next_id(),
@@ -121,7 +142,7 @@ impl Post {
         (base, content)
     }
 
-    pub fn metadata(&self) -> &HashMap<String, String> {
+    pub fn metadata(&self) -> &Metadata {
         &self.metadata
     }
 
@@ -337,19 +358,28 @@ fn validate_post_data(
         });
     }
 
-    if metadata.len() > 255 {
+    if metadata.len() > METADATA_MAX_LEN {
         return Err(ValidateManipulatePostError {
             field: "metadata",
-            msg: "cannot be longer than 255 characters",
+            msg: "cannot be longer than 65535 bytes",
         });
     }
 
-    if let Err(e) = serde_json::from_str::<Value>(metadata) {
-        error!("failed to parse json:{}, error: {e}", metadata);
-        return Err(ValidateManipulatePostError {
-            field: "metadata",
-            msg: "failed to parse metadata, please make sure it's a json",
-        });
+    match serde_json::from_str::<Metadata>(metadata) {
+        Ok(metadata) if metadata.contains_key("title") => {
+            return Err(ValidateManipulatePostError {
+                field: "metadata",
+                msg: "title is a field of the post, it can't be in metadata",
+            });
+        }
+        Ok(_) => {}
+        Err(e) => {
+            error!("failed to parse json:{}, error: {e}", metadata);
+            return Err(ValidateManipulatePostError {
+                field: "metadata",
+                msg: "failed to parse metadata, please make sure it's a json object",
+            });
+        }
     }
     Ok(())
 }
@@ -509,17 +539,51 @@ impl Validate for PostPageReq {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "platform")]
 pub enum SyncReq {
     Github(GithubSyncReq),
+    Gitea(GiteaSyncReq),
+    Gitlab(GitlabSyncReq),
+    LocalGit(LocalGitSyncReq),
 }
 
-#[derive(Debug, Deserialize)]
-
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GithubSyncReq {
     path: Option<String>,
     repository: Option<String>,
+    /// base url of the github api, e.g. a github enterprise server
+    #[serde(alias = "api_url")]
+    api_url: Option<String>,
+    #[serde(default)]
+    mode: GithubWriteMode,
+    /// push to a branch of the post and open a pull request, instead of the default branch
+    #[serde(default, alias = "pull_request")]
+    pull_request: bool,
+    /// branch to commit to, the default is `GITHUB_BRANCH` env or the default branch
+    branch: Option<String>,
+    /// template of the commit messages, the default is `GITHUB_COMMIT_MESSAGE` env
+    #[serde(alias = "commit_message")]
+    commit_message: Option<String>,
+    committer: Option<GitIdentity>,
+    author: Option<GitIdentity>,
+    /// id of the credential whose token is used, the default is the github app or
+    /// `GITHUB_TOKEN` env
+    #[serde(
+        default,
+        alias = "credential_id",
+        serialize_with = "serialize_option_as_string",
+        deserialize_with = "deserialize_option_from_string"
+    )]
+    credential_id: Option<i64>,
+    /// installation of the github app which has access to the repository,
+    /// the default is `GITHUB_APP_INSTALLATION_ID` env
+    #[serde(alias = "installation_id")]
+    installation_id: Option<i64>,
+    /// rules which transform the content for the target, applied in order
+    #[serde(default)]
+    transforms: Vec<TransformRule>,
 }
 
 impl GithubSyncReq {
@@ -530,6 +594,323 @@ impl GithubSyncReq {
     pub fn repository(&self) -> Option<String> {
         self.repository.clone()
     }
+
+    pub fn api_url(&self) -> Option<String> {
+        self.api_url.clone()
+    }
+
+    pub fn mode(&self) -> GithubWriteMode {
+        self.mode
+    }
+
+    pub fn pull_request(&self) -> bool {
+        self.pull_request
+    }
+
+    pub fn installation_id(&self) -> Option<i64> {
+        self.installation_id
+    }
+
+    pub fn commit_options(&self) -> CommitOptions {
+        CommitOptions {
+            message: self.commit_message.clone(),
+            branch: self.branch.clone(),
+            committer: self.committer.clone(),
+            author: self.author.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GiteaSyncReq {
+    path: Option<String>,
+    /// `owner/repo`
+    repository: Option<String>,
+    /// id of the credential whose token is used, the default is `GITEA_TOKEN` env
+    #[serde(
+        default,
+        alias = "credential_id",
+        serialize_with = "serialize_option_as_string",
+        deserialize_with = "deserialize_option_from_string"
+    )]
+    credential_id: Option<i64>,
+    /// rules which transform the content for the target, applied in order
+    #[serde(default)]
+    transforms: Vec<TransformRule>,
+}
+
+impl GiteaSyncReq {
+    pub fn path(&self) -> Option<String> {
+        self.path.clone()
+    }
+
+    pub fn repository(&self) -> Option<String> {
+        self.repository.clone()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitlabSyncReq {
+    path: Option<String>,
+    /// full path of the project, e.g. `group/project`
+    repository: Option<String>,
+    /// branch to commit to, the default is `GITLAB_BRANCH` env or `main`
+    branch: Option<String>,
+    /// id of the credential whose token is used, the default is `GITLAB_TOKEN` env
+    #[serde(
+        default,
+        alias = "credential_id",
+        serialize_with = "serialize_option_as_string",
+        deserialize_with = "deserialize_option_from_string"
+    )]
+    credential_id: Option<i64>,
+    /// rules which transform the content for the target, applied in order
+    #[serde(default)]
+    transforms: Vec<TransformRule>,
+}
+
+impl GitlabSyncReq {
+    pub fn path(&self) -> Option<String> {
+        self.path.clone()
+    }
+
+    pub fn repository(&self) -> Option<String> {
+        self.repository.clone()
+    }
+
+    pub fn branch(&self) -> Option<String> {
+        self.branch.clone()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalGitSyncReq {
+    /// path relative to the repository root
+    path: Option<String>,
+    /// directory of the repository checkout under `LOCAL_GIT_ROOT` env, relative to it
+    repository: Option<String>,
+    /// rules which transform the content for the target, applied in order
+    #[serde(default)]
+    transforms: Vec<TransformRule>,
+}
+
+impl LocalGitSyncReq {
+    pub fn path(&self) -> Option<String> {
+        self.path.clone()
+    }
+
+    pub fn repository(&self) -> Option<String> {
+        self.repository.clone()
+    }
+}
+
+/// publish a post to several platforms at once
+#[derive(Debug, Deserialize)]
+pub struct PublishReq {
+    pub targets: Vec<SyncReq>,
+}
+
+impl Validate for PublishReq {
+    type Item = PublishReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if self.targets.is_empty() {
+            return Err(ValidateManipulatePostError {
+                field: "targets",
+                msg: "at least one target is required",
+            });
+        }
+        Ok(self)
+    }
+}
+
+impl SyncReq {
+    pub fn platform(&self) -> Platform {
+        match self {
+            SyncReq::Github(_) => Platform::Github,
+            SyncReq::Gitea(_) => Platform::Gitea,
+            SyncReq::Gitlab(_) => Platform::Gitlab,
+            SyncReq::LocalGit(_) => Platform::LocalGit,
+        }
+    }
+
+    /// the credential whose token is used instead of the one of the env
+    pub fn credential_id(&self) -> Option<i64> {
+        match self {
+            SyncReq::Github(req) => req.credential_id,
+            SyncReq::Gitea(req) => req.credential_id,
+            SyncReq::Gitlab(req) => req.credential_id,
+            SyncReq::LocalGit(_) => None,
+        }
+    }
+
+    /// rules which transform the content for the target
+    pub fn transforms(&self) -> &[TransformRule] {
+        match self {
+            SyncReq::Github(req) => &req.transforms,
+            SyncReq::Gitea(req) => &req.transforms,
+            SyncReq::Gitlab(req) => &req.transforms,
+            SyncReq::LocalGit(req) => &req.transforms,
+        }
+    }
+
+    /// the request which synchronized the record, at the current place of the record.
+    /// the records written before the targets are stored can't be rebuilt, since their
+    /// credential, mode and transforms are unknown, the target has to be synchronized by
+    /// a request once.
+    pub fn from_record(record: &SyncRecord) -> Result<SyncReq, SyncError> {
+        match record.target() {
+            Some(target) if target.platform() == record.platform() => {
+                Ok(target.clone().at(record.path(), record.repository()))
+            }
+            _ => Err(SyncError::UserError(format!(
+                "the settings of {} of {} aren't stored, synchronize it by a request first",
+                record.path(),
+                record.repository()
+            ))),
+        }
+    }
+
+    /// the same request which synchronizes to another place
+    fn at(self, path: &str, repository: &str) -> SyncReq {
+        let path = Some(path.to_string());
+        let repository = Some(repository.to_string());
+        match self {
+            SyncReq::Github(req) => SyncReq::Github(GithubSyncReq {
+                path,
+                repository,
+                ..req
+            }),
+            SyncReq::Gitea(req) => SyncReq::Gitea(GiteaSyncReq {
+                path,
+                repository,
+                ..req
+            }),
+            SyncReq::Gitlab(req) => SyncReq::Gitlab(GitlabSyncReq {
+                path,
+                repository,
+                ..req
+            }),
+            SyncReq::LocalGit(req) => SyncReq::LocalGit(LocalGitSyncReq {
+                path,
+                repository,
+                ..req
+            }),
+        }
+    }
+
+    /// the place of the record, it only identifies the target in the reports
+    pub fn location(record: &SyncRecord) -> SyncReq {
+        let path = Some(record.path().to_string());
+        let repository = Some(record.repository().to_string());
+        match record.platform() {
+            Platform::Github => SyncReq::Github(GithubSyncReq {
+                path,
+                repository,
+                api_url: None,
+                mode: GithubWriteMode::default(),
+                pull_request: false,
+                branch: None,
+                commit_message: None,
+                committer: None,
+                author: None,
+                credential_id: None,
+                installation_id: None,
+                transforms: vec![],
+            }),
+            Platform::Gitea => SyncReq::Gitea(GiteaSyncReq {
+                path,
+                repository,
+                credential_id: None,
+                transforms: vec![],
+            }),
+            Platform::Gitlab => SyncReq::Gitlab(GitlabSyncReq {
+                path,
+                repository,
+                branch: None,
+                credential_id: None,
+                transforms: vec![],
+            }),
+            Platform::LocalGit => SyncReq::LocalGit(LocalGitSyncReq {
+                path,
+                repository,
+                transforms: vec![],
+            }),
+        }
+    }
+}
+
+pub const DEFAULT_BULK_SYNC_CONCURRENCY: usize = 4;
+const MAX_BULK_SYNC_CONCURRENCY: usize = 16;
+
+/// synchronize all posts changed since their latest sync records
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkSyncReq {
+    pub platform: Option<Platform>,
+    pub repository: Option<String>,
+    /// only posts whose head version is created in [since, until)
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    /// how many posts are synchronized at the same time
+    pub concurrency: Option<usize>,
+}
+
+impl Validate for BulkSyncReq {
+    type Item = BulkSyncReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if let Some(concurrency) = self.concurrency {
+            if concurrency == 0 || concurrency > MAX_BULK_SYNC_CONCURRENCY {
+                return Err(ValidateManipulatePostError {
+                    field: "concurrency",
+                    msg: "concurrency must be between 1 and 16",
+                });
+            }
+        }
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since >= until {
+                return Err(ValidateManipulatePostError {
+                    field: "since",
+                    msg: "since must be earlier than until",
+                });
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// query of the sync routes, nothing is written in preview mode
+#[derive(Debug, Default, Deserialize)]
+pub struct SyncModeReq {
+    #[serde(default)]
+    pub preview: bool,
+    /// enqueue a background job and return its id instead of waiting for the result
+    #[serde(default, rename = "async")]
+    pub background: bool,
+}
+
+impl Validate for SyncModeReq {
+    type Item = SyncModeReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if self.preview && self.background {
+            return Err(ValidateManipulatePostError {
+                field: "async",
+                msg: "a preview can't be run in background",
+            });
+        }
+        Ok(self)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -566,12 +947,84 @@ impl Validate for SyncPageReq {
 #[serde(tag = "platform")]
 pub enum SyncRecord {
     Github(GithubRecord),
+    Gitea(GiteaRecord),
+    Gitlab(GitlabRecord),
+    LocalGit(LocalGitRecord),
+}
+
+impl SyncRecord {
+    pub fn post_id(&self) -> i64 {
+        match self {
+            SyncRecord::Github(r) => r.post_id(),
+            SyncRecord::Gitea(r) => r.post_id(),
+            SyncRecord::Gitlab(r) => r.post_id(),
+            SyncRecord::LocalGit(r) => r.post_id(),
+        }
+    }
+
+    pub fn version(&self) -> &str {
+        match self {
+            SyncRecord::Github(r) => r.version(),
+            SyncRecord::Gitea(r) => r.version(),
+            SyncRecord::Gitlab(r) => r.version(),
+            SyncRecord::LocalGit(r) => r.version(),
+        }
+    }
+
+    pub fn platform(&self) -> Platform {
+        match self {
+            SyncRecord::Github(_) => Platform::Github,
+            SyncRecord::Gitea(_) => Platform::Gitea,
+            SyncRecord::Gitlab(_) => Platform::Gitlab,
+            SyncRecord::LocalGit(_) => Platform::LocalGit,
+        }
+    }
+
+    pub fn repository(&self) -> &str {
+        match self {
+            SyncRecord::Github(r) => r.repository(),
+            SyncRecord::Gitea(r) => r.repository(),
+            SyncRecord::Gitlab(r) => r.repository(),
+            SyncRecord::LocalGit(r) => r.repository(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            SyncRecord::Github(r) => r.path(),
+            SyncRecord::Gitea(r) => r.path(),
+            SyncRecord::Gitlab(r) => r.path(),
+            SyncRecord::LocalGit(r) => r.path(),
+        }
+    }
+
+    /// the request which synchronized the record
+    pub fn target(&self) -> Option<&SyncReq> {
+        match self {
+            SyncRecord::Github(r) => r.target(),
+            SyncRecord::Gitea(r) => r.target(),
+            SyncRecord::Gitlab(r) => r.target(),
+            SyncRecord::LocalGit(r) => r.target(),
+        }
+    }
+
+    /// whether the article is unpublished
+    pub fn deleted(&self) -> bool {
+        match self {
+            SyncRecord::Github(r) => r.deleted(),
+            SyncRecord::Gitea(r) => r.deleted(),
+            SyncRecord::Gitlab(r) => r.deleted(),
+            SyncRecord::LocalGit(r) => r.deleted(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum SyncRecordVO {
     Github(GithubRecordVO),
+    Forge(ForgeRecordVO),
+    LocalGit(LocalGitRecordVO),
 }
 
 #[derive(Debug, Error)]
@@ -610,3 +1063,127 @@ impl From<diesel::result::Error> for RevertPostError {
         }
     }
 }
+
+#[cfg(test)]
+mod posts_test {
+    use super::*;
+
+    #[test]
+    fn validate_metadata_test() {
+        let tags: Vec<String> = (0..50).map(|i| format!("tag-{i}")).collect();
+        let metadata = serde_json::json!({"tags": tags, "summary": "a".repeat(200)}).to_string();
+        assert!(metadata.len() > 255);
+        assert!(validate_post_data("title", &metadata, "").is_ok());
+
+        let error = validate_post_data("title", r#"["a"]"#, "").unwrap_err();
+        assert_eq!(error.field, "metadata");
+        let error = validate_post_data("title", r#"{"title": "a"}"#, "").unwrap_err();
+        assert_eq!(error.field, "metadata");
+        let metadata = serde_json::json!({"summary": "a".repeat(METADATA_MAX_LEN)}).to_string();
+        assert!(validate_post_data("title", &metadata, "").is_err());
+    }
+
+    /// a github record written by the request, through the document stored in mongodb
+    fn github_record(target: Option<&SyncReq>) -> SyncRecord {
+        use crate::{traits::DocumentConvert, types::github_record::InsertableGithubRecord};
+        let mut doc = InsertableGithubRecord::new(
+            1,
+            "v1".to_string(),
+            "posts/moved.md".to_string(),
+            "sha".to_string(),
+            "letterman/blog".to_string(),
+            "url".to_string(),
+        )
+        .for_target(target)
+        .to_doc();
+        doc.insert("_id", mongodb::bson::oid::ObjectId::new());
+        SyncRecord::Github(mongodb::bson::from_document(doc).unwrap())
+    }
+
+    fn github_req(json: serde_json::Value) -> SyncReq {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn from_record_test() {
+        use crate::{traits::DocumentConvert, types::forge_record::InsertableForgeRecord};
+        let req = github_req(serde_json::json!({
+            "platform": "Github",
+            "path": "posts/hello.md",
+            "repository": "letterman/blog",
+            "apiUrl": "https://github.example.com/api/v3",
+            "mode": "gitData",
+            "pullRequest": true,
+            "branch": "blog",
+            "credentialId": "42",
+            "installationId": 7,
+            "transforms": [
+                {"kind": "callout", "style": "hexoTag"},
+                {"kind": "wikiLink", "prefix": "/posts/", "suffix": ".html"},
+                {
+                    "kind": "regex",
+                    "pattern": "->",
+                    "replacement": "→",
+                    "reverse": {"pattern": "→", "replacement": "->"}
+                }
+            ],
+        }));
+        let rebuilt = SyncReq::from_record(&github_record(Some(&req))).unwrap();
+        // bulk syncs and webhooks push with the credential and the pipeline of the target
+        assert_eq!(rebuilt.credential_id(), Some(42));
+        assert_eq!(rebuilt.transforms().len(), 3);
+        assert_eq!(rebuilt.transforms(), req.transforms());
+        let SyncReq::Github(rebuilt) = rebuilt else {
+            panic!("platform is changed");
+        };
+        assert_eq!(rebuilt.path(), Some("posts/moved.md".to_string()));
+        assert_eq!(rebuilt.repository(), Some("letterman/blog".to_string()));
+        assert_eq!(
+            rebuilt.api_url(),
+            Some("https://github.example.com/api/v3".to_string())
+        );
+        assert_eq!(rebuilt.mode(), GithubWriteMode::GitData);
+        assert!(rebuilt.pull_request());
+        assert_eq!(rebuilt.installation_id(), Some(7));
+        assert_eq!(rebuilt.commit_options().branch, Some("blog".to_string()));
+        // the records written before the targets are stored can't be rebuilt
+        assert!(SyncReq::from_record(&github_record(None)).is_err());
+
+        // the targets stored in snake case are still read
+        let req = github_req(serde_json::json!({
+            "platform": "Gitlab",
+            "path": "posts/hello.md",
+            "repository": "group/blog",
+            "credential_id": "43",
+        }));
+        let mut doc = InsertableForgeRecord::new(
+            Platform::Gitlab,
+            1,
+            "v1".to_string(),
+            "posts/hello.md".to_string(),
+            "sha".to_string(),
+            "group/blog".to_string(),
+            "url".to_string(),
+        )
+        .for_target(Some(&req))
+        .to_doc();
+        doc.insert("_id", mongodb::bson::oid::ObjectId::new());
+        let record = SyncRecord::Gitlab(mongodb::bson::from_document(doc).unwrap());
+        assert_eq!(
+            SyncReq::from_record(&record).unwrap().credential_id(),
+            Some(43)
+        );
+    }
+
+    #[test]
+    fn sync_mode_test() {
+        let mode = |json| {
+            serde_json::from_value::<SyncModeReq>(json)
+                .unwrap()
+                .validate()
+        };
+        assert!(mode(serde_json::json!({"preview": true})).is_ok());
+        assert!(mode(serde_json::json!({"async": true})).is_ok());
+        assert!(mode(serde_json::json!({"preview": true, "async": true})).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! pull requests which publish articles to repositories requiring reviews.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PullRequestState {
+    Open,
+    Closed,
+    Merged,
+}
+
+/// pull request stored in the sync record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequest {
+    pub number: i64,
+    /// branch the article is pushed to
+    pub branch: String,
+    pub state: PullRequestState,
+    /// url to view the pull request in browser
+    pub url: String,
+}
+
+/// schema of the pull request returned by github
+#[derive(Debug, Deserialize)]
+pub struct PullRequestResp {
+    pub number: i64,
+    /// `open` or `closed`
+    pub state: String,
+    pub merged_at: Option<String>,
+    pub html_url: String,
+    pub head: PullRequestHead,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequestHead {
+    #[serde(rename = "ref")]
+    pub branch: String,
+}
+
+impl From<PullRequestResp> for PullRequest {
+    fn from(resp: PullRequestResp) -> Self {
+        let state = match (&*resp.state, resp.merged_at) {
+            ("open", _) => PullRequestState::Open,
+            (_, Some(_)) => PullRequestState::Merged,
+            _ => PullRequestState::Closed,
+        };
+        PullRequest {
+            number: resp.number,
+            branch: resp.head.branch,
+            state,
+            url: resp.html_url,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatePullRequestParam {
+    title: String,
+    head: String,
+    base: String,
+    body: String,
+}
+
+impl CreatePullRequestParam {
+    pub fn new(title: &str, head: &str, base: &str) -> CreatePullRequestParam {
+        CreatePullRequestParam {
+            title: title.to_string(),
+            head: head.to_string(),
+            base: base.to_string(),
+            body: "Published by letterman.".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateRefParam {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    sha: String,
+}
+
+impl CreateRefParam {
+    /// create the branch at the commit
+    pub fn branch(branch: &str, sha: &str) -> CreateRefParam {
+        CreateRefParam {
+            git_ref: format!("refs/heads/{branch}"),
+            sha: sha.to_string(),
+        }
+    }
+}
+
+/// name of the branch publishing the version of the post, e.g. `letterman/1-hello-world-0a1b2c3d`.
+/// Every version gets a new branch, so a closed pull request never gets in the way.
+pub fn branch_name(post_id: i64, title: &str, version: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_end_matches('-').chars().take(40).collect();
+    let slug = slug.trim_end_matches('-');
+    let version: String = version.chars().take(8).collect();
+    if slug.is_empty() {
+        format!("letterman/{post_id}-{version}")
+    } else {
+        format!("letterman/{post_id}-{slug}-{version}")
+    }
+}
+
+#[cfg(test)]
+mod pull_requests_test {
+    use super::*;
+
+    #[test]
+    fn branch_name_test() {
+        assert_eq!(
+            branch_name(1, "Hello, World!", "0a1b2c3d4e5f"),
+            "letterman/1-hello-world-0a1b2c3d"
+        );
+        assert_eq!(branch_name(2, "你好", "abc"), "letterman/2-abc");
+    }
+
+    #[test]
+    fn state_test() {
+        let resp = |state: &str, merged_at: Option<&str>| PullRequestResp {
+            number: 1,
+            state: state.to_string(),
+            merged_at: merged_at.map(str::to_string),
+            html_url: String::new(),
+            head: PullRequestHead {
+                branch: "letterman/1".to_string(),
+            },
+        };
+        let state = |resp: PullRequestResp| PullRequest::from(resp).state;
+        assert_eq!(state(resp("open", None)), PullRequestState::Open);
+        assert_eq!(state(resp("closed", None)), PullRequestState::Closed);
+        assert_eq!(
+            state(resp("closed", Some("2024-01-01T00:00:00Z"))),
+            PullRequestState::Merged
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::traits::Validate;
+
+use super::{
+    deserialize_option_from_string,
+    posts::{SyncReq, ValidateManipulatePostError},
+};
+
+/// render a post or the markdown submitted to html
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderReq {
+    #[serde(default, deserialize_with = "deserialize_option_from_string")]
+    pub post_id: Option<i64>,
+    /// version of the post, the latest version by default
+    pub version: Option<String>,
+    /// markdown rendered instead of a post
+    pub content: Option<String>,
+    /// the target whose transforms are applied before the content is rendered
+    pub target: Option<SyncReq>,
+}
+
+impl Validate for RenderReq {
+    type Item = RenderReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        match (&self.post_id, &self.content) {
+            (Some(_), Some(_)) | (None, None) => Err(ValidateManipulatePostError {
+                field: "postId",
+                msg: "either postId or content is required",
+            }),
+            (None, Some(_)) if self.version.is_some() => Err(ValidateManipulatePostError {
+                field: "version",
+                msg: "version is only allowed with postId",
+            }),
+            _ => Ok(self),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenderedVO {
+    /// title of the post, `None` for the markdown submitted
+    pub title: Option<String>,
+    /// sanitized html, the code is highlighted with the classes of `highlight.css`
+    pub html: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HighlightCssReq {
+    /// a theme of syntect, `InspiredGitHub` by default
+    pub theme: Option<String>,
+}
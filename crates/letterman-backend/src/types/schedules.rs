@@ -0,0 +1,311 @@
+use std::fmt::{self, Display, Formatter};
+
+use chrono::NaiveDateTime;
+use diesel::{deserialize::Queryable, prelude::Insertable, Selectable};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{traits::Validate, utils};
+
+use super::{
+    deserialize_from_string,
+    posts::{SyncReq, ValidateManipulatePostError},
+    serialize_as_string, serialize_naive_date_time, PageValidationError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScheduleStatus {
+    /// waiting for the publish time
+    Pending,
+    Running,
+    /// every target is synchronized or up to date
+    Published,
+    /// at least one of the targets is failed
+    Failed,
+}
+
+impl Display for ScheduleStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ScheduleStatus::Pending => write!(f, "pending"),
+            ScheduleStatus::Running => write!(f, "running"),
+            ScheduleStatus::Published => write!(f, "published"),
+            ScheduleStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// a version of the post which is published to the targets at `publish_at`
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = crate::schema::t_post_schedule)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct PostSchedule {
+    pub id: i64,
+    pub post_id: i64,
+    pub version: String,
+    /// the `SyncReq`s in json
+    pub targets: String,
+    pub publish_at: NaiveDateTime,
+    pub status: String,
+    /// the `TargetResult`s of the last run in json
+    pub result: Option<String>,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+    /// the running schedule is owned by its scheduler until then, `None` if it isn't running
+    pub lease_expire_time: Option<NaiveDateTime>,
+}
+
+impl PostSchedule {
+    pub fn targets(&self) -> Result<Vec<SyncReq>, serde_json::Error> {
+        serde_json::from_str(&self.targets)
+    }
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::t_post_schedule)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct InsertablePostSchedule {
+    pub id: i64,
+    pub post_id: i64,
+    pub version: String,
+    pub targets: String,
+    pub publish_at: NaiveDateTime,
+    pub status: String,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl InsertablePostSchedule {
+    pub fn new(
+        post_id: i64,
+        version: String,
+        targets: &[SyncReq],
+        publish_at: NaiveDateTime,
+    ) -> Result<InsertablePostSchedule, serde_json::Error> {
+        let now = utils::time_utils::now();
+        Ok(InsertablePostSchedule {
+            id: utils::snowflake::next_id(),
+            post_id,
+            version,
+            targets: serde_json::to_string(targets)?,
+            publish_at,
+            status: ScheduleStatus::Pending.to_string(),
+            create_time: now,
+            update_time: now,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostScheduleVO {
+    #[serde(serialize_with = "serialize_as_string")]
+    id: i64,
+    #[serde(serialize_with = "serialize_as_string")]
+    post_id: i64,
+    version: String,
+    targets: Value,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    publish_at: NaiveDateTime,
+    status: String,
+    result: Option<Value>,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    create_time: NaiveDateTime,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    update_time: NaiveDateTime,
+}
+
+impl From<PostSchedule> for PostScheduleVO {
+    fn from(schedule: PostSchedule) -> Self {
+        PostScheduleVO {
+            id: schedule.id,
+            post_id: schedule.post_id,
+            version: schedule.version,
+            targets: serde_json::from_str(&schedule.targets).unwrap_or(Value::Null),
+            publish_at: schedule.publish_at,
+            status: schedule.status,
+            result: schedule
+                .result
+                .and_then(|result| serde_json::from_str(&result).ok()),
+            create_time: schedule.create_time,
+            update_time: schedule.update_time,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateScheduleReq {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub post_id: i64,
+    /// the latest version is scheduled if it's absent
+    pub version: Option<String>,
+    pub targets: Vec<SyncReq>,
+    /// in UTC
+    pub publish_at: NaiveDateTime,
+}
+
+impl Validate for CreateScheduleReq {
+    type Item = CreateScheduleReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        validate_targets(&self.targets)?;
+        Ok(self)
+    }
+}
+
+/// fields which are absent are not changed
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateScheduleReq {
+    #[serde(deserialize_with = "deserialize_from_string")]
+    pub id: i64,
+    pub version: Option<String>,
+    pub targets: Option<Vec<SyncReq>>,
+    pub publish_at: Option<NaiveDateTime>,
+}
+
+impl Validate for UpdateScheduleReq {
+    type Item = UpdateScheduleReq;
+
+    type Error = ValidateManipulatePostError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if let Some(targets) = &self.targets {
+            validate_targets(targets)?;
+        }
+        Ok(self)
+    }
+}
+
+fn validate_targets(targets: &[SyncReq]) -> Result<(), ValidateManipulatePostError> {
+    if targets.is_empty() {
+        return Err(ValidateManipulatePostError {
+            field: "targets",
+            msg: "at least one target is required",
+        });
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulePageReq {
+    pub page: i32,
+    pub page_size: i32,
+    pub post_id: Option<i64>,
+    pub status: Option<ScheduleStatus>,
+}
+
+impl Validate for SchedulePageReq {
+    type Item = SchedulePageReq;
+
+    type Error = PageValidationError;
+
+    fn validate(self) -> Result<Self::Item, Self::Error> {
+        if self.page <= 0 {
+            return Err(PageValidationError {
+                field: "page",
+                msg: "page must be greater than 0",
+            });
+        };
+        if self.page_size <= 0 {
+            return Err(PageValidationError {
+                field: "page_size",
+                msg: "page_size must be greater than 0",
+            });
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QueryScheduleError {
+    #[error("Database Error")]
+    Database,
+    #[error("Schedule not found")]
+    NotFound,
+}
+
+#[derive(Debug, Error)]
+pub enum CreateScheduleError {
+    #[error("Database Error")]
+    Database,
+    #[error("Post version not found")]
+    PostNotFound,
+    #[error("Invalid targets: {0}")]
+    Serialize(String),
+}
+
+#[derive(Debug, Error)]
+pub enum UpdateScheduleError {
+    #[error("Database Error")]
+    Database,
+    #[error("Schedule not found")]
+    NotFound,
+    #[error("Post version not found")]
+    PostNotFound,
+    #[error("Schedule is running or published")]
+    Locked,
+    #[error("Invalid targets: {0}")]
+    Serialize(String),
+}
+
+#[derive(Debug, Error)]
+pub enum DeleteScheduleError {
+    #[error("Database Error")]
+    Database,
+    #[error("Schedule not found")]
+    NotFound,
+    #[error("Schedule is running")]
+    Running,
+}
+
+impl From<diesel::result::Error> for QueryScheduleError {
+    fn from(item: diesel::result::Error) -> Self {
+        match item {
+            diesel::result::Error::NotFound => QueryScheduleError::NotFound,
+            _ => QueryScheduleError::Database,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for CreateScheduleError {
+    fn from(_: diesel::result::Error) -> Self {
+        CreateScheduleError::Database
+    }
+}
+
+impl From<serde_json::Error> for CreateScheduleError {
+    fn from(item: serde_json::Error) -> Self {
+        CreateScheduleError::Serialize(item.to_string())
+    }
+}
+
+impl From<diesel::result::Error> for UpdateScheduleError {
+    fn from(item: diesel::result::Error) -> Self {
+        match item {
+            diesel::result::Error::NotFound => UpdateScheduleError::NotFound,
+            _ => UpdateScheduleError::Database,
+        }
+    }
+}
+
+impl From<serde_json::Error> for UpdateScheduleError {
+    fn from(item: serde_json::Error) -> Self {
+        UpdateScheduleError::Serialize(item.to_string())
+    }
+}
+
+impl From<diesel::result::Error> for DeleteScheduleError {
+    fn from(item: diesel::result::Error) -> Self {
+        match item {
+            diesel::result::Error::NotFound => DeleteScheduleError::NotFound,
+            _ => DeleteScheduleError::Database,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! rules which transform the content of a post for a target platform.
+
+use serde::{Deserialize, Serialize};
+
+/// a rule of the pipeline of a target, rules are applied in order to the content before
+/// it's pushed, and reverted in reverse order after it's pulled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TransformRule {
+    /// Obsidian callouts like `> [!note] Title`
+    Callout { style: CalloutStyle },
+    /// Obsidian wiki-links like `[[Page|text]]` to `[text](prefix + Page + suffix)`,
+    /// the links under `prefix` are turned back into wiki-links when they're pulled
+    #[serde(rename_all = "camelCase")]
+    WikiLink {
+        prefix: String,
+        #[serde(default)]
+        suffix: String,
+    },
+    /// replace the prefix `from` of the destinations of images with `to`
+    ImagePath { from: String, to: String },
+    /// replace the matches of a regular expression, `replacement` may refer to the groups
+    /// like `$1`. The content pulled is only changed if `reverse` is given, it's matched
+    /// in the same scope.
+    Regex {
+        pattern: String,
+        replacement: String,
+        #[serde(default)]
+        scope: RegexScope,
+        reverse: Option<RegexReplace>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CalloutStyle {
+    /// `{% note type %}` of the hexo themes like NexT
+    HexoTag,
+    /// `<div class="callout callout-type">`
+    Html,
+}
+
+/// the pattern and the replacement used when the content is pulled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegexReplace {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+/// which parts of the markdown the regular expression is matched in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RegexScope {
+    /// the whole content
+    #[default]
+    Content,
+    /// text of the paragraphs, headings and so on, code and html are left as they are
+    Text,
+}
+
+#[cfg(test)]
+mod transforms_test {
+    use super::*;
+
+    #[test]
+    fn deserialize_test() {
+        let rules: Vec<TransformRule> = serde_json::from_str(
+            r#"[
+                {"kind": "callout", "style": "hexoTag"},
+                {"kind": "wikiLink", "prefix": "/posts/"},
+                {"kind": "regex", "pattern": "==(.+?)==", "replacement": "<mark>$1</mark>", "scope": "text"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                TransformRule::Callout {
+                    style: CalloutStyle::HexoTag
+                },
+                TransformRule::WikiLink {
+                    prefix: "/posts/".to_string(),
+                    suffix: String::new(),
+                },
+                TransformRule::Regex {
+                    pattern: "==(.+?)==".to_string(),
+                    replacement: "<mark>$1</mark>".to_string(),
+                    scope: RegexScope::Text,
+                    reverse: None,
+                },
+            ]
+        );
+    }
+}
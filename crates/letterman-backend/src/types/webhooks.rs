@@ -0,0 +1,240 @@
+use chrono::NaiveDateTime;
+use diesel::{deserialize::Queryable, prelude::Insertable, Selectable};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::utils;
+
+use super::{serialize_as_string, serialize_naive_date_time, Platform};
+
+/// payload of the github `push` event, only the fields used by letterman
+#[derive(Debug, Deserialize)]
+pub struct GithubPushEvent {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// the commit after the push
+    pub after: String,
+    pub repository: GithubRepository,
+    #[serde(default)]
+    pub commits: Vec<GithubCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubRepository {
+    /// `owner/repo`
+    pub full_name: String,
+    pub default_branch: String,
+    /// url of the repository in the browser, its host tells which github sent the event
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubCommit {
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+impl GithubPushEvent {
+    /// articles are only synchronized with the default branch
+    pub fn is_default_branch(&self) -> bool {
+        self.is_branch(None)
+    }
+
+    /// whether the push is to the branch, the default branch if it's `None`
+    pub fn is_branch(&self, branch: Option<&str>) -> bool {
+        let branch = branch.unwrap_or(&self.repository.default_branch);
+        self.git_ref == format!("refs/heads/{}", branch)
+    }
+
+    /// whether the event is sent by the github whose api is `api_url`,
+    /// `api.github.com` serves `github.com` and an enterprise server serves its own host
+    pub fn is_sent_by(&self, api_url: &str) -> bool {
+        let host = |url: &str| {
+            reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| host.to_string()))
+        };
+        match (host(&self.repository.html_url), host(api_url)) {
+            (Some(html_host), Some(api_host)) => {
+                html_host == api_host.strip_prefix("api.").unwrap_or(&api_host)
+            }
+            _ => false,
+        }
+    }
+
+    /// files which exist and are changed after the push, in order of the first change
+    pub fn changed_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = vec![];
+        for commit in &self.commits {
+            for path in commit.added.iter().chain(commit.modified.iter()) {
+                if !paths.contains(path) {
+                    paths.push(path.clone());
+                }
+            }
+            paths.retain(|path| !commit.removed.contains(path));
+        }
+        paths
+    }
+}
+
+/// the remote article is changed while the local post has unsynchronized changes,
+/// it's resolved by synchronizing the post.
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = crate::schema::t_post_divergence)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct PostDivergence {
+    pub id: i64,
+    pub post_id: i64,
+    pub platform: String,
+    pub repository: String,
+    pub path: String,
+    pub local_version: String,
+    pub remote_commit: String,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::t_post_divergence)]
+#[diesel(check_for_backend(diesel::mysql::Mysql))]
+pub struct InsertablePostDivergence {
+    pub id: i64,
+    pub post_id: i64,
+    pub platform: String,
+    pub repository: String,
+    pub path: String,
+    pub local_version: String,
+    pub remote_commit: String,
+    pub create_time: NaiveDateTime,
+    pub update_time: NaiveDateTime,
+}
+
+impl InsertablePostDivergence {
+    pub fn new(
+        post_id: i64,
+        platform: Platform,
+        repository: String,
+        path: String,
+        local_version: String,
+        remote_commit: String,
+    ) -> InsertablePostDivergence {
+        let now = utils::time_utils::now();
+        InsertablePostDivergence {
+            id: utils::snowflake::next_id(),
+            post_id,
+            platform: platform.to_string(),
+            repository,
+            path,
+            local_version,
+            remote_commit,
+            create_time: now,
+            update_time: now,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostDivergenceVO {
+    #[serde(serialize_with = "serialize_as_string")]
+    id: i64,
+    #[serde(serialize_with = "serialize_as_string")]
+    post_id: i64,
+    platform: String,
+    repository: String,
+    path: String,
+    local_version: String,
+    remote_commit: String,
+    #[serde(serialize_with = "serialize_naive_date_time")]
+    create_time: NaiveDateTime,
+}
+
+impl From<PostDivergence> for PostDivergenceVO {
+    fn from(divergence: PostDivergence) -> Self {
+        PostDivergenceVO {
+            id: divergence.id,
+            post_id: divergence.post_id,
+            platform: divergence.platform,
+            repository: divergence.repository,
+            path: divergence.path,
+            local_version: divergence.local_version,
+            remote_commit: divergence.remote_commit,
+            create_time: divergence.create_time,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DivergenceError {
+    #[error("Database Error")]
+    Database,
+}
+
+impl From<diesel::result::Error> for DivergenceError {
+    fn from(_: diesel::result::Error) -> Self {
+        DivergenceError::Database
+    }
+}
+
+#[cfg(test)]
+mod webhooks_test {
+    use super::*;
+
+    #[test]
+    fn changed_paths_test() {
+        let event: GithubPushEvent = serde_json::from_str(
+            r#"{
+                "ref": "refs/heads/main",
+                "after": "abc",
+                "repository": {"full_name": "owner/blog", "default_branch": "main"},
+                "commits": [
+                    {"added": ["a.md"], "modified": ["b.md"], "removed": []},
+                    {"added": [], "modified": ["a.md", "c.md"], "removed": ["b.md"]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(event.is_default_branch());
+        assert_eq!(event.changed_paths(), vec!["a.md", "c.md"]);
+    }
+
+    #[test]
+    fn target_of_event_test() {
+        let event: GithubPushEvent = serde_json::from_str(
+            r#"{
+                "ref": "refs/heads/posts",
+                "after": "abc",
+                "repository": {
+                    "full_name": "owner/blog",
+                    "default_branch": "main",
+                    "html_url": "https://github.com/owner/blog"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(!event.is_default_branch());
+        assert!(event.is_branch(Some("posts")));
+        assert!(event.is_sent_by("https://api.github.com"));
+        assert!(!event.is_sent_by("https://ghe.example.com/api/v3"));
+
+        let event: GithubPushEvent = serde_json::from_str(
+            r#"{
+                "ref": "refs/heads/main",
+                "after": "abc",
+                "repository": {
+                    "full_name": "owner/blog",
+                    "default_branch": "main",
+                    "html_url": "https://ghe.example.com/owner/blog"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(event.is_sent_by("https://ghe.example.com/api/v3"));
+        assert!(!event.is_sent_by("https://api.github.com"));
+    }
+}
@@ -45,13 +45,102 @@ pub mod mongo_utils {
     }
 }
 
-pub mod sha_utils {
+pub mod signature_utils {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    /// verify the `sha256=<hex digest>` signature of the body, which is signed with the secret
+    pub fn verify_hmac_sha256(secret: &str, body: &[u8], signature: &str) -> bool {
+        let signature = match signature
+            .strip_prefix("sha256=")
+            .and_then(|hex_digest| hex::decode(hex_digest).ok())
+        {
+            Some(signature) => signature,
+            None => return false,
+        };
+        let mut mac = match Hmac::<Sha256>::new_from_slice(secret.as_bytes()) {
+            Ok(mac) => mac,
+            Err(_) => return false,
+        };
+        mac.update(body);
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    #[test]
+    fn verify_hmac_sha256_test() {
+        // the example of github webhook documents
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert!(verify_hmac_sha256(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            signature
+        ));
+        assert!(!verify_hmac_sha256("secret", b"Hello, World!", signature));
+        assert!(!verify_hmac_sha256(
+            "It's a Secret to Everybody",
+            b"Hello, World!",
+            "757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        ));
+    }
+}
 
-    use std::collections::HashMap;
+pub mod cipher_utils {
+    use aes_gcm::{
+        aead::{Aead, AeadCore, KeyInit, OsRng},
+        Aes256Gcm, Nonce,
+    };
+    use base64::{prelude::BASE64_STANDARD, Engine};
+
+    const NONCE_LEN: usize = 12;
+
+    /// the 32 bytes key of `LETTERMAN_SECRET_KEY` env in hex, `None` if it's absent or invalid
+    pub fn secret_key() -> Option<Vec<u8>> {
+        let key = hex::decode(std::env::var("LETTERMAN_SECRET_KEY").ok()?).ok()?;
+        (key.len() == 32).then_some(key)
+    }
+
+    /// encrypt with AES-256-GCM, the result is the base64 of the random nonce and the ciphertext
+    pub fn encrypt(key: &[u8], plaintext: &str) -> Option<String> {
+        let cipher = Aes256Gcm::new_from_slice(key).ok()?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend(cipher.encrypt(&nonce, plaintext.as_bytes()).ok()?);
+        Some(BASE64_STANDARD.encode(encrypted))
+    }
 
+    /// `None` if the key is wrong or the ciphertext is tampered with
+    pub fn decrypt(key: &[u8], encrypted: &str) -> Option<String> {
+        let cipher = Aes256Gcm::new_from_slice(key).ok()?;
+        let encrypted = BASE64_STANDARD.decode(encrypted).ok()?;
+        if encrypted.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LEN);
+        let plaintext = cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+
+    #[test]
+    fn encrypt_test() {
+        let key = [7u8; 32];
+        let encrypted = encrypt(&key, "ghp_token").unwrap();
+        assert_ne!(encrypted, encrypt(&key, "ghp_token").unwrap());
+        assert_eq!(decrypt(&key, &encrypted).unwrap(), "ghp_token");
+        assert!(decrypt(&[8u8; 32], &encrypted).is_none());
+        assert!(decrypt(&key, "AAAA").is_none());
+        assert!(encrypt(&[7u8; 16], "ghp_token").is_none());
+    }
+}
+
+pub mod sha_utils {
+
+    #[cfg(test)]
     use base64::Engine;
+    use serde_json::Value;
     use sha256::digest;
 
+    use crate::types::posts::Metadata;
+
     pub fn sha(input: &str) -> String {
         digest(input)
     }
@@ -61,10 +150,16 @@ pub mod sha_utils {
         sha(&input)
     }
 
-    pub fn sha_post2(title: &str, metadata: &HashMap<String, String>, content: &str) -> String {
+    /// the keys are sorted, strings are hashed as they are and other values as json
+    pub fn sha_post2(title: &str, metadata: &Metadata, content: &str) -> String {
+        let mut metadata: Vec<_> = metadata.iter().collect();
+        metadata.sort_by_key(|(k, _)| *k);
         let metadata = metadata
-            .iter()
-            .map(|(k, v)| format!("{}:{}", k, v))
+            .into_iter()
+            .map(|(k, v)| match v {
+                Value::String(v) => format!("{}:{}", k, v),
+                v => format!("{}:{}", k, v),
+            })
             .fold("---\n".to_string(), |a, s| format!("{}\n{}", a, s))
             + "\n---";
         let input = format!("{}\n{}\n{}", metadata, title, content);
@@ -73,14 +168,23 @@ pub mod sha_utils {
 
     #[test]
     fn sha_post_test() {
-        let mut map = HashMap::new();
-        map.insert("a".to_string(), "b".to_string());
+        let mut map = Metadata::new();
+        map.insert("a".to_string(), Value::from("b"));
         let title = "aaa";
         let content = "test";
         let sha1 = sha_post2(title, &map, content);
 
         let sha2 = sha_post2(title, &map, content);
-        assert_eq!(sha1, sha2)
+        assert_eq!(sha1, sha2);
+
+        map.insert("tags".to_string(), Value::from(vec!["x", "y"]));
+        map.insert("top".to_string(), Value::from(true));
+        let sha3 = sha_post2(title, &map, content);
+        let mut entries: Vec<_> = map.clone().into_iter().collect();
+        entries.reverse();
+        let reversed: Metadata = entries.into_iter().collect();
+        assert_eq!(sha3, sha_post2(title, &reversed, content));
+        assert_ne!(sha1, sha3);
     }
 
     #[test]